@@ -0,0 +1,395 @@
+use std::fmt;
+
+use crate::calc::{number::Number, token::Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Factorial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// Comparisons and the `and` and `or` joining them, see [`ExprKind::Condition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionOp {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+pub const UNARY_PRECEDENCE: u8 = 3;
+/// Implicit multiplication by a unit symbol binds tighter than `*` and `/`.
+pub const UNIT_PRECEDENCE: u8 = 3;
+pub const POSTFIX_PRECEDENCE: u8 = 5;
+
+impl ConditionOp {
+    /// Precedence among conditions, all of them bind looser than any operator.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            ConditionOp::Or => 0,
+            ConditionOp::And => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        !matches!(self, ConditionOp::And | ConditionOp::Or)
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ConditionOp::Less => "<",
+            ConditionOp::LessEqual => "<=",
+            ConditionOp::Greater => ">",
+            ConditionOp::GreaterEqual => ">=",
+            ConditionOp::And => "and",
+            ConditionOp::Or => "or",
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::Add | BinaryOp::Sub => 1,
+            BinaryOp::Mul | BinaryOp::Div => 2,
+            BinaryOp::Pow => 4,
+        }
+    }
+
+    pub fn is_right_assoc(&self) -> bool {
+        matches!(self, BinaryOp::Pow)
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Number(Number),
+    Ident(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// `[1, 2, 3]`, a matrix is a vector of equally long rows
+    Vector(Vec<Expr>),
+    Equation(Box<Expr>, Box<Expr>),
+    /// `x < 1` or `a and b`, evaluates to 1 when it holds and 0 otherwise
+    Condition(ConditionOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn number(val: f64) -> Self {
+        Self::new(ExprKind::Number(Number::from_f64(val)), Span::default())
+    }
+
+    pub fn exact(val: Number) -> Self {
+        Self::new(ExprKind::Number(val), Span::default())
+    }
+
+    pub fn ident(name: &str) -> Self {
+        Self::new(ExprKind::Ident(name.to_owned()), Span::default())
+    }
+
+    pub fn unary(op: UnaryOp, operand: Expr) -> Self {
+        Self::new(ExprKind::Unary(op, Box::new(operand)), Span::default())
+    }
+
+    pub fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Self {
+        Self::new(
+            ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+            Span::default(),
+        )
+    }
+
+    pub fn call(name: &str, args: Vec<Expr>) -> Self {
+        Self::new(ExprKind::Call(name.to_owned(), args), Span::default())
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match &self.kind {
+            ExprKind::Number(val) => Some(val.to_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_exact(&self) -> Option<&Number> {
+        match &self.kind {
+            ExprKind::Number(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn contains_ident(&self, name: &str) -> bool {
+        let mut found = false;
+        self.visit(&mut |expr| {
+            if let ExprKind::Ident(ident) = &expr.kind {
+                found |= ident == name;
+            }
+        });
+        found
+    }
+
+    /// Names used as variables, function names of calls are not included.
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.visit(&mut |expr| {
+            if let ExprKind::Ident(name) = &expr.kind
+                && !names.contains(name)
+            {
+                names.push(name.clone());
+            }
+        });
+        names
+    }
+
+    pub fn visit(&self, func: &mut impl FnMut(&Expr)) {
+        func(self);
+        match &self.kind {
+            ExprKind::Number(_) | ExprKind::Ident(_) => {}
+            ExprKind::Unary(_, operand) => operand.visit(func),
+            ExprKind::Binary(_, lhs, rhs)
+            | ExprKind::Equation(lhs, rhs)
+            | ExprKind::Condition(_, lhs, rhs) => {
+                lhs.visit(func);
+                rhs.visit(func);
+            }
+            ExprKind::Call(_, args) | ExprKind::Vector(args) => {
+                args.iter().for_each(|arg| arg.visit(func))
+            }
+        }
+    }
+
+    /// Replaces every identifier with a binding in `bindings`.
+    pub fn substitute(&self, bindings: &[(String, Expr)]) -> Expr {
+        let kind = match &self.kind {
+            ExprKind::Ident(name) => {
+                if let Some((_, val)) = bindings.iter().find(|(bound, _)| bound == name) {
+                    return val.clone();
+                }
+                ExprKind::Ident(name.clone())
+            }
+            ExprKind::Number(val) => ExprKind::Number(val.clone()),
+            ExprKind::Unary(op, operand) => {
+                ExprKind::Unary(*op, Box::new(operand.substitute(bindings)))
+            }
+            ExprKind::Binary(op, lhs, rhs) => ExprKind::Binary(
+                *op,
+                Box::new(lhs.substitute(bindings)),
+                Box::new(rhs.substitute(bindings)),
+            ),
+            ExprKind::Call(name, args) => ExprKind::Call(
+                name.clone(),
+                args.iter().map(|arg| arg.substitute(bindings)).collect(),
+            ),
+            ExprKind::Vector(items) => {
+                ExprKind::Vector(items.iter().map(|item| item.substitute(bindings)).collect())
+            }
+            ExprKind::Equation(lhs, rhs) => ExprKind::Equation(
+                Box::new(lhs.substitute(bindings)),
+                Box::new(rhs.substitute(bindings)),
+            ),
+            ExprKind::Condition(op, lhs, rhs) => ExprKind::Condition(
+                *op,
+                Box::new(lhs.substitute(bindings)),
+                Box::new(rhs.substitute(bindings)),
+            ),
+        };
+        Expr::new(kind, self.span)
+    }
+
+    pub fn precedence(&self) -> u8 {
+        match &self.kind {
+            ExprKind::Number(val) if val.is_negative() => UNARY_PRECEDENCE,
+            // A fraction is printed as `1/3`
+            ExprKind::Number(Number::Rational(val)) if !val.is_integer() => {
+                BinaryOp::Div.precedence()
+            }
+            ExprKind::Call(name, _) if name == "to" => 0,
+            ExprKind::Number(_)
+            | ExprKind::Ident(_)
+            | ExprKind::Call(_, _)
+            | ExprKind::Vector(_) => u8::MAX,
+            ExprKind::Unary(UnaryOp::Neg, _) => UNARY_PRECEDENCE,
+            ExprKind::Unary(UnaryOp::Factorial, _) => POSTFIX_PRECEDENCE,
+            ExprKind::Binary(op, _, _) => op.precedence(),
+            ExprKind::Equation(_, _) | ExprKind::Condition(_, _, _) => 0,
+        }
+    }
+}
+
+/// Conditions are parenthesised below `min_precedence` among them, other operands
+/// only when they bind as loosely as a conversion.
+fn write_condition_operand(
+    f: &mut fmt::Formatter<'_>,
+    expr: &Expr,
+    min_precedence: u8,
+) -> fmt::Result {
+    let parenthesised = match &expr.kind {
+        ExprKind::Condition(op, _, _) => op.precedence() < min_precedence,
+        _ => expr.precedence() == 0,
+    };
+    if parenthesised {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, min_precedence: u8) -> fmt::Result {
+    if expr.precedence() < min_precedence {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Number(val) => write!(f, "{}", val),
+            ExprKind::Ident(name) => write!(f, "{}", name),
+            ExprKind::Unary(UnaryOp::Neg, operand) => {
+                write!(f, "-")?;
+                write_operand(f, operand, UNARY_PRECEDENCE + 1)
+            }
+            ExprKind::Unary(UnaryOp::Factorial, operand) => {
+                write_operand(f, operand, POSTFIX_PRECEDENCE + 1)?;
+                write!(f, "!")
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let precedence = op.precedence();
+                let (lhs_min, rhs_min) = if op.is_right_assoc() {
+                    (precedence + 1, precedence)
+                } else {
+                    (precedence, precedence + 1)
+                };
+                write_operand(f, lhs, lhs_min)?;
+                match op {
+                    BinaryOp::Pow => write!(f, "^")?,
+                    _ => write!(f, " {} ", op.symbol())?,
+                }
+                write_operand(f, rhs, rhs_min)
+            }
+            ExprKind::Call(name, args) if name == "to" && args.len() == 2 => {
+                write!(f, "{} to {}", args[0], args[1])
+            }
+            ExprKind::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            ExprKind::Vector(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            ExprKind::Equation(lhs, rhs) => write!(f, "{} = {}", lhs, rhs),
+            ExprKind::Condition(op, lhs, rhs) => {
+                // A chain like `0 < x < 1` parses differently, so comparisons of
+                // comparisons keep their parentheses
+                let precedence = op.precedence();
+                let lhs_min = if op.is_comparison() {
+                    precedence + 1
+                } else {
+                    precedence
+                };
+                write_condition_operand(f, lhs, lhs_min)?;
+                write!(f, " {} ", op.symbol())?;
+                write_condition_operand(f, rhs, precedence + 1)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Expr(Expr),
+    /// `name = value`
+    Assign(String, Expr),
+    /// `name(params) = body`
+    Function {
+        name: String,
+        params: Vec<String>,
+        body: Expr,
+    },
+}
+
+impl Statement {
+    pub fn from_expr(expr: Expr) -> Self {
+        if let ExprKind::Equation(lhs, rhs) = &expr.kind {
+            match &lhs.kind {
+                ExprKind::Ident(name) => return Statement::Assign(name.clone(), *rhs.clone()),
+                ExprKind::Call(name, args) => {
+                    let params: Option<Vec<String>> = args
+                        .iter()
+                        .map(|arg| match &arg.kind {
+                            ExprKind::Ident(param) => Some(param.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    if let Some(params) = params {
+                        return Statement::Function {
+                            name: name.clone(),
+                            params,
+                            body: *rhs.clone(),
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        Statement::Expr(expr)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Expr(expr) => write!(f, "{}", expr),
+            Statement::Assign(name, value) => write!(f, "{} = {}", name, value),
+            Statement::Function { name, params, body } => {
+                write!(f, "{}({}) = {}", name, params.join(", "), body)
+            }
+        }
+    }
+}
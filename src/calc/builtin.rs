@@ -0,0 +1,485 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use once_cell::sync::Lazy;
+
+use crate::calc::{complex::Complex, matrix, number::Number, value::Value};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exact(n) => count == *n,
+            Arity::AtLeast(n) => count >= *n,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum BuiltinFn {
+    Real2(fn(f64, f64) -> f64),
+    /// Real function that continues to the complex plane, used for complex
+    /// arguments and for real arguments outside the real domain like `ln(-1)`
+    Holomorphic(fn(f64) -> f64, fn(Complex64) -> Complex64),
+    Values(fn(&[Value]) -> Result<Value, String>),
+}
+
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub func: BuiltinFn,
+}
+
+impl Builtin {
+    pub fn call(&self, args: &[Value]) -> Result<Value, String> {
+        if !self.arity.accepts(args.len()) {
+            return Err(match self.arity {
+                Arity::Exact(n) => {
+                    format!("{} takes {} argument(s), got {}", self.name, n, args.len())
+                }
+                Arity::AtLeast(n) => {
+                    format!(
+                        "{} takes at least {} argument(s), got {}",
+                        self.name,
+                        n,
+                        args.len()
+                    )
+                }
+            });
+        }
+        let result = match self.func {
+            BuiltinFn::Real2(func) => {
+                let x = number_arg(self.name, &args[0])?;
+                let y = number_arg(self.name, &args[1])?;
+                real_result(func(x.to_f64(), y.to_f64()), &[x, y])
+            }
+            BuiltinFn::Holomorphic(real, complex) => match &args[0] {
+                Value::Number(x) => {
+                    let result = real(x.to_f64());
+                    if result.is_nan() && !x.is_nan() {
+                        let z = Complex64::new(x.to_f64(), 0.0);
+                        Value::complex(Complex::from_c64(complex(z)))
+                    } else {
+                        real_result(result, &[x])
+                    }
+                }
+                Value::Complex(z) => Value::complex(Complex::from_c64(complex(z.to_c64()))),
+                val => {
+                    return Err(format!(
+                        "{} expects a number, got {}",
+                        self.name,
+                        val.type_name()
+                    ));
+                }
+            },
+            BuiltinFn::Values(func) => func(args)?,
+        };
+        let is_nan = match &result {
+            Value::Number(val) => val.is_nan(),
+            Value::Complex(val) => val.is_nan(),
+            Value::Quantity(val) => val.value.is_nan(),
+            Value::Vector(_) | Value::Matrix(_) | Value::Expression(_) => false,
+        };
+        if is_nan {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            return Err(format!(
+                "{} is undefined for {}",
+                self.name,
+                args.join(", ")
+            ));
+        }
+        Ok(result)
+    }
+}
+
+/// Floating point results are inexact, except for `sin(0)`, `cos(0)`, `ln(1)`
+/// and the like where an exact argument gives -1, 0 or 1.
+fn real_result(result: f64, args: &[&Number]) -> Value {
+    if args.iter().all(|arg| arg.is_exact()) && [-1.0, 0.0, 1.0].contains(&result) {
+        Value::Number(Number::from_f64(result))
+    } else {
+        Value::from(result)
+    }
+}
+
+pub fn number_arg<'a>(name: &str, val: &'a Value) -> Result<&'a Number, String> {
+    val.as_number()
+        .ok_or_else(|| format!("{} expects a number, got {}", name, val.type_name()))
+}
+
+pub fn complex_arg(name: &str, val: &Value) -> Result<Complex, String> {
+    val.to_complex()
+        .ok_or_else(|| format!("{} expects a number, got {}", name, val.type_name()))
+}
+
+pub fn real_arg(name: &str, val: &Value) -> Result<f64, String> {
+    Ok(number_arg(name, val)?.to_f64())
+}
+
+pub fn integer_arg(name: &str, val: &Value) -> Result<BigInt, String> {
+    let val = number_arg(name, val)?;
+    val.to_integer()
+        .ok_or_else(|| format!("{} expects an integer, got {}", name, val))
+}
+
+/// Lanczos approximation, g = 7.
+pub fn gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        return std::f64::consts::PI / ((std::f64::consts::PI * x).sin() * gamma(1.0 - x));
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let mut sum = COEFFICIENTS[0];
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    (2.0 * std::f64::consts::PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * sum
+}
+
+pub fn factorial(x: f64) -> f64 {
+    if x < 0.0 && x.fract() == 0.0 {
+        return f64::NAN;
+    }
+    // 171! is past the largest f64
+    if x > 170.0 {
+        return f64::INFINITY;
+    }
+    if x.fract() != 0.0 {
+        return gamma(x + 1.0);
+    }
+    (2..=(x as u64)).fold(1.0, |acc, n| acc * n as f64)
+}
+
+pub fn binomial(n: f64, k: f64) -> f64 {
+    if n.fract() != 0.0 || k.fract() != 0.0 {
+        return factorial(n) / (factorial(k) * factorial(n - k));
+    }
+    if k < 0.0 || k > n {
+        return 0.0;
+    }
+    // Each factor is at least 2 once k <= n / 2, so the product overflows in at
+    // most about a thousand steps
+    let k = k.min(n - k) as u64;
+    let mut acc = 1.0f64;
+    for i in 1..=k {
+        acc *= (n - k as f64 + i as f64) / i as f64;
+        if acc.is_infinite() {
+            break;
+        }
+    }
+    acc.round()
+}
+
+/// Exact `n (n - 1) ... (n - k + 1)` for small non-negative integers.
+fn falling_factorial(n: &Number, k: &Number) -> Option<BigInt> {
+    if !n.is_exact() || !k.is_exact() {
+        return None;
+    }
+    let n = n.to_integer()?;
+    let k = k.to_integer()?.to_u64().filter(|k| *k <= 20_000)?;
+    if n.is_negative() {
+        return None;
+    }
+    let mut result = BigInt::one();
+    for i in 0..k {
+        result *= &n - i;
+    }
+    Some(result)
+}
+
+fn ncr(args: &[Value]) -> Result<Value, String> {
+    let n = number_arg("nCr", &args[0])?;
+    let k = number_arg("nCr", &args[1])?;
+    if let Some(numer) = falling_factorial(n, k) {
+        let denom = k.factorial()?;
+        return Ok(Value::Number(Number::from_integer(numer).div(&denom)?));
+    }
+    Ok(Value::from(binomial(n.to_f64(), k.to_f64())))
+}
+
+fn npr(args: &[Value]) -> Result<Value, String> {
+    let n = number_arg("nPr", &args[0])?;
+    let k = number_arg("nPr", &args[1])?;
+    if let Some(result) = falling_factorial(n, k) {
+        return Ok(Value::Number(Number::from_integer(result)));
+    }
+    let (n, k) = (n.to_f64(), k.to_f64());
+    Ok(Value::from(binomial(n, k) * factorial(k)))
+}
+
+fn number1(args: &[Value], name: &str, func: fn(&Number) -> Number) -> Result<Value, String> {
+    Ok(Value::Number(func(number_arg(name, &args[0])?)))
+}
+
+fn extremum(args: &[Value], name: &str, ordering: Ordering) -> Result<Value, String> {
+    let mut result = number_arg(name, &args[0])?;
+    for arg in &args[1..] {
+        let val = number_arg(name, arg)?;
+        if val.partial_cmp(result) == Some(ordering) {
+            result = val;
+        }
+    }
+    Ok(Value::Number(result.clone()))
+}
+
+fn min(args: &[Value]) -> Result<Value, String> {
+    extremum(args, "min", Ordering::Less)
+}
+
+fn max(args: &[Value]) -> Result<Value, String> {
+    extremum(args, "max", Ordering::Greater)
+}
+
+fn gcd(args: &[Value]) -> Result<Value, String> {
+    let mut result = BigInt::zero();
+    for arg in args {
+        result = result.gcd(&integer_arg("gcd", arg)?);
+    }
+    Ok(Value::Number(Number::from_integer(result)))
+}
+
+fn lcm(args: &[Value]) -> Result<Value, String> {
+    let mut result = BigInt::one();
+    for arg in args {
+        result = result.lcm(&integer_arg("lcm", arg)?);
+    }
+    Ok(Value::Number(Number::from_integer(result)))
+}
+
+fn log(args: &[Value]) -> Result<Value, String> {
+    let (base, x) = match args {
+        [x] => (Complex64::new(10.0, 0.0), x),
+        [base, x] => (complex_arg("log", base)?.to_c64(), x),
+        _ => return Err(format!("log takes 1 or 2 arguments, got {}", args.len())),
+    };
+    let x = complex_arg("log", x)?.to_c64();
+    Ok(Value::complex(Complex::from_c64(x.ln() / base.ln())))
+}
+
+fn sqrt(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::complex(complex_arg("sqrt", &args[0])?.sqrt(None)))
+}
+
+fn abs(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(complex_arg("abs", &args[0])?.abs()))
+}
+
+fn re(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(complex_arg("re", &args[0])?.re))
+}
+
+fn im(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(complex_arg("im", &args[0])?.im))
+}
+
+fn arg(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(complex_arg("arg", &args[0])?.arg()))
+}
+
+fn conj(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::complex(complex_arg("conj", &args[0])?.conj()))
+}
+
+fn round(args: &[Value]) -> Result<Value, String> {
+    match args {
+        [x] => Ok(Value::Number(number_arg("round", x)?.round())),
+        [x, digits] => {
+            let x = number_arg("round", x)?;
+            let digits = integer_arg("round", digits)?
+                .to_i64()
+                .filter(|digits| digits.abs() <= 1000)
+                .ok_or("round expects at most 1000 digits")?;
+            let scale = Number::from_integer(10).pow(&Number::from_integer(digits));
+            Ok(Value::Number(x.mul(&scale).round().div(&scale)?))
+        }
+        _ => Err(format!("round takes 1 or 2 arguments, got {}", args.len())),
+    }
+}
+
+fn sign(args: &[Value]) -> Result<Value, String> {
+    let x = number_arg("sign", &args[0])?;
+    Ok(Value::Number(match x {
+        Number::Float(val) if val.is_nan() => Number::Float(f64::NAN),
+        _ if x.is_zero() => Number::zero(),
+        _ if x.is_negative() => Number::from_integer(-1),
+        _ => Number::one(),
+    }))
+}
+
+fn exact_factorial(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(
+        number_arg("factorial", &args[0])?.factorial()?,
+    ))
+}
+
+fn exact_gamma(args: &[Value]) -> Result<Value, String> {
+    let x = number_arg("gamma", &args[0])?;
+    if x.is_exact() && x.is_integer() && !x.is_negative() && !x.is_zero() {
+        return Ok(Value::Number(x.sub(&Number::one()).factorial()?));
+    }
+    Ok(Value::from(gamma(x.to_f64())))
+}
+
+fn modulo(args: &[Value]) -> Result<Value, String> {
+    let a = number_arg("mod", &args[0])?;
+    let b = number_arg("mod", &args[1])?;
+    Ok(Value::Number(a.rem_euclid(b)?))
+}
+
+fn real2(name: &'static str, func: fn(f64, f64) -> f64) -> Builtin {
+    Builtin {
+        name,
+        arity: Arity::Exact(2),
+        func: BuiltinFn::Real2(func),
+    }
+}
+
+fn holomorphic(
+    name: &'static str,
+    real: fn(f64) -> f64,
+    complex: fn(Complex64) -> Complex64,
+) -> Builtin {
+    Builtin {
+        name,
+        arity: Arity::Exact(1),
+        func: BuiltinFn::Holomorphic(real, complex),
+    }
+}
+
+fn values(
+    name: &'static str,
+    arity: Arity,
+    func: fn(&[Value]) -> Result<Value, String>,
+) -> Builtin {
+    Builtin {
+        name,
+        arity,
+        func: BuiltinFn::Values(func),
+    }
+}
+
+static BUILTINS: Lazy<HashMap<&'static str, Builtin>> = Lazy::new(|| {
+    let builtins = [
+        holomorphic("sin", f64::sin, Complex64::sin),
+        holomorphic("cos", f64::cos, Complex64::cos),
+        holomorphic("tan", f64::tan, Complex64::tan),
+        holomorphic("cot", |x| 1.0 / x.tan(), |z| z.tan().inv()),
+        holomorphic("sec", |x| 1.0 / x.cos(), |z| z.cos().inv()),
+        holomorphic("csc", |x| 1.0 / x.sin(), |z| z.sin().inv()),
+        holomorphic("asin", f64::asin, Complex64::asin),
+        holomorphic("acos", f64::acos, Complex64::acos),
+        holomorphic("atan", f64::atan, Complex64::atan),
+        real2("atan2", f64::atan2),
+        holomorphic("sinh", f64::sinh, Complex64::sinh),
+        holomorphic("cosh", f64::cosh, Complex64::cosh),
+        holomorphic("tanh", f64::tanh, Complex64::tanh),
+        holomorphic("asinh", f64::asinh, Complex64::asinh),
+        holomorphic("acosh", f64::acosh, Complex64::acosh),
+        holomorphic("atanh", f64::atanh, Complex64::atanh),
+        holomorphic("exp", f64::exp, Complex64::exp),
+        holomorphic("ln", f64::ln, Complex64::ln),
+        values("log", Arity::AtLeast(1), log),
+        holomorphic("log2", f64::log2, |z| z.ln() / std::f64::consts::LN_2),
+        holomorphic("log10", f64::log10, Complex64::log10),
+        values("sqrt", Arity::Exact(1), sqrt),
+        values("cbrt", Arity::Exact(1), |args| {
+            number1(args, "cbrt", Number::cbrt)
+        }),
+        values("abs", Arity::Exact(1), abs),
+        values("re", Arity::Exact(1), re),
+        values("im", Arity::Exact(1), im),
+        values("arg", Arity::Exact(1), arg),
+        values("conj", Arity::Exact(1), conj),
+        values("sign", Arity::Exact(1), sign),
+        values("floor", Arity::Exact(1), |args| {
+            number1(args, "floor", Number::floor)
+        }),
+        values("ceil", Arity::Exact(1), |args| {
+            number1(args, "ceil", Number::ceil)
+        }),
+        values("round", Arity::AtLeast(1), round),
+        values("trunc", Arity::Exact(1), |args| {
+            number1(args, "trunc", Number::trunc)
+        }),
+        values("min", Arity::AtLeast(1), min),
+        values("max", Arity::AtLeast(1), max),
+        values("gcd", Arity::AtLeast(1), gcd),
+        values("lcm", Arity::AtLeast(1), lcm),
+        values("gamma", Arity::Exact(1), exact_gamma),
+        values("factorial", Arity::Exact(1), exact_factorial),
+        values("nCr", Arity::Exact(2), ncr),
+        values("binomial", Arity::Exact(2), ncr),
+        values("nPr", Arity::Exact(2), npr),
+        values("mod", Arity::Exact(2), modulo),
+        values("dot", Arity::Exact(2), matrix::dot),
+        values("cross", Arity::Exact(2), matrix::cross),
+        values("norm", Arity::Exact(1), matrix::norm),
+        values("transpose", Arity::Exact(1), matrix::transpose),
+        values("identity", Arity::Exact(1), matrix::identity),
+        values("det", Arity::Exact(1), matrix::det),
+        values("inv", Arity::Exact(1), matrix::inv),
+        values("rank", Arity::Exact(1), matrix::rank),
+        values("linsolve", Arity::Exact(2), matrix::linsolve),
+        values("eigvals", Arity::Exact(1), matrix::eigvals),
+        values("eigvecs", Arity::Exact(1), matrix::eigvecs),
+    ];
+    builtins.into_iter().map(|b| (b.name, b)).collect()
+});
+
+pub fn get_builtin(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.get(name)
+}
+
+pub fn constants() -> Vec<(&'static str, f64)> {
+    vec![
+        ("pi", std::f64::consts::PI),
+        ("π", std::f64::consts::PI),
+        ("tau", std::f64::consts::TAU),
+        ("e", std::f64::consts::E),
+        ("phi", 1.618_033_988_749_895),
+        ("inf", f64::INFINITY),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factorial_of_large_numbers() {
+        assert_eq!(factorial(5.0), 120.0);
+        assert_eq!(factorial(170.0), factorial(169.0) * 170.0);
+        assert_eq!(factorial(171.0), f64::INFINITY);
+        assert_eq!(factorial(1e10), f64::INFINITY);
+        assert_eq!(factorial(1e300), f64::INFINITY);
+        assert_eq!(factorial(200.5), f64::INFINITY);
+    }
+
+    #[test]
+    fn binomial_of_large_numbers() {
+        assert_eq!(binomial(10.0, 3.0), 120.0);
+        assert_eq!(binomial(1e9, 1.0), 1e9);
+        assert_eq!(binomial(1e9, 5e8), f64::INFINITY);
+        assert_eq!(binomial(1e300, 1e150), f64::INFINITY);
+    }
+}
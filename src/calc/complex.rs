@@ -0,0 +1,200 @@
+use std::fmt;
+
+use num_complex::Complex64;
+
+use crate::calc::number::Number;
+
+/// Integer powers above this are computed with floats.
+const MAX_EXACT_POWER: i64 = 4096;
+
+/// Complex number whose parts stay exact where possible, e.g. `(1 + 2i) / (3 - i)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complex {
+    pub re: Number,
+    pub im: Number,
+}
+
+impl Complex {
+    pub fn new(re: Number, im: Number) -> Self {
+        Self { re, im }
+    }
+
+    pub fn i() -> Self {
+        Self::new(Number::zero(), Number::one())
+    }
+
+    pub fn from_real(re: Number) -> Self {
+        Self::new(re, Number::zero())
+    }
+
+    pub fn from_c64(val: Complex64) -> Self {
+        Self::new(Number::Float(val.re), Number::Float(val.im))
+    }
+
+    pub fn to_c64(&self) -> Complex64 {
+        Complex64::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im.is_zero()
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.re.is_exact() && self.im.is_exact()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.re.is_nan() || self.im.is_nan()
+    }
+
+    pub fn add(&self, other: &Complex) -> Complex {
+        Complex::new(self.re.add(&other.re), self.im.add(&other.im))
+    }
+
+    pub fn sub(&self, other: &Complex) -> Complex {
+        Complex::new(self.re.sub(&other.re), self.im.sub(&other.im))
+    }
+
+    pub fn mul(&self, other: &Complex) -> Complex {
+        Complex::new(
+            self.re.mul(&other.re).sub(&self.im.mul(&other.im)),
+            self.re.mul(&other.im).add(&self.im.mul(&other.re)),
+        )
+    }
+
+    pub fn div(&self, other: &Complex) -> Result<Complex, String> {
+        let denom = other.norm_sqr();
+        if denom.is_zero() {
+            return Err("Division by zero".to_owned());
+        }
+        let numer = self.mul(&other.conj());
+        Ok(Complex::new(numer.re.div(&denom)?, numer.im.div(&denom)?))
+    }
+
+    pub fn neg(&self) -> Complex {
+        Complex::new(self.re.neg(), self.im.neg())
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.re.clone(), self.im.neg())
+    }
+
+    pub fn norm_sqr(&self) -> Number {
+        self.re.mul(&self.re).add(&self.im.mul(&self.im))
+    }
+
+    /// Modulus, exact for Pythagorean pairs like `3 + 4i`.
+    pub fn abs(&self) -> Number {
+        self.norm_sqr().sqrt(None)
+    }
+
+    /// Principal argument in `(-π, π]`.
+    pub fn arg(&self) -> Number {
+        if self.is_real() && !self.re.is_negative() {
+            return Number::zero();
+        }
+        Number::Float(self.im.to_f64().atan2(self.re.to_f64()))
+    }
+
+    pub fn powi(&self, exp: i64) -> Result<Complex, String> {
+        if exp.abs() > MAX_EXACT_POWER {
+            return Ok(Complex::from_c64(self.to_c64().powf(exp as f64)));
+        }
+        let mut result = Complex::from_real(Number::one());
+        let mut base = self.clone();
+        let mut n = exp.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(&base);
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base.mul(&base);
+            }
+        }
+        if exp < 0 {
+            return Complex::from_real(Number::one()).div(&result);
+        }
+        Ok(result)
+    }
+
+    /// Principal power `exp(w ln z)`, exact for integer exponents.
+    pub fn pow(&self, exp: &Complex) -> Result<Complex, String> {
+        if exp.is_real()
+            && exp.re.is_exact()
+            && let Some(n) = exp.re.to_i64()
+        {
+            return self.powi(n);
+        }
+        if self.is_zero() {
+            return if exp.re.is_negative() || exp.re.is_zero() {
+                Err(format!("0 ^ {} is undefined", exp))
+            } else {
+                Ok(Complex::from_real(Number::zero()))
+            };
+        }
+        Ok(Complex::from_c64(self.to_c64().powc(exp.to_c64())))
+    }
+
+    /// Principal square root, `sqrt(-4) = 2i` stays exact.
+    pub fn sqrt(&self, digits: Option<u32>) -> Complex {
+        if self.is_real() {
+            return if self.re.is_negative() {
+                Complex::new(Number::zero(), self.re.neg().sqrt(digits))
+            } else {
+                Complex::from_real(self.re.sqrt(digits))
+            };
+        }
+        Complex::from_c64(self.to_c64().sqrt())
+    }
+
+    pub fn map_parts(&self, func: impl Fn(&Number) -> Number) -> Complex {
+        Complex::new(func(&self.re), func(&self.im))
+    }
+}
+
+/// Imaginary coefficient without the `i`, `1` and `-1` are left out.
+fn format_imaginary(im: &Number) -> String {
+    let text = im.abs().to_string();
+    if text == "1" {
+        String::new()
+    } else if text.contains('/') {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let im = format_imaginary(&self.im);
+        if self.re.is_zero() {
+            let sign = if self.im.is_negative() { "-" } else { "" };
+            return write!(f, "{}{}i", sign, im);
+        }
+        let sign = if self.im.is_negative() { "-" } else { "+" };
+        write!(f, "{} {} {}i", self.re, sign, im)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_integer_powers() {
+        let two_i = Complex::new(Number::zero(), Number::from_integer(2));
+        let exact = two_i.powi(4).unwrap();
+        assert_eq!(exact, Complex::from_real(Number::from_integer(16)));
+
+        // 2^32 must not wrap around to 0 on the way to the float power
+        let huge = two_i.powi(1 << 32).unwrap().to_c64();
+        assert!(huge.norm().is_infinite());
+        let small = two_i.powi(-(1 << 32)).unwrap().to_c64();
+        assert_eq!(small.norm(), 0.0);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::calc::{
+    ast::{BinaryOp, Expr, ExprKind, UnaryOp},
+    eval::{Environment, UserFunction},
+    simplify::simplify,
+};
+
+fn add(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::binary(BinaryOp::Add, lhs, rhs)
+}
+
+fn sub(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::binary(BinaryOp::Sub, lhs, rhs)
+}
+
+fn mul(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::binary(BinaryOp::Mul, lhs, rhs)
+}
+
+fn div(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::binary(BinaryOp::Div, lhs, rhs)
+}
+
+fn pow(lhs: Expr, rhs: Expr) -> Expr {
+    Expr::binary(BinaryOp::Pow, lhs, rhs)
+}
+
+fn neg(operand: Expr) -> Expr {
+    Expr::unary(UnaryOp::Neg, operand)
+}
+
+fn num(val: f64) -> Expr {
+    Expr::number(val)
+}
+
+fn call(name: &str, arg: Expr) -> Expr {
+    Expr::call(name, vec![arg])
+}
+
+/// Derivative of `expr` with respect to `var`, user functions must already be expanded.
+pub fn differentiate(expr: &Expr, var: &str) -> Result<Expr, String> {
+    // Constant vectors still differentiate to a zero vector
+    if !expr.contains_ident(var) && !matches!(expr.kind, ExprKind::Vector(_)) {
+        return Ok(num(0.0));
+    }
+    match &expr.kind {
+        ExprKind::Number(_) => Ok(num(0.0)),
+        ExprKind::Ident(_) => Ok(num(1.0)),
+        ExprKind::Unary(UnaryOp::Neg, u) => Ok(neg(differentiate(u, var)?)),
+        ExprKind::Unary(UnaryOp::Factorial, _) => {
+            Err(format!("Can't differentiate the factorial {}", expr))
+        }
+        ExprKind::Binary(op, u, v) => {
+            let (u, v) = (u.as_ref().clone(), v.as_ref().clone());
+            let du = differentiate(&u, var)?;
+            let dv = differentiate(&v, var)?;
+            Ok(match op {
+                BinaryOp::Add => add(du, dv),
+                BinaryOp::Sub => sub(du, dv),
+                BinaryOp::Mul => add(mul(du, v), mul(u, dv)),
+                BinaryOp::Div => {
+                    if v.contains_ident(var) {
+                        div(sub(mul(du, v.clone()), mul(u, dv)), pow(v, num(2.0)))
+                    } else {
+                        div(du, v)
+                    }
+                }
+                BinaryOp::Pow => {
+                    if !v.contains_ident(var) {
+                        mul(mul(v.clone(), pow(u, sub(v, num(1.0)))), du)
+                    } else if !u.contains_ident(var) {
+                        mul(mul(expr.clone(), call("ln", u)), dv)
+                    } else {
+                        mul(
+                            expr.clone(),
+                            add(mul(dv, call("ln", u.clone())), div(mul(v, du), u)),
+                        )
+                    }
+                }
+            })
+        }
+        ExprKind::Call(name, args) => differentiate_call(name, args, var),
+        ExprKind::Vector(items) => Ok(Expr::new(
+            ExprKind::Vector(
+                items
+                    .iter()
+                    .map(|item| differentiate(item, var))
+                    .collect::<Result<_, _>>()?,
+            ),
+            expr.span,
+        )),
+        ExprKind::Equation(_, _) => Err("Can't differentiate an equation".to_owned()),
+        ExprKind::Condition(_, _, _) => Err("Can't differentiate a condition".to_owned()),
+    }
+}
+
+fn differentiate_call(name: &str, args: &[Expr], var: &str) -> Result<Expr, String> {
+    if name == "log" && args.len() == 2 {
+        let rewritten = div(call("ln", args[1].clone()), call("ln", args[0].clone()));
+        return differentiate(&rewritten, var);
+    }
+    if name == "atan2" && args.len() == 2 {
+        let (y, x) = (args[0].clone(), args[1].clone());
+        let (dy, dx) = (differentiate(&y, var)?, differentiate(&x, var)?);
+        return Ok(div(
+            sub(mul(x.clone(), dy), mul(y.clone(), dx)),
+            add(pow(x, num(2.0)), pow(y, num(2.0))),
+        ));
+    }
+    if args.len() != 1 {
+        return Err(format!("Can't differentiate {}", name));
+    }
+
+    let u = args[0].clone();
+    let outer = match name {
+        "sin" => call("cos", u.clone()),
+        "cos" => neg(call("sin", u.clone())),
+        "tan" => div(num(1.0), pow(call("cos", u.clone()), num(2.0))),
+        "cot" => neg(div(num(1.0), pow(call("sin", u.clone()), num(2.0)))),
+        "sec" => mul(call("sec", u.clone()), call("tan", u.clone())),
+        "csc" => neg(mul(call("csc", u.clone()), call("cot", u.clone()))),
+        "asin" => div(
+            num(1.0),
+            call("sqrt", sub(num(1.0), pow(u.clone(), num(2.0)))),
+        ),
+        "acos" => neg(div(
+            num(1.0),
+            call("sqrt", sub(num(1.0), pow(u.clone(), num(2.0)))),
+        )),
+        "atan" => div(num(1.0), add(num(1.0), pow(u.clone(), num(2.0)))),
+        "sinh" => call("cosh", u.clone()),
+        "cosh" => call("sinh", u.clone()),
+        "tanh" => div(num(1.0), pow(call("cosh", u.clone()), num(2.0))),
+        "asinh" => div(
+            num(1.0),
+            call("sqrt", add(pow(u.clone(), num(2.0)), num(1.0))),
+        ),
+        "acosh" => div(
+            num(1.0),
+            call("sqrt", sub(pow(u.clone(), num(2.0)), num(1.0))),
+        ),
+        "atanh" => div(num(1.0), sub(num(1.0), pow(u.clone(), num(2.0)))),
+        "exp" => call("exp", u.clone()),
+        "ln" => div(num(1.0), u.clone()),
+        "log" | "log10" => div(num(1.0), mul(u.clone(), call("ln", num(10.0)))),
+        "log2" => div(num(1.0), mul(u.clone(), call("ln", num(2.0)))),
+        "sqrt" => div(num(1.0), mul(num(2.0), call("sqrt", u.clone()))),
+        "cbrt" => div(
+            num(1.0),
+            mul(num(3.0), pow(call("cbrt", u.clone()), num(2.0))),
+        ),
+        "abs" => div(u.clone(), call("abs", u.clone())),
+        _ => return Err(format!("Can't differentiate {}", name)),
+    };
+    Ok(mul(outer, differentiate(&u, var)?))
+}
+
+/// Inlines user functions, `f'` derivatives and nested `diff` calls so that
+/// the result only contains built-in functions.
+pub fn expand(expr: &Expr, env: &Environment) -> Result<Expr, String> {
+    expand_depth(expr, env, 0)
+}
+
+fn expand_depth(expr: &Expr, env: &Environment, depth: usize) -> Result<Expr, String> {
+    if depth >= env.max_depth {
+        return Err("Recursion too deep while expanding functions".to_owned());
+    }
+    let kind = match &expr.kind {
+        ExprKind::Number(_) | ExprKind::Ident(_) => return Ok(expr.clone()),
+        ExprKind::Unary(op, operand) => {
+            ExprKind::Unary(*op, Box::new(expand_depth(operand, env, depth)?))
+        }
+        ExprKind::Binary(op, lhs, rhs) => ExprKind::Binary(
+            *op,
+            Box::new(expand_depth(lhs, env, depth)?),
+            Box::new(expand_depth(rhs, env, depth)?),
+        ),
+        ExprKind::Vector(items) => ExprKind::Vector(
+            items
+                .iter()
+                .map(|item| expand_depth(item, env, depth))
+                .collect::<Result<_, _>>()?,
+        ),
+        ExprKind::Equation(lhs, rhs) => ExprKind::Equation(
+            Box::new(expand_depth(lhs, env, depth)?),
+            Box::new(expand_depth(rhs, env, depth)?),
+        ),
+        ExprKind::Condition(op, lhs, rhs) => ExprKind::Condition(
+            *op,
+            Box::new(expand_depth(lhs, env, depth)?),
+            Box::new(expand_depth(rhs, env, depth)?),
+        ),
+        ExprKind::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| expand_depth(arg, env, depth))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if name == "diff" {
+                let (target, vars) = derivative_args(&args)?;
+                let mut result = target;
+                for var in vars {
+                    result = simplify(&differentiate(&result, &var)?);
+                }
+                return Ok(result);
+            }
+
+            let func = match env.get_function(name) {
+                Some(func) => Some(func.clone()),
+                None => derive_function(env, name)?,
+            };
+            if let Some(func) = func {
+                if func.params.len() != args.len() {
+                    return Err(format!(
+                        "{} takes {} argument(s), got {}",
+                        name,
+                        func.params.len(),
+                        args.len()
+                    ));
+                }
+                let bindings: Vec<(String, Expr)> = func.params.iter().cloned().zip(args).collect();
+                return expand_depth(&func.body.substitute(&bindings), env, depth + 1);
+            }
+            ExprKind::Call(name.clone(), args)
+        }
+    };
+    Ok(Expr::new(kind, expr.span))
+}
+
+/// Splits the arguments of `diff(expr, x, y, ...)`, a missing variable defaults
+/// to the only identifier of `expr` or `x`.
+pub fn derivative_args(args: &[Expr]) -> Result<(Expr, Vec<String>), String> {
+    let Some(target) = args.first() else {
+        return Err("diff takes at least 1 argument".to_owned());
+    };
+    let mut vars = Vec::new();
+    for arg in &args[1..] {
+        match &arg.kind {
+            ExprKind::Ident(name) => vars.push(name.clone()),
+            _ => return Err(format!("diff expects a variable name, got {}", arg)),
+        }
+    }
+    if vars.is_empty() {
+        let identifiers = target.identifiers();
+        vars.push(match identifiers.as_slice() {
+            [only] => only.clone(),
+            _ => "x".to_owned(),
+        });
+    }
+    Ok((target.clone(), vars))
+}
+
+/// `f'`, `f''`, ... of a single variable user function or built-in function.
+pub fn derive_function(env: &Environment, name: &str) -> Result<Option<UserFunction>, String> {
+    let base = name.trim_end_matches('\'');
+    let order = name.len() - base.len();
+    if order == 0 {
+        return Ok(None);
+    }
+    let func = match env.get_function(base) {
+        Some(func) => func.clone(),
+        None if crate::calc::builtin::get_builtin(base).is_some() => UserFunction {
+            params: vec!["x".to_owned()],
+            body: call(base, Expr::ident("x")),
+        },
+        None => return Ok(None),
+    };
+    if func.params.len() != 1 {
+        return Err(format!(
+            "{} has {} parameters, use diff for partial derivatives",
+            base,
+            func.params.len()
+        ));
+    }
+    let mut body = expand(&func.body, env)?;
+    for _ in 0..order {
+        body = simplify(&differentiate(&body, &func.params[0])?);
+    }
+    Ok(Some(UserFunction {
+        params: func.params,
+        body,
+    }))
+}
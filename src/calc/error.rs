@@ -0,0 +1,45 @@
+use std::fmt;
+
+use crate::calc::token::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalcError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl CalcError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// One line description for the info bar, the offending input is wrapped in `»«`.
+    pub fn report(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+        let column = source[..start].chars().count() + 1;
+        format!(
+            "{} (column {}): {}»{}«{}",
+            self.message,
+            column,
+            &source[..start],
+            &source[start..end],
+            &source[end..]
+        )
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for CalcError {}
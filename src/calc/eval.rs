@@ -0,0 +1,1222 @@
+use std::{cell::Cell, collections::HashMap, fmt};
+
+use num_complex::Complex64;
+
+use crate::calc::{
+    ast::{BinaryOp, ConditionOp, Expr, ExprKind, Statement, UnaryOp},
+    builtin::{Builtin, constants, get_builtin},
+    complex::Complex,
+    diff::{derivative_args, derive_function, differentiate, expand},
+    error::CalcError,
+    matrix::{self, Matrix},
+    number::{Number, NumberMode},
+    numeric::{
+        NumericMethod, NumericResult, derivative, find_root, find_root_in, find_root_system,
+        integrate, minimize_interval, minimize_region, solve_ode,
+    },
+    simplify::simplify,
+    unit::{Quantity, Unit, is_unit, lookup_unit, physical_constants},
+    value::{Value, format_f64},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFunction {
+    pub params: Vec<String>,
+    pub body: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evaluated {
+    Value(Value),
+    Assign(String, Value),
+    Function(String, UserFunction),
+    /// A top level numerical method with its error estimate, optionally assigned
+    Numeric(Option<String>, NumericResult),
+}
+
+impl Evaluated {
+    /// Whether the result is known without rounding.
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Evaluated::Value(val) | Evaluated::Assign(_, val) => val.is_exact(),
+            Evaluated::Function(_, _) => true,
+            Evaluated::Numeric(_, _) => false,
+        }
+    }
+}
+
+/// `= 1/3 ≈ 0.333333333333` for exact fractions and `≈ 1.41421356237` for rounded results.
+fn format_result(val: &Value) -> String {
+    match val {
+        Value::Number(num @ Number::Rational(ratio)) if !ratio.is_integer() => {
+            format!("= {} ≈ {}", num, format_f64(num.to_f64()))
+        }
+        Value::Quantity(quantity) if quantity.displays_exactly() => format!("= {}", quantity),
+        Value::Quantity(quantity) => format!("≈ {}", quantity),
+        _ if val.is_exact() => format!("= {}", val),
+        _ => format!("≈ {}", val),
+    }
+}
+
+impl fmt::Display for Evaluated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evaluated::Value(val) => write!(f, "{}", format_result(val)),
+            Evaluated::Assign(name, val) => write!(f, "{} {}", name, format_result(val)),
+            Evaluated::Function(name, func) => {
+                write!(f, "{}({}) = {}", name, func.params.join(", "), func.body)
+            }
+            Evaluated::Numeric(None, result) => write!(f, "{}", result),
+            Evaluated::Numeric(Some(name), result)
+                if matches!(
+                    result.method,
+                    NumericMethod::Root | NumericMethod::Trajectory
+                ) =>
+            {
+                write!(f, "{} = {}", name, result)
+            }
+            Evaluated::Numeric(Some(name), result) => write!(f, "{} {}", name, result),
+        }
+    }
+}
+
+type Locals = [(String, Value)];
+
+/// Stack of the thread [`with_stack`] evaluates on, nested user functions may use
+/// three quarters of it.
+const EVAL_STACK_SIZE: usize = 256 << 20;
+/// Stack nested user functions may use on other threads, whose size is unknown.
+const DEFAULT_STACK_BUDGET: usize = 512 << 10;
+
+thread_local! {
+    static STACK_BUDGET: Cell<usize> = const { Cell::new(DEFAULT_STACK_BUDGET) };
+    /// Stack position of the outermost user function call, 0 outside of one.
+    static STACK_START: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `f` on a thread with a large stack, so deep recursion in user functions
+/// ends at `max_depth` rather than at a smaller stack budget.
+pub fn with_stack<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(EVAL_STACK_SIZE)
+            .spawn_scoped(scope, || {
+                STACK_BUDGET.set(EVAL_STACK_SIZE / 4 * 3);
+                f()
+            })
+            .expect("failed to spawn the evaluation thread")
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Address of a local, the stack grows down as calls nest.
+#[inline(never)]
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+#[derive(Debug, Clone)]
+pub struct Environment {
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, UserFunction>,
+    /// Maximum nesting of user function calls before evaluation is aborted.
+    pub max_depth: usize,
+    pub number_mode: NumberMode,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        let variables = constants()
+            .into_iter()
+            .map(|(name, val)| (name.to_owned(), Value::from(val)))
+            .chain([("i".to_owned(), Value::Complex(Complex::i()))])
+            .chain(
+                physical_constants()
+                    .into_iter()
+                    .map(|(name, val)| (name.to_owned(), Value::Quantity(val))),
+            )
+            .collect();
+        Self {
+            variables,
+            functions: HashMap::new(),
+            max_depth: 256,
+            number_mode: NumberMode::Exact,
+        }
+    }
+
+    pub fn get_variable(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    pub fn set_variable(&mut self, name: &str, val: Value) {
+        self.variables.insert(name.to_owned(), val);
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<&UserFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn define_function(&mut self, name: &str, func: UserFunction) -> Result<(), String> {
+        if get_builtin(name).is_some() || is_special_form(name) {
+            return Err(format!("Cannot redefine built-in function {}", name));
+        }
+        self.functions.insert(name.to_owned(), func);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.variables.remove(name);
+        self.functions.remove(name);
+    }
+
+    pub fn execute(&mut self, stmt: &Statement) -> Result<Evaluated, CalcError> {
+        match stmt {
+            Statement::Expr(expr) => match self.numeric_call(expr)? {
+                Some(result) => Ok(Evaluated::Numeric(None, result)),
+                None => Ok(Evaluated::Value(self.eval(expr)?)),
+            },
+            Statement::Assign(name, expr) => {
+                if let Some(result) = self.numeric_call(expr)? {
+                    self.set_variable(name, result.value.clone());
+                    return Ok(Evaluated::Numeric(Some(name.clone()), result));
+                }
+                let val = self.eval(expr)?;
+                self.set_variable(name, val.clone());
+                Ok(Evaluated::Assign(name.clone(), val))
+            }
+            Statement::Function { name, params, body } => {
+                let func = UserFunction {
+                    params: params.clone(),
+                    body: body.clone(),
+                };
+                self.define_function(name, func.clone())
+                    .map_err(|err| CalcError::new(err, body.span))?;
+                Ok(Evaluated::Function(name.clone(), func))
+            }
+        }
+    }
+
+    /// Evaluates `solve(...)`, `integrate(...)` and the like with their error estimate,
+    /// `None` for any other expression.
+    pub fn numeric_call(&self, expr: &Expr) -> Result<Option<NumericResult>, CalcError> {
+        match &expr.kind {
+            ExprKind::Call(name, args) if is_numeric_method(name) => {
+                let mut result = self.eval_numeric(expr, name, args, &[], 0)?;
+                result.value = self.normalize(result.value);
+                Ok(Some(result))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn eval(&self, expr: &Expr) -> Result<Value, CalcError> {
+        Ok(self.normalize(self.eval_expr(expr, &[], 0)?))
+    }
+
+    pub fn eval_with(&self, expr: &Expr, bindings: &[(&str, Value)]) -> Result<Value, CalcError> {
+        let locals: Vec<(String, Value)> = bindings
+            .iter()
+            .map(|(name, val)| (name.to_string(), val.clone()))
+            .collect();
+        Ok(self.normalize(self.eval_expr(expr, &locals, 0)?))
+    }
+
+    /// Evaluates to a real number, used by the plotting code for sampling.
+    pub fn eval_real(&self, expr: &Expr, bindings: &[(&str, f64)]) -> Result<f64, CalcError> {
+        let locals: Vec<(String, Value)> = bindings
+            .iter()
+            .map(|(name, val)| (name.to_string(), Value::from(*val)))
+            .collect();
+        let val = self.eval_expr(expr, &locals, 0)?;
+        val.as_f64().ok_or_else(|| {
+            CalcError::new(
+                format!("Expected a real number, got {}", val.type_name()),
+                expr.span,
+            )
+        })
+    }
+
+    /// Evaluates to a complex number, used by domain colouring plots.
+    pub fn eval_complex(
+        &self,
+        expr: &Expr,
+        bindings: &[(&str, Complex64)],
+    ) -> Result<Complex64, CalcError> {
+        let locals: Vec<(String, Value)> = bindings
+            .iter()
+            .map(|(name, val)| (name.to_string(), Value::complex(Complex::from_c64(*val))))
+            .collect();
+        let val = self.eval_expr(expr, &locals, 0)?;
+        val.to_complex().map(|val| val.to_c64()).ok_or_else(|| {
+            CalcError::new(
+                format!("Expected a complex number, got {}", val.type_name()),
+                expr.span,
+            )
+        })
+    }
+
+    /// Rounds a result to the precision of the current mode, intermediate values
+    /// stay exact in decimal mode.
+    pub fn normalize(&self, val: Value) -> Value {
+        match (self.number_mode, val) {
+            (NumberMode::Exact, val) => val,
+            (NumberMode::Decimal(digits), Value::Number(val)) => {
+                Value::Number(val.round_to(digits))
+            }
+            (NumberMode::Decimal(digits), Value::Complex(val)) => {
+                Value::complex(val.map_parts(|part| part.round_to(digits)))
+            }
+            (NumberMode::Decimal(digits), Value::Quantity(val)) => {
+                Value::Quantity(val.map_value(|val| val.round_to(digits)))
+            }
+            (NumberMode::Decimal(_), Value::Vector(items)) => {
+                Value::Vector(items.into_iter().map(|item| self.normalize(item)).collect())
+            }
+            (NumberMode::Decimal(_), Value::Matrix(matrix)) => {
+                Value::Matrix(matrix.map(|entry| self.normalize(entry.clone())))
+            }
+            (NumberMode::Float, val) => to_float(val),
+            (_, val) => val,
+        }
+    }
+
+    fn eval_expr(&self, expr: &Expr, locals: &Locals, depth: usize) -> Result<Value, CalcError> {
+        let val = self.eval_node(expr, locals, depth)?;
+        Ok(match self.number_mode {
+            NumberMode::Float => to_float(val),
+            _ => val,
+        })
+    }
+
+    fn eval_node(&self, expr: &Expr, locals: &Locals, depth: usize) -> Result<Value, CalcError> {
+        match &expr.kind {
+            ExprKind::Number(val) => Ok(Value::Number(val.clone())),
+            ExprKind::Ident(name) => match self.lookup(name, locals) {
+                Some(val) => Ok(val.clone()),
+                None => lookup_unit(name)
+                    .map(|unit| Value::quantity(Quantity::from_unit(unit)))
+                    .ok_or_else(|| {
+                        CalcError::new(format!("Unknown variable '{}'", name), expr.span)
+                    }),
+            },
+            ExprKind::Unary(op, operand) => {
+                let val = self.eval_expr(operand, locals, depth)?;
+                apply_unary(*op, &val).map_err(|err| CalcError::new(err, expr.span))
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs = self.eval_expr(lhs, locals, depth)?;
+                let rhs = self.eval_expr(rhs, locals, depth)?;
+                apply_binary(*op, &lhs, &rhs).map_err(|err| CalcError::new(err, expr.span))
+            }
+            ExprKind::Call(name, args) => self.eval_call(expr, name, args, locals, depth),
+            ExprKind::Vector(items) => {
+                let mut vals = Vec::with_capacity(items.len());
+                for item in items {
+                    vals.push(self.eval_expr(item, locals, depth)?);
+                }
+                matrix::from_items(vals).map_err(|err| CalcError::new(err, expr.span))
+            }
+            ExprKind::Equation(_, _) => Err(CalcError::new(
+                "An equation can't be evaluated to a value",
+                expr.span,
+            )),
+            ExprKind::Condition(op, lhs, rhs) => self
+                .eval_condition(*op, lhs, rhs, locals, depth)
+                .map(|holds| Value::Number(Number::from_integer(holds as i32))),
+        }
+    }
+
+    /// Quantities compare when they share a dimension, `and` and `or` take non-zero
+    /// numbers as true like `if`.
+    fn eval_condition(
+        &self,
+        op: ConditionOp,
+        lhs: &Expr,
+        rhs: &Expr,
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<bool, CalcError> {
+        let truthy = |arg: &Expr| {
+            let val = self.eval_expr(arg, locals, depth)?;
+            Ok::<_, CalcError>(val.as_number().is_some_and(|val| !val.is_zero()))
+        };
+        match op {
+            ConditionOp::And => return Ok(truthy(lhs)? && truthy(rhs)?),
+            ConditionOp::Or => return Ok(truthy(lhs)? || truthy(rhs)?),
+            _ => {}
+        }
+        let real = |arg: &Expr| {
+            let val = self.eval_expr(arg, locals, depth)?;
+            val.to_quantity().ok_or_else(|| {
+                CalcError::new(format!("Can't compare a {}", val.type_name()), arg.span)
+            })
+        };
+        let (a, b) = (real(lhs)?, real(rhs)?);
+        if a.dimension != b.dimension {
+            return Err(CalcError::new(
+                "Can't compare quantities of different dimensions",
+                lhs.span.join(rhs.span),
+            ));
+        }
+        let (a, b) = (a.value.to_f64(), b.value.to_f64());
+        Ok(match op {
+            ConditionOp::Less => a < b,
+            ConditionOp::LessEqual => a <= b,
+            ConditionOp::Greater => a > b,
+            _ => a >= b,
+        })
+    }
+
+    fn lookup<'a>(&'a self, name: &str, locals: &'a Locals) -> Option<&'a Value> {
+        locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, val)| val)
+            .or_else(|| self.variables.get(name))
+    }
+
+    fn eval_call(
+        &self,
+        expr: &Expr,
+        name: &str,
+        args: &[Expr],
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<Value, CalcError> {
+        let is_local = locals.iter().any(|(local, _)| local == name);
+
+        if !is_local && let Some(func) = self.functions.get(name) {
+            if func.params.len() != args.len() {
+                return Err(CalcError::new(
+                    format!(
+                        "{} takes {} argument(s), got {}",
+                        name,
+                        func.params.len(),
+                        args.len()
+                    ),
+                    expr.span,
+                ));
+            }
+            // Frames are large, the stack in use is checked as well as the nesting
+            let position = stack_position();
+            let start = STACK_START.get();
+            if depth >= self.max_depth || start.saturating_sub(position) > STACK_BUDGET.get() {
+                return Err(CalcError::new(
+                    format!("Recursion too deep in {}", name),
+                    expr.span,
+                ));
+            }
+            let mut frame = Vec::with_capacity(args.len());
+            for (param, arg) in func.params.iter().zip(args) {
+                frame.push((param.clone(), self.eval_expr(arg, locals, depth)?));
+            }
+            if start == 0 {
+                STACK_START.set(position);
+            }
+            // Spans inside the body belong to the definition, report at the call site
+            let result = self
+                .eval_expr(&func.body, &frame, depth + 1)
+                .map_err(|err| CalcError::new(err.message, expr.span));
+            if start == 0 {
+                STACK_START.set(0);
+            }
+            return result;
+        }
+
+        if !is_local && is_special_form(name) {
+            return self.eval_special_form(expr, name, args, locals, depth);
+        }
+
+        if !is_local && let Some(builtin) = get_builtin(name) {
+            let mut vals = Vec::with_capacity(args.len());
+            for arg in args {
+                vals.push(self.eval_expr(arg, locals, depth)?);
+            }
+            return self
+                .call_builtin(builtin, &vals)
+                .map_err(|err| CalcError::new(err, expr.span));
+        }
+
+        if !is_local
+            && name.ends_with('\'')
+            && let Some(func) =
+                derive_function(self, name).map_err(|err| CalcError::new(err, expr.span))?
+        {
+            if args.len() != 1 {
+                return Err(CalcError::new(
+                    format!("{} takes 1 argument, got {}", name, args.len()),
+                    expr.span,
+                ));
+            }
+            let arg = self.eval_expr(&args[0], locals, depth)?;
+            let frame = vec![(func.params[0].clone(), arg)];
+            return self
+                .eval_expr(&func.body, &frame, depth + 1)
+                .map_err(|err| CalcError::new(err.message, expr.span));
+        }
+
+        // `a(b + 1)` with a variable `a` is an implicit multiplication
+        if let Some(val) = self.lookup(name, locals)
+            && args.len() == 1
+        {
+            let rhs = self.eval_expr(&args[0], locals, depth)?;
+            return apply_binary(BinaryOp::Mul, val, &rhs)
+                .map_err(|err| CalcError::new(err, expr.span));
+        }
+
+        Err(CalcError::new(
+            format!("Unknown function '{}'", name),
+            expr.span,
+        ))
+    }
+
+    fn eval_special_form(
+        &self,
+        expr: &Expr,
+        name: &str,
+        args: &[Expr],
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<Value, CalcError> {
+        match name {
+            "if" => {
+                if args.len() != 3 {
+                    return Err(CalcError::new(
+                        format!("if takes 3 arguments, got {}", args.len()),
+                        expr.span,
+                    ));
+                }
+                let condition = self.eval_expr(&args[0], locals, depth)?;
+                let truthy = condition.as_number().is_some_and(|val| !val.is_zero());
+                self.eval_expr(if truthy { &args[1] } else { &args[2] }, locals, depth)
+            }
+            "to" => self.eval_conversion(expr, args, locals, depth),
+            _ if is_numeric_method(name) => self
+                .eval_numeric(expr, name, args, locals, depth)
+                .map(|result| result.value),
+            "diff" => self
+                .eval_derivative(args, locals, depth)
+                .map_err(|err| CalcError::new(err, expr.span)),
+            "simplify" => {
+                if args.len() != 1 {
+                    return Err(CalcError::new(
+                        format!("simplify takes 1 argument, got {}", args.len()),
+                        expr.span,
+                    ));
+                }
+                let expanded =
+                    expand(&args[0], self).map_err(|err| CalcError::new(err, expr.span))?;
+                Ok(self.bind_symbolic(&simplify(&expanded), locals))
+            }
+            _ => Err(CalcError::new(
+                format!("Unknown function '{}'", name),
+                expr.span,
+            )),
+        }
+    }
+
+    /// `value to unit`, the target is evaluated like any expression so `km/hr` works.
+    fn eval_conversion(
+        &self,
+        expr: &Expr,
+        args: &[Expr],
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<Value, CalcError> {
+        let [value, target] = args else {
+            return Err(CalcError::new(
+                format!("to takes 2 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        };
+        let quantity = |arg: &Expr| {
+            let val = self.eval_expr(arg, locals, depth)?;
+            val.to_quantity().ok_or_else(|| {
+                CalcError::new(
+                    format!("Can't convert a {} to a unit", val.type_name()),
+                    arg.span,
+                )
+            })
+        };
+        let (val, target_val) = (quantity(value)?, quantity(target)?);
+        let unit = Unit {
+            name: target.to_string().replace(" / ", "/").replace(" * ", " "),
+            factor: target_val.value,
+            dimension: target_val.dimension,
+        };
+        val.convert(unit)
+            .map(Value::Quantity)
+            .map_err(|err| CalcError::new(err, expr.span))
+    }
+
+    /// Differentiates symbolically, the result is a number when every remaining
+    /// variable is bound and an expression otherwise.
+    fn eval_derivative(
+        &self,
+        args: &[Expr],
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<Value, String> {
+        let (target, vars) = derivative_args(args)?;
+        let mut result = expand(&target, self)?;
+        for var in &vars {
+            result = simplify(&differentiate(&result, var)?);
+        }
+
+        let unbound = result
+            .identifiers()
+            .iter()
+            .any(|name| self.lookup(name, locals).is_none() && !is_unit(name));
+        if !unbound {
+            return self
+                .eval_expr(&result, locals, depth)
+                .map_err(|err| err.message);
+        }
+        Ok(self.bind_symbolic(&result, locals))
+    }
+
+    /// `solve(f(x) = 0, x, guess)`, `solve(f(x) = 0, x, a, b)`, `nsolve([eqs], [vars], [guess])`,
+    /// `integrate(f, x, a, b)`, `minimize`/`maximize` over `x, a, b` or `[x, y], [x0, x1], [y0, y1]`
+    /// and `ode(y' = f(t, y), y0, t0..t1)` with an optional fixed step.
+    fn eval_numeric(
+        &self,
+        expr: &Expr,
+        name: &str,
+        args: &[Expr],
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<NumericResult, CalcError> {
+        let failed = |err: String| CalcError::new(err, expr.span);
+        let real = |arg: &Expr| self.eval_real_arg(arg, locals, depth);
+        match (name, args) {
+            ("solve", [equation, var, rest @ ..]) if rest.len() <= 2 => {
+                let func = residual(equation);
+                let vars = [single_var(var)?];
+                let f = self.real_function(&func, &vars, locals, depth);
+                let f = |x: f64| f(&[x]);
+                let (root, error) = match rest {
+                    [a, b] => find_root_in(&f, real(a)?, real(b)?),
+                    _ => {
+                        let guess = rest.first().map(real).transpose()?.unwrap_or(0.0);
+                        let slope = expand(&func, self)
+                            .and_then(|expanded| differentiate(&expanded, &vars[0]))
+                            .map(|slope| simplify(&slope));
+                        match slope {
+                            Ok(slope) => {
+                                let df = self.real_function(&slope, &vars, locals, depth);
+                                find_root(&f, &|x: f64| df(&[x]), guess)
+                            }
+                            Err(_) => find_root(&f, &|x: f64| derivative(&f, x), guess),
+                        }
+                    }
+                }
+                .map_err(failed)?;
+                Ok(NumericResult {
+                    method: NumericMethod::Root,
+                    func: func.clone(),
+                    vars: vars.to_vec(),
+                    bounds: Vec::new(),
+                    value: Value::from(root),
+                    at: vec![root],
+                    error,
+                })
+            }
+            ("nsolve", [equations, vars, rest @ ..]) if rest.len() <= 1 => {
+                let funcs: Vec<Expr> = match &equations.kind {
+                    ExprKind::Vector(items) => items.iter().map(residual).collect(),
+                    _ => vec![residual(equations)],
+                };
+                let vars = numeric_vars(vars)?;
+                let guess = match rest.first() {
+                    Some(guess) => self.eval_reals(guess, Some(vars.len()), locals, depth)?,
+                    None => vec![0.0; vars.len()],
+                };
+                let residuals: Vec<_> = funcs
+                    .iter()
+                    .map(|func| self.real_function(func, &vars, locals, depth))
+                    .collect();
+                let f = |x: &[f64]| residuals.iter().map(|f| f(x)).collect();
+                let (root, error) = find_root_system(&f, &guess).map_err(failed)?;
+                let value = match root.as_slice() {
+                    [x] => Value::from(*x),
+                    _ => Value::Vector(root.iter().copied().map(Value::from).collect()),
+                };
+                Ok(NumericResult {
+                    method: NumericMethod::Root,
+                    func: Expr::new(ExprKind::Vector(funcs.clone()), equations.span),
+                    vars: vars.clone(),
+                    bounds: Vec::new(),
+                    value,
+                    at: root,
+                    error,
+                })
+            }
+            ("integrate", [func, var, a, b]) => {
+                let vars = [single_var(var)?];
+                let (a, b) = (real(a)?, real(b)?);
+                let f = self.real_function(func, &vars, locals, depth);
+                let (val, error) = integrate(&|x: f64| f(&[x]), a, b).map_err(failed)?;
+                Ok(NumericResult {
+                    method: NumericMethod::Integral,
+                    func: func.clone(),
+                    vars: vars.to_vec(),
+                    bounds: vec![(a, b)],
+                    value: Value::from(val),
+                    at: Vec::new(),
+                    error,
+                })
+            }
+            ("minimize" | "maximize", [func, var, a, b]) => {
+                let sign = if name == "minimize" { 1.0 } else { -1.0 };
+                let vars = numeric_vars(var)?;
+                let f = self.real_function(func, &vars, locals, depth);
+                let f = |x: &[f64]| f(x).map(|val| sign * val);
+                let (at, val, error, bounds) = match vars.len() {
+                    1 => {
+                        let (a, b) = (real(a)?, real(b)?);
+                        let (x, val, error) =
+                            minimize_interval(&|x: f64| f(&[x]), a, b).map_err(failed)?;
+                        (vec![x], val, error, vec![(a, b)])
+                    }
+                    2 => {
+                        let range = |arg: &Expr| {
+                            let [lo, hi] = self.eval_reals(arg, Some(2), locals, depth)?[..] else {
+                                unreachable!()
+                            };
+                            Ok::<_, CalcError>((lo, hi))
+                        };
+                        let bounds = [range(a)?, range(b)?];
+                        let (at, val, error) = minimize_region(&f, bounds).map_err(failed)?;
+                        (at.to_vec(), val, error, bounds.to_vec())
+                    }
+                    _ => return Err(failed(format!("{} supports 1 or 2 variables", name))),
+                };
+                Ok(NumericResult {
+                    method: if sign > 0.0 {
+                        NumericMethod::Minimum
+                    } else {
+                        NumericMethod::Maximum
+                    },
+                    func: func.clone(),
+                    vars: vars.clone(),
+                    bounds,
+                    value: Value::from(sign * val),
+                    at,
+                    error,
+                })
+            }
+            ("ode", [system, initial, span, rest @ ..]) if rest.len() <= 1 => {
+                let (vars, funcs) = ode_system(system)?;
+                let y0 = self.eval_reals(initial, None, locals, depth)?;
+                let [t0, t1] = self.eval_reals(span, Some(2), locals, depth)?[..] else {
+                    unreachable!()
+                };
+                let step = rest.first().map(real).transpose()?;
+                // A single equation may have a vector state like `y' = A y`
+                let packed = funcs.len() == 1 && y0.len() > 1;
+                if !packed && y0.len() != vars.len() {
+                    return Err(CalcError::new(
+                        format!("Expected {} initial values, got {}", vars.len(), y0.len()),
+                        initial.span,
+                    ));
+                }
+                let f = |t: f64, y: &[f64]| {
+                    let mut frame = locals.to_vec();
+                    frame.push(("t".to_owned(), Value::from(t)));
+                    if packed {
+                        let state = y.iter().copied().map(Value::from).collect();
+                        frame.push((vars[0].clone(), Value::Vector(state)));
+                    } else {
+                        frame.extend(
+                            vars.iter()
+                                .zip(y)
+                                .map(|(var, val)| (var.clone(), Value::from(*val))),
+                        );
+                    }
+                    let mut slope = Vec::with_capacity(y.len());
+                    for func in &funcs {
+                        let val = self
+                            .eval_expr(func, &frame, depth)
+                            .map_err(|err| err.message)?;
+                        let items = match val {
+                            Value::Vector(items) => items,
+                            val => vec![val],
+                        };
+                        for item in items {
+                            slope.push(item.as_f64().ok_or_else(|| {
+                                format!("Expected a real derivative, got {}", item.type_name())
+                            })?);
+                        }
+                    }
+                    Ok(slope)
+                };
+                let (samples, error) = solve_ode(&f, &y0, t0, t1, step).map_err(failed)?;
+                let at = samples.last().map_or(Vec::new(), |row| row[1..].to_vec());
+                let rows = samples
+                    .into_iter()
+                    .map(|row| row.into_iter().map(Value::from).collect())
+                    .collect();
+                Ok(NumericResult {
+                    method: NumericMethod::Trajectory,
+                    func: system.clone(),
+                    vars,
+                    bounds: vec![(t0, t1)],
+                    value: Value::Matrix(Matrix::from_rows(rows).map_err(failed)?),
+                    at,
+                    error,
+                })
+            }
+            _ => Err(failed(format!(
+                "Wrong arguments for {}, try {}",
+                name,
+                match name {
+                    "solve" => "solve(x^2 = 2, x, 1) or solve(x^2 = 2, x, 0, 2)",
+                    "nsolve" => "nsolve([x + y = 3, x - y = 1], [x, y], [0, 0])",
+                    "integrate" => "integrate(x^2, x, 0, 1)",
+                    "ode" => "ode(y' = -y, 1, 0..5) or ode([x' = y, y' = -x], [1, 0], 0..10)",
+                    _ =>
+                        "minimize(x^2 - x, x, -1, 1) or minimize(x^2 + y^2, [x, y], [-1, 1], [-1, 1])",
+                }
+            ))),
+        }
+    }
+
+    /// `body` as a real function of `vars` for the numerical methods.
+    fn real_function<'a>(
+        &'a self,
+        body: &'a Expr,
+        vars: &'a [String],
+        locals: &'a Locals,
+        depth: usize,
+    ) -> impl Fn(&[f64]) -> Result<f64, String> + 'a {
+        move |point: &[f64]| {
+            let mut frame = locals.to_vec();
+            frame.extend(
+                vars.iter()
+                    .zip(point)
+                    .map(|(var, val)| (var.clone(), Value::from(*val))),
+            );
+            let val = self
+                .eval_expr(body, &frame, depth)
+                .map_err(|err| err.message)?;
+            val.as_f64()
+                .ok_or_else(|| format!("Expected a real number, got {}", val.type_name()))
+        }
+    }
+
+    fn eval_real_arg(&self, arg: &Expr, locals: &Locals, depth: usize) -> Result<f64, CalcError> {
+        let val = self.eval_expr(arg, locals, depth)?;
+        val.as_f64().ok_or_else(|| {
+            CalcError::new(
+                format!("Expected a real number, got {}", val.type_name()),
+                arg.span,
+            )
+        })
+    }
+
+    /// A vector of real numbers, of `len` items if given, a plain number is a single item.
+    fn eval_reals(
+        &self,
+        arg: &Expr,
+        len: Option<usize>,
+        locals: &Locals,
+        depth: usize,
+    ) -> Result<Vec<f64>, CalcError> {
+        let val = self.eval_expr(arg, locals, depth)?;
+        let reals = match &val {
+            Value::Vector(items) => items.iter().map(Value::as_f64).collect(),
+            val => val.as_f64().map(|val| vec![val]),
+        };
+        reals
+            .filter(|reals| len.is_none_or(|len| reals.len() == len))
+            .ok_or_else(|| {
+                let expected = len.map_or("real numbers".to_owned(), |len| {
+                    format!("{} real number(s)", len)
+                });
+                CalcError::new(format!("Expected {}, got {}", expected, val), arg.span)
+            })
+    }
+
+    /// Substitutes bound variables other than the built-in constants into a symbolic result.
+    fn bind_symbolic(&self, expr: &Expr, locals: &Locals) -> Value {
+        let constants: Vec<&str> = constants()
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(["i"])
+            .chain(physical_constants().iter().map(|(name, _)| *name))
+            .collect();
+        let bindings: Vec<(String, Expr)> = expr
+            .identifiers()
+            .into_iter()
+            .filter(|name| !constants.contains(&name.as_str()))
+            .filter_map(|name| {
+                let val = self.lookup(&name, locals)?;
+                Some((name, val.to_expr()))
+            })
+            .collect();
+        let result = simplify(&expr.substitute(&bindings));
+        match result.as_exact() {
+            Some(val) => Value::Number(val.clone()),
+            None => Value::Expression(Box::new(result)),
+        }
+    }
+
+    /// Square roots keep the configured number of digits in decimal mode, dimensionless
+    /// quantities like `30 deg` are passed on as plain numbers.
+    fn call_builtin(&self, builtin: &Builtin, args: &[Value]) -> Result<Value, String> {
+        let args: Vec<Value> = args
+            .iter()
+            .map(|arg| match arg {
+                Value::Quantity(val) if val.dimension.is_none() => Value::Number(val.value.clone()),
+                arg => arg.clone(),
+            })
+            .collect();
+        let args = args.as_slice();
+        if let ("sqrt" | "abs", [Value::Quantity(val)]) = (builtin.name, args) {
+            return match builtin.name {
+                "sqrt" => val
+                    .pow(&Number::parse_literal("0.5").unwrap())
+                    .map(Value::quantity),
+                _ => Ok(Value::Quantity(val.map_value(Number::abs))),
+            };
+        }
+        if let (NumberMode::Decimal(digits), "sqrt", [arg]) = (self.number_mode, builtin.name, args)
+            && let Some(z) = arg.to_complex()
+            && z.is_real()
+            && z.re.as_rational().is_some()
+        {
+            return Ok(Value::complex(z.sqrt(Some(digits))));
+        }
+        builtin.call(args)
+    }
+}
+
+fn is_special_form(name: &str) -> bool {
+    matches!(name, "if" | "to" | "diff" | "simplify") || is_numeric_method(name)
+}
+
+fn is_numeric_method(name: &str) -> bool {
+    matches!(
+        name,
+        "solve" | "nsolve" | "integrate" | "minimize" | "maximize" | "ode"
+    )
+}
+
+/// `lhs - rhs` of an equation, other expressions are taken as `expr = 0`.
+fn residual(expr: &Expr) -> Expr {
+    match &expr.kind {
+        ExprKind::Equation(lhs, rhs) => Expr::new(
+            ExprKind::Binary(BinaryOp::Sub, lhs.clone(), rhs.clone()),
+            expr.span,
+        ),
+        _ => expr.clone(),
+    }
+}
+
+/// `x` or `[x, y]` naming the unknowns of a numerical method.
+fn numeric_vars(arg: &Expr) -> Result<Vec<String>, CalcError> {
+    let items = match &arg.kind {
+        ExprKind::Vector(items) => items.as_slice(),
+        _ => std::slice::from_ref(arg),
+    };
+    items.iter().map(single_var).collect()
+}
+
+/// `y' = f(t, y)` or `[x' = f(t, x, y), y' = g(t, x, y)]`, the state variables and
+/// their derivatives.
+fn ode_system(system: &Expr) -> Result<(Vec<String>, Vec<Expr>), CalcError> {
+    let equations = match &system.kind {
+        ExprKind::Vector(items) => items.as_slice(),
+        _ => std::slice::from_ref(system),
+    };
+    equations
+        .iter()
+        .map(|equation| match &equation.kind {
+            ExprKind::Equation(lhs, rhs)
+                if let ExprKind::Ident(name) = &lhs.kind
+                    && let Some(var) = name.strip_suffix('\'')
+                    && !var.ends_with('\'') =>
+            {
+                Ok((var.to_owned(), (**rhs).clone()))
+            }
+            _ => Err(CalcError::new(
+                format!("Expected an equation like y' = f(t, y), found {}", equation),
+                equation.span,
+            )),
+        })
+        .collect()
+}
+
+fn single_var(arg: &Expr) -> Result<String, CalcError> {
+    match &arg.kind {
+        ExprKind::Ident(name) => Ok(name.clone()),
+        _ => Err(CalcError::new(
+            format!("Expected a variable name, found {}", arg),
+            arg.span,
+        )),
+    }
+}
+
+pub fn to_float(val: Value) -> Value {
+    match val {
+        Value::Number(val) => Value::Number(val.to_float()),
+        Value::Complex(val) => Value::Complex(val.map_parts(Number::to_float)),
+        Value::Quantity(val) => Value::Quantity(val.map_value(Number::to_float)),
+        Value::Vector(items) => Value::Vector(items.into_iter().map(to_float).collect()),
+        Value::Matrix(matrix) => Value::Matrix(matrix.map(|entry| to_float(entry.clone()))),
+        val => val,
+    }
+}
+
+pub fn apply_unary(op: UnaryOp, val: &Value) -> Result<Value, String> {
+    let x = match val {
+        Value::Number(x) => x,
+        Value::Complex(z) => {
+            return match op {
+                UnaryOp::Neg => Ok(Value::Complex(z.neg())),
+                UnaryOp::Factorial => Err(format!("Factorial is undefined for {}", val)),
+            };
+        }
+        Value::Quantity(q) => {
+            return match op {
+                UnaryOp::Neg => Ok(Value::Quantity(q.map_value(Number::neg))),
+                UnaryOp::Factorial => Err(format!("Factorial is undefined for {}", val)),
+            };
+        }
+        Value::Vector(items) => {
+            return items
+                .iter()
+                .map(|item| apply_unary(op, item))
+                .collect::<Result<_, _>>()
+                .map(Value::Vector);
+        }
+        Value::Matrix(matrix) => {
+            return matrix
+                .try_map(|entry| apply_unary(op, entry))
+                .map(Value::Matrix);
+        }
+        Value::Expression(expr) => {
+            return Ok(Value::Expression(Box::new(simplify(&Expr::unary(
+                op,
+                expr.as_ref().clone(),
+            )))));
+        }
+    };
+    match op {
+        UnaryOp::Neg => Ok(Value::Number(x.neg())),
+        UnaryOp::Factorial => {
+            let result = x.factorial()?;
+            if result.is_nan() {
+                return Err(format!("Factorial is undefined for {}", val));
+            }
+            Ok(Value::Number(result))
+        }
+    }
+}
+
+pub fn apply_binary(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let (a, b) = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => (a, b),
+        (Value::Vector(_) | Value::Matrix(_), _) | (_, Value::Vector(_) | Value::Matrix(_)) => {
+            return matrix::apply_linear(op, lhs, rhs);
+        }
+        (Value::Expression(_), _) | (_, Value::Expression(_)) => {
+            return Ok(Value::Expression(Box::new(simplify(&Expr::binary(
+                op,
+                lhs.to_expr(),
+                rhs.to_expr(),
+            )))));
+        }
+        (Value::Quantity(_), _) | (_, Value::Quantity(_)) => {
+            return apply_quantity(op, lhs, rhs);
+        }
+        _ => return apply_complex(op, lhs, rhs),
+    };
+    let result = match op {
+        BinaryOp::Add => a.add(b),
+        BinaryOp::Sub => a.sub(b),
+        BinaryOp::Mul => a.mul(b),
+        BinaryOp::Div => a.div(b)?,
+        BinaryOp::Pow => a.pow(b),
+    };
+    if result.is_nan() && !a.is_nan() && !b.is_nan() {
+        // `(-8)^(1/4)` has no real value, use the principal complex root
+        if op == BinaryOp::Pow && a.is_negative() {
+            return apply_complex(op, lhs, rhs);
+        }
+        return Err(format!("{} {} {} is undefined", lhs, op.symbol(), rhs));
+    }
+    Ok(Value::Number(result))
+}
+
+fn apply_complex(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let (Some(a), Some(b)) = (lhs.to_complex(), rhs.to_complex()) else {
+        return Err(format!(
+            "Can't apply {} to {}",
+            op.symbol(),
+            lhs.type_name()
+        ));
+    };
+    let result = match op {
+        BinaryOp::Add => a.add(&b),
+        BinaryOp::Sub => a.sub(&b),
+        BinaryOp::Mul => a.mul(&b),
+        BinaryOp::Div => a.div(&b)?,
+        BinaryOp::Pow => a.pow(&b)?,
+    };
+    if result.is_nan() {
+        return Err(format!("{} {} {} is undefined", lhs, op.symbol(), rhs));
+    }
+    Ok(Value::complex(result))
+}
+
+/// Dimensions must match for `+` and `-`, exponents must be dimensionless.
+fn apply_quantity(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let (Some(a), Some(b)) = (lhs.to_quantity(), rhs.to_quantity()) else {
+        return Err(format!(
+            "Can't apply {} to a complex number and a quantity",
+            op.symbol()
+        ));
+    };
+    let result = match op {
+        BinaryOp::Add => a.add(&b)?,
+        BinaryOp::Sub => a.sub(&b)?,
+        BinaryOp::Mul => a.mul(&b)?,
+        BinaryOp::Div => a.div(&b)?,
+        BinaryOp::Pow if !b.dimension.is_none() => {
+            return Err(format!("Exponent {} must be dimensionless", rhs));
+        }
+        BinaryOp::Pow => a.pow(&b.value)?,
+    };
+    Ok(Value::quantity(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::{parser::parse_with, token::Span};
+
+    /// Parses like the algebra view, with the variables defined so far.
+    fn run(env: &mut Environment, input: &str) -> Result<Evaluated, CalcError> {
+        let expr = parse_with(input, &|name| env.get_variable(name).is_some())?;
+        env.execute(&Statement::from_expr(expr))
+    }
+
+    fn value(env: &mut Environment, input: &str) -> f64 {
+        match run(env, input) {
+            Ok(Evaluated::Value(val)) => val.as_f64().expect("a real result"),
+            other => panic!("{} evaluated to {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let mut env = Environment::new();
+        assert_eq!(value(&mut env, "1 + 2 * 3"), 7.0);
+        assert_eq!(value(&mut env, "(1 + 2) * 3"), 9.0);
+        assert_eq!(value(&mut env, "2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(value(&mut env, "-2 ^ 2"), -4.0);
+        assert_eq!(value(&mut env, "10 - 4 - 3"), 3.0);
+        assert_eq!(value(&mut env, "12 / 3 / 2"), 2.0);
+        assert_eq!(value(&mut env, "3!"), 6.0);
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        let mut env = Environment::new();
+        run(&mut env, "x = 3").unwrap();
+        assert_eq!(value(&mut env, "2x"), 6.0);
+        assert_eq!(value(&mut env, "2(x + 1)"), 8.0);
+        assert_eq!(value(&mut env, "(x - 1)(x + 1)"), 8.0);
+        assert_eq!(value(&mut env, "2x^2"), 18.0);
+        assert!((value(&mut env, "2pi") - std::f64::consts::TAU).abs() < 1e-12);
+    }
+
+    #[test]
+    fn assignment() {
+        let mut env = Environment::new();
+        assert!(matches!(
+            run(&mut env, "a = 4"),
+            Ok(Evaluated::Assign(name, _)) if name == "a"
+        ));
+        assert_eq!(value(&mut env, "a^2"), 16.0);
+        run(&mut env, "a = a + 1").unwrap();
+        assert_eq!(value(&mut env, "a"), 5.0);
+        let err = run(&mut env, "b + 1").unwrap_err();
+        assert_eq!(err.message, "Unknown variable 'b'");
+        assert_eq!(err.span, Span::new(0, 1));
+    }
+
+    #[test]
+    fn user_functions() {
+        let mut env = Environment::new();
+        assert!(matches!(
+            run(&mut env, "f(x) = x^2 + 1"),
+            Ok(Evaluated::Function(name, _)) if name == "f"
+        ));
+        assert_eq!(value(&mut env, "f(3)"), 10.0);
+        run(&mut env, "g(x, y) = f(x) - y").unwrap();
+        assert_eq!(value(&mut env, "g(2, 1)"), 4.0);
+        // Parameters shadow variables of the same name
+        run(&mut env, "x = 100").unwrap();
+        assert_eq!(value(&mut env, "f(1)"), 2.0);
+        let err = run(&mut env, "f(1, 2)").unwrap_err();
+        assert_eq!(err.message, "f takes 1 argument(s), got 2");
+    }
+
+    #[test]
+    fn variables_named_like_units() {
+        let mut env = Environment::new();
+        run(&mut env, "x = 2").unwrap();
+        assert_eq!(value(&mut env, "1/2 x"), 1.0);
+        run(&mut env, "m = 2").unwrap();
+        assert_eq!(value(&mut env, "1/2 m"), 1.0);
+        run(&mut env, "f(s) = 1/2 s").unwrap();
+        assert_eq!(value(&mut env, "f(4)"), 2.0);
+    }
+
+    #[test]
+    fn recursion_depth() {
+        let mut env = Environment::new();
+        env.max_depth = 16;
+        run(&mut env, "f(n) = f(n - 1) + 1").unwrap();
+        let input = "1 + f(3)";
+        let err = run(&mut env, input).unwrap_err();
+        assert_eq!(err.message, "Recursion too deep in f");
+        // Reported at the call in the input rather than inside the body
+        assert_eq!(&input[err.span.start..err.span.end], "f(3)");
+    }
+
+    #[test]
+    fn recursion_limit() {
+        with_stack(|| {
+            let mut env = Environment::new();
+            run(&mut env, "f(n) = if(n <= 0, 0, f(n - 1) + 1)").unwrap();
+            let below = env.max_depth - 2;
+            assert_eq!(value(&mut env, &format!("f({})", below)), below as f64);
+            let above = format!("f({})", env.max_depth);
+            let err = run(&mut env, &above).unwrap_err();
+            assert_eq!(err.message, "Recursion too deep in f");
+        });
+    }
+
+    #[test]
+    fn recursion_stays_on_the_stack() {
+        let mut env = Environment::new();
+        env.max_depth = usize::MAX;
+        run(&mut env, "f(n) = if(n <= 0, 0, f(n - 1) + 1)").unwrap();
+        let err = run(&mut env, "f(1000000)").unwrap_err();
+        assert_eq!(err.message, "Recursion too deep in f");
+        // Evaluation carries on after the error
+        assert_eq!(value(&mut env, "f(2)"), 2.0);
+    }
+}
@@ -0,0 +1,885 @@
+use std::cmp::Ordering;
+
+use num_complex::Complex64;
+
+use crate::calc::{
+    ast::BinaryOp,
+    builtin::get_builtin,
+    complex::Complex,
+    eval::{apply_binary, to_float},
+    number::{MAX_EXACT_BITS, Number},
+    value::Value,
+};
+
+/// Relative size below which rounded pivots count as zero.
+const PIVOT_TOLERANCE: f64 = 1e-12;
+/// Looser tolerance for the null space of `A - λI`, `λ` itself is rounded.
+const EIGEN_TOLERANCE: f64 = 1e-8;
+
+/// Dense row-major matrix of scalar values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    entries: Vec<Value>,
+}
+
+impl Matrix {
+    pub fn new(rows: usize, cols: usize, entries: Vec<Value>) -> Self {
+        assert_eq!(
+            entries.len(),
+            rows * cols,
+            "Matrix entries don't match its shape"
+        );
+        Self {
+            rows,
+            cols,
+            entries,
+        }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<Value>>) -> Result<Self, String> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err("Matrix rows must have the same length".to_owned());
+        }
+        Ok(Self::new(
+            rows.len(),
+            cols,
+            rows.into_iter().flatten().collect(),
+        ))
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let entries = (0..n * n)
+            .map(|i| {
+                Value::Number(if i / n == i % n {
+                    Number::one()
+                } else {
+                    Number::zero()
+                })
+            })
+            .collect();
+        Self::new(n, n, entries)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    pub fn entries(&self) -> &[Value] {
+        &self.entries
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &Value {
+        &self.entries[row * self.cols + col]
+    }
+
+    pub fn row(&self, row: usize) -> &[Value] {
+        &self.entries[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn column(&self, col: usize) -> Vec<Value> {
+        (0..self.rows)
+            .map(|row| self.get(row, col).clone())
+            .collect()
+    }
+
+    pub fn to_rows(&self) -> Vec<Vec<Value>> {
+        (0..self.rows).map(|row| self.row(row).to_vec()).collect()
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let entries = (0..self.cols).flat_map(|col| self.column(col)).collect();
+        Matrix::new(self.cols, self.rows, entries)
+    }
+
+    pub fn map(&self, f: impl FnMut(&Value) -> Value) -> Matrix {
+        Matrix::new(self.rows, self.cols, self.entries.iter().map(f).collect())
+    }
+
+    pub fn try_map(
+        &self,
+        f: impl FnMut(&Value) -> Result<Value, String>,
+    ) -> Result<Matrix, String> {
+        let entries = self.entries.iter().map(f).collect::<Result<_, _>>()?;
+        Ok(Matrix::new(self.rows, self.cols, entries))
+    }
+}
+
+fn zero() -> Value {
+    Value::Number(Number::zero())
+}
+
+fn is_linear(val: &Value) -> bool {
+    matches!(val, Value::Vector(_) | Value::Matrix(_))
+}
+
+/// Sum of products, starting from the first product so quantities never meet a bare zero.
+fn dot_values(a: &[Value], b: &[Value]) -> Result<Value, String> {
+    let mut products = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| apply_binary(BinaryOp::Mul, x, y));
+    let Some(first) = products.next() else {
+        return Ok(zero());
+    };
+    products.try_fold(first?, |sum, product| {
+        apply_binary(BinaryOp::Add, &sum, &product?)
+    })
+}
+
+fn elementwise(op: BinaryOp, a: &[Value], b: &[Value]) -> Result<Vec<Value>, String> {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| apply_binary(op, x, y))
+        .collect()
+}
+
+fn matmul(a: &Matrix, b: &Matrix) -> Result<Matrix, String> {
+    if a.cols != b.rows {
+        return Err(format!(
+            "Can't multiply a {}x{} matrix by a {}x{} matrix",
+            a.rows, a.cols, b.rows, b.cols
+        ));
+    }
+    let columns: Vec<Vec<Value>> = (0..b.cols).map(|col| b.column(col)).collect();
+    let mut entries = Vec::with_capacity(a.rows * b.cols);
+    for row in 0..a.rows {
+        for column in &columns {
+            entries.push(dot_values(a.row(row), column)?);
+        }
+    }
+    Ok(Matrix::new(a.rows, b.cols, entries))
+}
+
+fn matrix_power(a: &Matrix, exp: &Value) -> Result<Matrix, String> {
+    let n = exp
+        .as_number()
+        .and_then(Number::to_i64)
+        .ok_or_else(|| format!("Matrix powers need an integer exponent, got {}", exp))?;
+    if !a.is_square() {
+        return Err(format!(
+            "Can't raise a {}x{} matrix to a power",
+            a.rows, a.cols
+        ));
+    }
+    let mut base = if n < 0 { inverse(a)? } else { a.clone() };
+    // Entries grow like (rows * largest entry)^n, past the limit for exact powers
+    // of numbers the power is taken in floats
+    let bits = base.entries.iter().map(exact_bits).max().unwrap_or(0)
+        + (usize::BITS - base.rows.leading_zeros()) as u64;
+    if bits.saturating_mul(n.unsigned_abs()) > MAX_EXACT_BITS {
+        base = base.map(|entry| to_float(entry.clone()));
+    }
+    let mut result = Matrix::identity(a.rows);
+    let mut n = n.unsigned_abs();
+    while n > 0 {
+        if n & 1 == 1 {
+            result = matmul(&result, &base)?;
+        }
+        n >>= 1;
+        if n > 0 {
+            base = matmul(&base, &base)?;
+        }
+    }
+    Ok(result)
+}
+
+fn exact_bits(val: &Value) -> u64 {
+    match val {
+        Value::Number(val) => val.bits(),
+        Value::Complex(val) => val.re.bits().max(val.im.bits()),
+        Value::Quantity(val) => val.value.bits(),
+        _ => 0,
+    }
+}
+
+/// Arithmetic where at least one operand is a vector or a matrix, vectors act as
+/// columns on the right of a matrix and as rows on the left.
+pub fn apply_linear(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    let scale = |items: &[Value], scalar: &Value, scalar_first: bool| {
+        items
+            .iter()
+            .map(|item| match scalar_first {
+                true => apply_binary(op, scalar, item),
+                false => apply_binary(op, item, scalar),
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let verb = if op == BinaryOp::Add {
+        "add"
+    } else {
+        "subtract"
+    };
+    match (op, lhs, rhs) {
+        (BinaryOp::Add | BinaryOp::Sub, Value::Vector(a), Value::Vector(b)) => {
+            if a.len() != b.len() {
+                return Err(format!(
+                    "Can't {} vectors of length {} and {}",
+                    verb,
+                    a.len(),
+                    b.len()
+                ));
+            }
+            Ok(Value::Vector(elementwise(op, a, b)?))
+        }
+        (BinaryOp::Add | BinaryOp::Sub, Value::Matrix(a), Value::Matrix(b)) => {
+            if (a.rows, a.cols) != (b.rows, b.cols) {
+                return Err(format!(
+                    "Can't {} a {}x{} and a {}x{} matrix",
+                    verb, a.rows, a.cols, b.rows, b.cols
+                ));
+            }
+            let entries = elementwise(op, &a.entries, &b.entries)?;
+            Ok(Value::Matrix(Matrix::new(a.rows, a.cols, entries)))
+        }
+        (BinaryOp::Mul, Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(matmul(a, b)?)),
+        (BinaryOp::Mul, Value::Matrix(a), Value::Vector(v)) => {
+            let column = Matrix::new(v.len(), 1, v.clone());
+            Ok(Value::Vector(matmul(a, &column)?.entries))
+        }
+        (BinaryOp::Mul, Value::Vector(v), Value::Matrix(a)) => {
+            let row = Matrix::new(1, v.len(), v.clone());
+            Ok(Value::Vector(matmul(&row, a)?.entries))
+        }
+        (BinaryOp::Mul, Value::Vector(_), Value::Vector(_)) => {
+            Err("Use dot or cross to multiply two vectors".to_owned())
+        }
+        (BinaryOp::Mul, scalar, Value::Vector(v)) if !is_linear(scalar) => {
+            Ok(Value::Vector(scale(v, scalar, true)?))
+        }
+        (BinaryOp::Mul, scalar, Value::Matrix(a)) if !is_linear(scalar) => {
+            let entries = scale(&a.entries, scalar, true)?;
+            Ok(Value::Matrix(Matrix::new(a.rows, a.cols, entries)))
+        }
+        (BinaryOp::Mul | BinaryOp::Div, Value::Vector(v), scalar) if !is_linear(scalar) => {
+            Ok(Value::Vector(scale(v, scalar, false)?))
+        }
+        (BinaryOp::Mul | BinaryOp::Div, Value::Matrix(a), scalar) if !is_linear(scalar) => {
+            let entries = scale(&a.entries, scalar, false)?;
+            Ok(Value::Matrix(Matrix::new(a.rows, a.cols, entries)))
+        }
+        (BinaryOp::Pow, Value::Matrix(a), exp) => Ok(Value::Matrix(matrix_power(a, exp)?)),
+        _ => Err(format!(
+            "Can't apply {} to a {} and a {}",
+            op.symbol(),
+            lhs.type_name(),
+            rhs.type_name()
+        )),
+    }
+}
+
+/// Builds the value of a `[...]` literal, a vector of equally long vectors is a matrix.
+pub fn from_items(items: Vec<Value>) -> Result<Value, String> {
+    if !items.iter().any(is_linear) {
+        return Ok(Value::Vector(items));
+    }
+    let rows = items
+        .into_iter()
+        .map(|item| match item {
+            Value::Vector(row) => Ok(row),
+            Value::Matrix(_) => Err("Matrix entries can't be matrices".to_owned()),
+            _ => Err("Matrix rows must all be vectors".to_owned()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::Matrix(Matrix::from_rows(rows)?))
+}
+
+fn vector_arg<'a>(name: &str, val: &'a Value) -> Result<&'a [Value], String> {
+    match val {
+        Value::Vector(items) => Ok(items),
+        _ => Err(format!(
+            "{} expects a vector, got {}",
+            name,
+            val.type_name()
+        )),
+    }
+}
+
+fn matrix_arg<'a>(name: &str, val: &'a Value) -> Result<&'a Matrix, String> {
+    match val {
+        Value::Matrix(matrix) => Ok(matrix),
+        _ => Err(format!(
+            "{} expects a matrix, got {}",
+            name,
+            val.type_name()
+        )),
+    }
+}
+
+fn square_arg<'a>(name: &str, val: &'a Value) -> Result<&'a Matrix, String> {
+    let matrix = matrix_arg(name, val)?;
+    if !matrix.is_square() {
+        return Err(format!(
+            "{} expects a square matrix, got {}x{}",
+            name, matrix.rows, matrix.cols
+        ));
+    }
+    Ok(matrix)
+}
+
+pub fn dot(args: &[Value]) -> Result<Value, String> {
+    let a = vector_arg("dot", &args[0])?;
+    let b = vector_arg("dot", &args[1])?;
+    if a.len() != b.len() {
+        return Err(format!(
+            "dot expects vectors of equal length, got {} and {}",
+            a.len(),
+            b.len()
+        ));
+    }
+    dot_values(a, b)
+}
+
+pub fn cross(args: &[Value]) -> Result<Value, String> {
+    let a = vector_arg("cross", &args[0])?;
+    let b = vector_arg("cross", &args[1])?;
+    let ([a0, a1, a2], [b0, b1, b2]) = (a, b) else {
+        return Err("cross expects two 3-vectors".to_owned());
+    };
+    let component = |x: &Value, y: &Value, z: &Value, w: &Value| {
+        apply_binary(
+            BinaryOp::Sub,
+            &apply_binary(BinaryOp::Mul, x, y)?,
+            &apply_binary(BinaryOp::Mul, z, w)?,
+        )
+    };
+    Ok(Value::Vector(vec![
+        component(a1, b2, a2, b1)?,
+        component(a2, b0, a0, b2)?,
+        component(a0, b1, a1, b0)?,
+    ]))
+}
+
+fn sqrt_value(val: Value) -> Result<Value, String> {
+    match val {
+        Value::Quantity(quantity) => Ok(Value::quantity(
+            quantity.pow(&Number::parse_literal("0.5").unwrap())?,
+        )),
+        val => get_builtin("sqrt").unwrap().call(&[val]),
+    }
+}
+
+/// Euclidean norm of a vector, Frobenius norm of a matrix.
+pub fn norm(args: &[Value]) -> Result<Value, String> {
+    let entries = match &args[0] {
+        Value::Vector(items) => items.as_slice(),
+        Value::Matrix(matrix) => matrix.entries(),
+        val => {
+            return Err(format!(
+                "norm expects a vector or a matrix, got {}",
+                val.type_name()
+            ));
+        }
+    };
+    let conjugates = entries
+        .iter()
+        .map(|entry| match entry {
+            Value::Complex(z) => Value::complex(z.conj()),
+            entry => entry.clone(),
+        })
+        .collect::<Vec<_>>();
+    sqrt_value(dot_values(entries, &conjugates)?)
+}
+
+pub fn transpose(args: &[Value]) -> Result<Value, String> {
+    match &args[0] {
+        Value::Vector(items) => Ok(Value::Matrix(Matrix::new(1, items.len(), items.clone()))),
+        Value::Matrix(matrix) => Ok(Value::Matrix(matrix.transpose())),
+        val => Err(format!(
+            "transpose expects a vector or a matrix, got {}",
+            val.type_name()
+        )),
+    }
+}
+
+pub fn identity(args: &[Value]) -> Result<Value, String> {
+    let n = args[0]
+        .as_number()
+        .and_then(Number::to_i64)
+        .filter(|n| (0..=1000).contains(n))
+        .ok_or_else(|| format!("identity expects a size, got {}", args[0]))?;
+    Ok(Value::Matrix(Matrix::identity(n as usize)))
+}
+
+fn magnitude(val: &Value) -> Option<f64> {
+    match val {
+        Value::Number(x) => Some(x.to_f64().abs()),
+        Value::Complex(z) => Some(z.to_c64().norm()),
+        _ => None,
+    }
+}
+
+fn is_exact_zero(val: &Value) -> bool {
+    match val {
+        Value::Number(x) => x.is_zero(),
+        Value::Complex(z) => z.is_zero(),
+        _ => false,
+    }
+}
+
+/// Rows in reduced row echelon form with the pivot column of every non-zero row.
+struct Reduced {
+    rows: Vec<Vec<Value>>,
+    pivots: Vec<usize>,
+    /// Determinant of the leading square block, valid when every column has a pivot
+    det: Value,
+}
+
+/// Gauss-Jordan elimination with partial pivoting on the first `pivot_cols` columns.
+/// Exact entries are compared with zero exactly, rounded ones relative to `tolerance`.
+fn reduce(
+    name: &str,
+    mut rows: Vec<Vec<Value>>,
+    pivot_cols: usize,
+    tolerance: f64,
+) -> Result<Reduced, String> {
+    let mut scale = 0.0f64;
+    for entry in rows.iter().flatten() {
+        let size = magnitude(entry)
+            .ok_or_else(|| format!("{} needs numeric entries, got {}", name, entry))?;
+        scale = scale.max(size);
+    }
+    let negligible = |val: &Value| {
+        is_exact_zero(val)
+            || (!val.is_exact() && magnitude(val).is_some_and(|size| size <= tolerance * scale))
+    };
+
+    let mut det = Value::Number(Number::one());
+    let mut pivots = Vec::new();
+    for col in 0..pivot_cols {
+        let row = pivots.len();
+        if row == rows.len() {
+            break;
+        }
+        let pivot_row = (row..rows.len())
+            .filter(|&i| !negligible(&rows[i][col]))
+            .max_by(|&i, &j| {
+                let (a, b) = (magnitude(&rows[i][col]), magnitude(&rows[j][col]));
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            });
+        let Some(pivot_row) = pivot_row else {
+            continue;
+        };
+        if pivot_row != row {
+            rows.swap(pivot_row, row);
+            det = apply_binary(BinaryOp::Sub, &zero(), &det)?;
+        }
+        let pivot = rows[row][col].clone();
+        det = apply_binary(BinaryOp::Mul, &det, &pivot)?;
+        rows[row] = rows[row]
+            .iter()
+            .map(|entry| apply_binary(BinaryOp::Div, entry, &pivot))
+            .collect::<Result<_, _>>()?;
+        rows[row][col] = Value::Number(Number::one());
+
+        for i in 0..rows.len() {
+            if i == row || is_exact_zero(&rows[i][col]) {
+                continue;
+            }
+            let factor = rows[i][col].clone();
+            let updated = rows[i]
+                .iter()
+                .zip(&rows[row])
+                .map(|(entry, pivot_entry)| {
+                    let product = apply_binary(BinaryOp::Mul, &factor, pivot_entry)?;
+                    apply_binary(BinaryOp::Sub, entry, &product)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            rows[i] = updated;
+            rows[i][col] = zero();
+        }
+        pivots.push(col);
+    }
+    if pivots.len() < pivot_cols {
+        det = zero();
+    }
+    Ok(Reduced { rows, pivots, det })
+}
+
+pub fn det(args: &[Value]) -> Result<Value, String> {
+    let matrix = square_arg("det", &args[0])?;
+    Ok(reduce("det", matrix.to_rows(), matrix.cols, PIVOT_TOLERANCE)?.det)
+}
+
+pub fn rank(args: &[Value]) -> Result<Value, String> {
+    let matrix = match &args[0] {
+        Value::Vector(items) => &Matrix::new(items.len(), 1, items.clone()),
+        val => matrix_arg("rank", val)?,
+    };
+    let reduced = reduce("rank", matrix.to_rows(), matrix.cols, PIVOT_TOLERANCE)?;
+    Ok(Value::Number(Number::from_integer(reduced.pivots.len())))
+}
+
+fn inverse(matrix: &Matrix) -> Result<Matrix, String> {
+    let n = matrix.rows;
+    let identity = Matrix::identity(n);
+    let augmented = (0..n)
+        .map(|row| [matrix.row(row), identity.row(row)].concat())
+        .collect();
+    let reduced = reduce("inv", augmented, n, PIVOT_TOLERANCE)?;
+    if reduced.pivots.len() < n {
+        return Err("The matrix is singular".to_owned());
+    }
+    Matrix::from_rows(
+        reduced
+            .rows
+            .into_iter()
+            .map(|row| row[n..].to_vec())
+            .collect(),
+    )
+}
+
+pub fn inv(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Matrix(inverse(square_arg("inv", &args[0])?)?))
+}
+
+/// Solves `A x = b` for a vector or matrix `b`.
+pub fn linsolve(args: &[Value]) -> Result<Value, String> {
+    let a = matrix_arg("linsolve", &args[0])?;
+    let (b, is_vector) = match &args[1] {
+        Value::Vector(items) => (Matrix::new(items.len(), 1, items.clone()), true),
+        Value::Matrix(matrix) => (matrix.clone(), false),
+        val => {
+            return Err(format!(
+                "linsolve expects a vector or a matrix, got {}",
+                val.type_name()
+            ));
+        }
+    };
+    if b.rows != a.rows {
+        return Err(format!(
+            "linsolve expects {} right hand side rows, got {}",
+            a.rows, b.rows
+        ));
+    }
+    let augmented = (0..a.rows)
+        .map(|row| [a.row(row), b.row(row)].concat())
+        .collect();
+    let reduced = reduce("linsolve", augmented, a.cols, PIVOT_TOLERANCE)?;
+    let rank = reduced.pivots.len();
+    // Rows below the rank read 0 = rhs
+    let consistent = reduced.rows[rank..].iter().all(|row| {
+        row[a.cols..]
+            .iter()
+            .all(|entry| is_exact_zero(entry) || magnitude(entry).is_some_and(|x| x < 1e-9))
+    });
+    if !consistent {
+        return Err("The system has no solution".to_owned());
+    }
+    if rank < a.cols {
+        return Err("The system has infinitely many solutions".to_owned());
+    }
+    let solution = Matrix::from_rows(
+        reduced.rows[..rank]
+            .iter()
+            .map(|row| row[a.cols..].to_vec())
+            .collect(),
+    )?;
+    Ok(if is_vector {
+        Value::Vector(solution.entries)
+    } else {
+        Value::Matrix(solution)
+    })
+}
+
+/// Coefficients of `det(λI - A)` from the constant term up, exact for exact entries
+/// (Faddeev-LeVerrier).
+fn characteristic_polynomial(a: &[Vec<Number>]) -> Result<Vec<Number>, String> {
+    let n = a.len();
+    let mut coefs = vec![Number::zero(); n + 1];
+    coefs[n] = Number::one();
+    let mut m = vec![vec![Number::zero(); n]; n];
+    for k in 1..=n {
+        let mut next = vec![vec![Number::zero(); n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                let mut sum = if i == j {
+                    coefs[n - k + 1].clone()
+                } else {
+                    Number::zero()
+                };
+                for (l, row) in m.iter().enumerate() {
+                    sum = sum.add(&a[i][l].mul(&row[j]));
+                }
+                next[i][j] = sum;
+            }
+        }
+        m = next;
+        let mut trace = Number::zero();
+        for (i, row) in a.iter().enumerate() {
+            for (l, m_row) in m.iter().enumerate() {
+                trace = trace.add(&row[l].mul(&m_row[i]));
+            }
+        }
+        coefs[n - k] = trace.neg().div(&Number::from_integer(k as i64))?;
+    }
+    Ok(coefs)
+}
+
+/// All complex roots of a monic polynomial (Durand-Kerner).
+fn polynomial_roots(coefs: &[f64]) -> Vec<Complex64> {
+    let degree = coefs.len() - 1;
+    let eval = |z: Complex64| {
+        coefs
+            .iter()
+            .rev()
+            .fold(Complex64::new(0.0, 0.0), |acc, &c| acc * z + c)
+    };
+    let radius = 1.0 + coefs[..degree].iter().fold(0.0f64, |m, c| m.max(c.abs()));
+    let mut roots: Vec<Complex64> = (0..degree)
+        .map(|k| {
+            let angle = std::f64::consts::TAU * k as f64 / degree as f64 + 0.4;
+            Complex64::from_polar(radius, angle)
+        })
+        .collect();
+    for _ in 0..2000 {
+        let mut change = 0.0f64;
+        for i in 0..degree {
+            let mut denom = Complex64::new(1.0, 0.0);
+            for j in 0..degree {
+                if i != j {
+                    denom *= roots[i] - roots[j];
+                }
+            }
+            if denom.norm() == 0.0 {
+                continue;
+            }
+            let step = eval(roots[i]) / denom;
+            roots[i] -= step;
+            change = change.max(step.norm());
+        }
+        if change <= 1e-15 * radius {
+            break;
+        }
+    }
+    roots
+}
+
+/// Eigenvalues of a symmetric matrix by cyclic Jacobi rotations.
+fn symmetric_eigenvalues(mut a: Vec<Vec<f64>>) -> Vec<f64> {
+    let n = a.len();
+    for _ in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        let total: f64 = a.iter().flatten().map(|x| x * x).sum();
+        if off <= 1e-30 * total {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (x, y) = (row[p], row[q]);
+                    row[p] = c * x - s * y;
+                    row[q] = s * x + c * y;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (x, y)) in row_p.into_iter().zip(row_q).enumerate() {
+                    a[p][k] = c * x - s * y;
+                    a[q][k] = s * x + c * y;
+                }
+            }
+        }
+    }
+    (0..n).map(|i| a[i][i]).collect()
+}
+
+/// Replaces a rounded root by a nearby exact one with a small denominator when the
+/// exact characteristic polynomial vanishes there.
+fn snap_root(root: Complex64, coefs: &[Number]) -> Value {
+    if coefs.iter().all(Number::is_exact) {
+        for denom in 1..=12 {
+            let round = |x: f64| {
+                let scaled = (x * denom as f64).round();
+                (scaled.abs() < 1e15).then(|| {
+                    Number::from_integer(scaled as i64)
+                        .div(&Number::from_integer(denom))
+                        .unwrap()
+                })
+            };
+            let (Some(re), Some(im)) = (round(root.re), round(root.im)) else {
+                break;
+            };
+            let candidate = Complex::new(re, im);
+            let residual = coefs
+                .iter()
+                .rev()
+                .fold(Complex::from_real(Number::zero()), |acc, c| {
+                    acc.mul(&candidate).add(&Complex::from_real(c.clone()))
+                });
+            if residual.is_zero() {
+                return Value::complex(candidate);
+            }
+        }
+    }
+    let scale = 1.0 + root.norm();
+    if root.im.abs() <= 1e-9 * scale {
+        return Value::Number(Number::Float(root.re));
+    }
+    Value::complex(Complex::from_c64(root))
+}
+
+fn real_matrix(name: &str, matrix: &Matrix) -> Result<Vec<Vec<Number>>, String> {
+    (0..matrix.rows)
+        .map(|row| {
+            matrix
+                .row(row)
+                .iter()
+                .map(|entry| match entry {
+                    Value::Number(x) => Ok(x.clone()),
+                    entry => Err(format!("{} needs real entries, got {}", name, entry)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Eigenvalues sorted by decreasing real part, then decreasing imaginary part.
+fn eigenvalues(name: &str, matrix: &Matrix) -> Result<Vec<Value>, String> {
+    let a = real_matrix(name, matrix)?;
+    let coefs = characteristic_polynomial(&a)?;
+    let floats: Vec<Vec<f64>> = a
+        .iter()
+        .map(|row| row.iter().map(Number::to_f64).collect())
+        .collect();
+    let n = a.len();
+    let symmetric = (0..n).all(|i| (0..i).all(|j| floats[i][j] == floats[j][i]));
+    let roots = if symmetric {
+        symmetric_eigenvalues(floats)
+            .into_iter()
+            .map(|x| Complex64::new(x, 0.0))
+            .collect()
+    } else {
+        polynomial_roots(&coefs.iter().map(Number::to_f64).collect::<Vec<_>>())
+    };
+    let mut values: Vec<Value> = roots
+        .into_iter()
+        .map(|root| snap_root(root, &coefs))
+        .collect();
+    values.sort_by(|a, b| {
+        let (a, b) = (to_c64(a), to_c64(b));
+        b.re.partial_cmp(&a.re)
+            .unwrap_or(Ordering::Equal)
+            .then(b.im.partial_cmp(&a.im).unwrap_or(Ordering::Equal))
+    });
+    Ok(values)
+}
+
+fn to_c64(val: &Value) -> Complex64 {
+    val.to_complex()
+        .map_or(Complex64::new(f64::NAN, 0.0), |z| z.to_c64())
+}
+
+pub fn eigvals(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Vector(eigenvalues(
+        "eigvals",
+        square_arg("eigvals", &args[0])?,
+    )?))
+}
+
+/// Basis of the null space of `A - λI`, exact for exact `λ`, unit length otherwise.
+fn eigenspace(matrix: &Matrix, lambda: &Value) -> Result<Vec<Vec<Value>>, String> {
+    let n = matrix.rows;
+    let shifted = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| match i == j {
+                    true => apply_binary(BinaryOp::Sub, matrix.get(i, j), lambda),
+                    false => Ok(matrix.get(i, j).clone()),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let reduced = reduce("eigvecs", shifted, n, EIGEN_TOLERANCE)?;
+    let mut basis = Vec::new();
+    for free in (0..n).filter(|col| !reduced.pivots.contains(col)) {
+        let mut vector = vec![zero(); n];
+        vector[free] = Value::Number(Number::one());
+        for (row, &pivot) in reduced.pivots.iter().enumerate() {
+            vector[pivot] = apply_binary(BinaryOp::Sub, &zero(), &reduced.rows[row][free])?;
+        }
+        if !vector.iter().all(Value::is_exact) {
+            let length = vector
+                .iter()
+                .map(|entry| magnitude(entry).unwrap_or(0.0).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            let length = Value::Number(Number::Float(length));
+            vector = vector
+                .iter()
+                .map(|entry| apply_binary(BinaryOp::Div, entry, &length))
+                .collect::<Result<_, _>>()?;
+        }
+        basis.push(vector);
+    }
+    Ok(basis)
+}
+
+/// Eigenvectors as the columns of a matrix, in the order of `eigvals`.
+pub fn eigvecs(args: &[Value]) -> Result<Value, String> {
+    let matrix = square_arg("eigvecs", &args[0])?;
+    let values = eigenvalues("eigvecs", matrix)?;
+    let n = matrix.rows;
+    let mut columns: Vec<Vec<Value>> = Vec::with_capacity(n);
+    let mut i = 0;
+    while i < values.len() {
+        let lambda = to_c64(&values[i]);
+        let tolerance = EIGEN_TOLERANCE * (1.0 + lambda.norm());
+        let multiplicity = values[i..]
+            .iter()
+            .take_while(|other| (to_c64(other) - lambda).norm() <= tolerance)
+            .count();
+        let basis = eigenspace(matrix, &values[i])?;
+        if basis.len() < multiplicity {
+            return Err(format!(
+                "The matrix is not diagonalizable, eigenvalue {} is defective",
+                values[i]
+            ));
+        }
+        columns.extend(basis.into_iter().take(multiplicity));
+        i += multiplicity;
+    }
+    let rows = (0..n)
+        .map(|row| columns.iter().map(|column| column[row].clone()).collect())
+        .collect();
+    Ok(Value::Matrix(Matrix::from_rows(rows)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci() -> Matrix {
+        let (one, zero) = (Value::Number(Number::one()), Value::Number(Number::zero()));
+        Matrix::from_rows(vec![vec![one.clone(), one.clone()], vec![one, zero]]).unwrap()
+    }
+
+    #[test]
+    fn large_powers_fall_back_to_floats() {
+        let exp = |n: i64| Value::Number(Number::from_integer(n));
+        let small = matrix_power(&fibonacci(), &exp(10)).unwrap();
+        assert_eq!(small.get(0, 1), &Value::Number(Number::from_integer(55)));
+
+        let large = matrix_power(&fibonacci(), &exp(100_000_000)).unwrap();
+        assert!(!large.get(0, 0).is_exact());
+        assert_eq!(large.get(0, 0).as_f64(), Some(f64::INFINITY));
+    }
+}
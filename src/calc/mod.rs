@@ -0,0 +1,15 @@
+pub mod ast;
+pub mod builtin;
+pub mod complex;
+pub mod diff;
+pub mod error;
+pub mod matrix;
+pub mod eval;
+pub mod number;
+pub mod numeric;
+pub mod parser;
+pub mod simplify;
+pub mod table;
+pub mod token;
+pub mod unit;
+pub mod value;
@@ -0,0 +1,541 @@
+use std::{cmp::Ordering, fmt};
+
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::calc::value::format_f64;
+
+/// Results larger than this many bits fall back to floats.
+pub const MAX_EXACT_BITS: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberMode {
+    /// Rationals and big integers wherever possible
+    Exact,
+    /// Rationals rounded to the given number of significant digits
+    Decimal(u32),
+    /// Plain `f64`, used for plotting
+    Float,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    Rational(BigRational),
+    /// Decimal rounded value, `exact` is false once rounding changed it
+    Decimal {
+        value: BigRational,
+        exact: bool,
+    },
+    Float(f64),
+}
+
+impl Number {
+    pub fn zero() -> Self {
+        Number::Rational(BigRational::zero())
+    }
+
+    pub fn one() -> Self {
+        Number::Rational(BigRational::one())
+    }
+
+    pub fn from_integer(val: impl Into<BigInt>) -> Self {
+        Number::Rational(BigRational::from_integer(val.into()))
+    }
+
+    /// Integral floats become exact integers, everything else stays a float.
+    pub fn from_f64(val: f64) -> Self {
+        if val.is_finite() && val.fract() == 0.0 && val.abs() < 9.007_199_254_740_992e15 {
+            Number::from_integer(val as i64)
+        } else {
+            Number::Float(val)
+        }
+    }
+
+    /// Parses a literal like `12`, `0.1` or `1.5e-3` exactly.
+    pub fn parse_literal(text: &str) -> Option<Self> {
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(index) => (&text[..index], text[index + 1..].parse::<i64>().ok()?),
+            None => (text, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return None;
+        }
+        let digits = format!("{}{}", int_part, frac_part);
+        let numer: BigInt = digits.parse().ok()?;
+        let exponent = exponent - frac_part.len() as i64;
+        if exponent.unsigned_abs() > 100_000 {
+            return text.parse::<f64>().ok().map(Number::Float);
+        }
+        let scale = BigInt::from(10).pow(exponent.unsigned_abs() as u32);
+        let value = if exponent >= 0 {
+            BigRational::from_integer(numer * scale)
+        } else {
+            BigRational::new(numer, scale)
+        };
+        Some(Number::Rational(value))
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Rational(val) | Number::Decimal { value: val, .. } => rational_to_f64(val),
+            Number::Float(val) => *val,
+        }
+    }
+
+    pub fn to_float(&self) -> Self {
+        Number::Float(self.to_f64())
+    }
+
+    pub fn as_rational(&self) -> Option<&BigRational> {
+        match self {
+            Number::Rational(val) | Number::Decimal { value: val, .. } => Some(val),
+            Number::Float(_) => None,
+        }
+    }
+
+    pub fn to_integer(&self) -> Option<BigInt> {
+        match self {
+            Number::Float(val) if val.fract() == 0.0 && val.abs() < 9.007_199_254_740_992e15 => {
+                Some(BigInt::from(*val as i64))
+            }
+            Number::Float(_) => None,
+            _ => {
+                let val = self.as_rational()?;
+                val.is_integer().then(|| val.to_integer())
+            }
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_integer()?.to_i64()
+    }
+
+    /// Bits of the larger of numerator and denominator, 0 for floats.
+    pub fn bits(&self) -> u64 {
+        self.as_rational()
+            .map_or(0, |val| val.numer().bits().max(val.denom().bits()))
+    }
+
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Number::Rational(_) => true,
+            Number::Decimal { exact, .. } => *exact,
+            Number::Float(_) => false,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Float(val) => *val == 0.0,
+            _ => self.as_rational().is_some_and(|val| val.is_zero()),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Number::Float(val) => *val < 0.0,
+            _ => self.as_rational().is_some_and(|val| val.is_negative()),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.to_integer().is_some()
+    }
+
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Number::Float(val) if val.is_nan())
+    }
+
+    /// Rebuilds a rational result, decimals stay decimals and keep the exactness of both operands.
+    fn combine(&self, other: &Number, value: BigRational) -> Number {
+        match (self, other) {
+            (Number::Rational(_), Number::Rational(_)) => Number::Rational(value),
+            _ => Number::Decimal {
+                value,
+                exact: self.is_exact() && other.is_exact(),
+            },
+        }
+    }
+
+    fn with_rational(&self, value: BigRational) -> Number {
+        match self {
+            Number::Decimal { exact, .. } => Number::Decimal {
+                value,
+                exact: *exact,
+            },
+            _ => Number::Rational(value),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        match (self.as_rational(), other.as_rational()) {
+            (Some(a), Some(b)) => self.combine(other, a + b),
+            _ => Number::Float(self.to_f64() + other.to_f64()),
+        }
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        match (self.as_rational(), other.as_rational()) {
+            (Some(a), Some(b)) => self.combine(other, a - b),
+            _ => Number::Float(self.to_f64() - other.to_f64()),
+        }
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        match (self.as_rational(), other.as_rational()) {
+            (Some(a), Some(b)) => self.combine(other, a * b),
+            _ => Number::Float(self.to_f64() * other.to_f64()),
+        }
+    }
+
+    pub fn div(&self, other: &Number) -> Result<Number, String> {
+        if other.is_zero() {
+            return Err("Division by zero".to_owned());
+        }
+        Ok(match (self.as_rational(), other.as_rational()) {
+            (Some(a), Some(b)) => self.combine(other, a / b),
+            _ => Number::Float(self.to_f64() / other.to_f64()),
+        })
+    }
+
+    pub fn neg(&self) -> Number {
+        match self {
+            Number::Float(val) => Number::Float(-val),
+            _ => self.with_rational(-self.as_rational().unwrap()),
+        }
+    }
+
+    pub fn abs(&self) -> Number {
+        if self.is_negative() {
+            self.neg()
+        } else {
+            self.clone()
+        }
+    }
+
+    pub fn floor(&self) -> Number {
+        match self.as_rational() {
+            Some(val) => self.with_rational(val.floor()),
+            None => Number::Float(self.to_f64().floor()),
+        }
+    }
+
+    pub fn ceil(&self) -> Number {
+        match self.as_rational() {
+            Some(val) => self.with_rational(val.ceil()),
+            None => Number::Float(self.to_f64().ceil()),
+        }
+    }
+
+    pub fn round(&self) -> Number {
+        match self.as_rational() {
+            Some(val) => self.with_rational(val.round()),
+            None => Number::Float(self.to_f64().round()),
+        }
+    }
+
+    pub fn trunc(&self) -> Number {
+        match self.as_rational() {
+            Some(val) => self.with_rational(val.trunc()),
+            None => Number::Float(self.to_f64().trunc()),
+        }
+    }
+
+    /// Euclidean remainder, always non-negative for a positive modulus.
+    pub fn rem_euclid(&self, other: &Number) -> Result<Number, String> {
+        if other.is_zero() {
+            return Err("Division by zero".to_owned());
+        }
+        let quotient = self.div(other)?.floor();
+        Ok(self.sub(&other.mul(&quotient)))
+    }
+
+    pub fn pow(&self, exp: &Number) -> Number {
+        if let (Some(base), Some(exp_val)) = (self.as_rational(), exp.as_rational())
+            && let Some(result) = rational_pow(base, exp_val)
+        {
+            return self.combine(exp, result);
+        }
+        Number::Float(self.to_f64().powf(exp.to_f64()))
+    }
+
+    /// Square root, exact for perfect squares and rounded to `digits` significant digits otherwise.
+    pub fn sqrt(&self, digits: Option<u32>) -> Number {
+        let Some(val) = self.as_rational() else {
+            return Number::Float(self.to_f64().sqrt());
+        };
+        if val.is_negative() {
+            return Number::Float(f64::NAN);
+        }
+        if let Some(root) = exact_root(val, 2) {
+            return self.with_rational(root);
+        }
+        match digits {
+            Some(digits) => {
+                // sqrt(p / q) = sqrt(p q 10^2k) / (q 10^k)
+                let shift = BigInt::from(10).pow(digits + 2);
+                let radicand = val.numer() * val.denom() * &shift * &shift;
+                let root = BigRational::new(radicand.sqrt(), val.denom() * shift);
+                Number::Decimal {
+                    value: round_significant(&root, digits),
+                    exact: false,
+                }
+            }
+            None => Number::Float(self.to_f64().sqrt()),
+        }
+    }
+
+    /// Cube root, exact for perfect cubes.
+    pub fn cbrt(&self) -> Number {
+        match self.as_rational().and_then(|val| exact_root(val, 3)) {
+            Some(root) => self.with_rational(root),
+            None => Number::Float(self.to_f64().cbrt()),
+        }
+    }
+
+    pub fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self.as_rational(), other.as_rational()) {
+            (Some(a), Some(b)) => Some(a.cmp(b)),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        }
+    }
+
+    /// Rounds rationals to `digits` significant digits, marking them inexact if that changed them.
+    pub fn round_to(&self, digits: u32) -> Number {
+        match self.as_rational() {
+            Some(val) if !val.is_integer() || val.numer().bits() > 3 * digits as u64 + 4 => {
+                let rounded = round_significant(val, digits);
+                let exact = self.is_exact() && rounded == *val;
+                Number::Decimal {
+                    value: rounded,
+                    exact,
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    pub fn factorial(&self) -> Result<Number, String> {
+        match self.to_integer() {
+            Some(n) if n.is_negative() => Err(format!("Factorial is undefined for {}", self)),
+            Some(n) if self.is_exact() => {
+                let n = n
+                    .to_u64()
+                    .filter(|n| factorial_bits(*n) <= MAX_EXACT_BITS as f64)
+                    .ok_or_else(|| format!("{}! is too large to compute exactly", self))?;
+                Ok(Number::from_integer(range_product(2, n + 1)))
+            }
+            _ => Ok(Number::Float(crate::calc::builtin::factorial(
+                self.to_f64(),
+            ))),
+        }
+    }
+}
+
+/// Approximate size of `n!` in bits by Stirling's formula.
+fn factorial_bits(n: u64) -> f64 {
+    if n < 2 {
+        return 0.0;
+    }
+    let n = n as f64;
+    (n * n.ln() - n + 0.5 * (std::f64::consts::TAU * n).ln()) / std::f64::consts::LN_2
+}
+
+/// Product of `low..high`, split in halves so the factors stay balanced.
+fn range_product(low: u64, high: u64) -> BigInt {
+    if high <= low + 16 {
+        return (low..high).fold(BigInt::one(), |acc, i| acc * i);
+    }
+    let middle = low + (high - low) / 2;
+    range_product(low, middle) * range_product(middle, high)
+}
+
+fn rational_to_f64(val: &BigRational) -> f64 {
+    if let Some(result) = val.to_f64()
+        && result.is_finite()
+    {
+        return result;
+    }
+    // Scale both parts down so huge numerators and denominators still convert
+    let shift = val
+        .numer()
+        .bits()
+        .max(val.denom().bits())
+        .saturating_sub(1000);
+    let numer = (val.numer() >> shift).to_f64().unwrap_or(f64::NAN);
+    let denom = (val.denom() >> shift).to_f64().unwrap_or(f64::NAN);
+    numer / denom
+}
+
+/// Exact `n`-th root of a rational if both parts are perfect powers.
+fn exact_root(val: &BigRational, n: u32) -> Option<BigRational> {
+    let negative = val.is_negative();
+    if negative && n.is_multiple_of(2) {
+        return None;
+    }
+    let numer = val.numer().abs();
+    let denom = val.denom();
+    let numer_root = numer.nth_root(n);
+    let denom_root = denom.nth_root(n);
+    if numer_root.pow(n) != numer || denom_root.pow(n) != *denom {
+        return None;
+    }
+    let root = BigRational::new(numer_root, denom_root);
+    Some(if negative { -root } else { root })
+}
+
+fn rational_pow(base: &BigRational, exp: &BigRational) -> Option<BigRational> {
+    let numer = exp.numer().to_i64()?;
+    let denom = exp.denom().to_u32()?;
+    if denom > 64 {
+        return None;
+    }
+    let base = if denom == 1 {
+        base.clone()
+    } else {
+        exact_root(base, denom)?
+    };
+    if base.is_zero() {
+        return (numer >= 0).then(|| if numer == 0 { BigRational::one() } else { base });
+    }
+    let bits = base.numer().bits().max(base.denom().bits());
+    if bits.saturating_mul(numer.unsigned_abs()) > MAX_EXACT_BITS {
+        return None;
+    }
+    let result = num_traits::pow(base, numer.unsigned_abs() as usize);
+    Some(if numer < 0 { result.recip() } else { result })
+}
+
+/// Rounds half away from zero to `digits` significant digits.
+fn round_significant(val: &BigRational, digits: u32) -> BigRational {
+    if val.is_zero() {
+        return val.clone();
+    }
+    let magnitude = decimal_exponent(val);
+    let shift = digits as i64 - 1 - magnitude;
+    let scale = BigRational::from_integer(BigInt::from(10).pow(shift.unsigned_abs() as u32));
+    let scaled = if shift >= 0 {
+        val * &scale
+    } else {
+        val / &scale
+    };
+    let rounded = BigRational::from_integer(scaled.round().to_integer());
+    if shift >= 0 {
+        rounded / scale
+    } else {
+        rounded * scale
+    }
+}
+
+/// `floor(log10(|val|))` for a non-zero rational.
+fn decimal_exponent(val: &BigRational) -> i64 {
+    let abs = val.abs();
+    let estimate =
+        (abs.numer().bits() as f64 - abs.denom().bits() as f64) * std::f64::consts::LOG10_2;
+    let mut exponent = estimate.floor() as i64;
+    let ten = BigRational::from_integer(BigInt::from(10));
+    let power = |exp: i64| {
+        if exp >= 0 {
+            num_traits::pow(ten.clone(), exp as usize)
+        } else {
+            num_traits::pow(ten.clone(), (-exp) as usize).recip()
+        }
+    };
+    while power(exponent) > abs {
+        exponent -= 1;
+    }
+    while power(exponent + 1) <= abs {
+        exponent += 1;
+    }
+    exponent
+}
+
+fn format_integer(val: &BigInt) -> String {
+    let text = val.to_string();
+    let digits = text.trim_start_matches('-').len();
+    if digits <= 64 {
+        return text;
+    }
+    let sign = if val.sign() == Sign::Minus { "-" } else { "" };
+    let body = text.trim_start_matches('-');
+    format!(
+        "{}{}…{} ({} digits)",
+        sign,
+        &body[..24],
+        &body[body.len() - 12..],
+        digits
+    )
+}
+
+/// Decimal expansion of a terminating rational.
+fn format_decimal(val: &BigRational) -> String {
+    if val.is_integer() {
+        return format_integer(&val.to_integer());
+    }
+    let mut denom = val.denom().clone();
+    let mut twos = 0u32;
+    let mut fives = 0u32;
+    while denom.is_even() {
+        denom /= 2;
+        twos += 1;
+    }
+    while (&denom % 5u32).is_zero() {
+        denom /= 5;
+        fives += 1;
+    }
+    let places = twos.max(fives);
+    if !denom.is_one() || places > 400 {
+        return format_f64(rational_to_f64(val));
+    }
+    let ten = BigInt::from(10);
+    let scaled = (val * BigRational::from_integer(ten.pow(places))).to_integer();
+    let text = scaled.abs().to_string();
+    let text = format!("{:0>width$}", text, width = places as usize + 1);
+    let (int_part, frac_part) = text.split_at(text.len() - places as usize);
+    let sign = if val.is_negative() { "-" } else { "" };
+    format!("{}{}.{}", sign, int_part, frac_part.trim_end_matches('0'))
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Rational(val) if val.is_integer() => {
+                write!(f, "{}", format_integer(&val.to_integer()))
+            }
+            Number::Rational(val) => write!(
+                f,
+                "{}/{}",
+                format_integer(val.numer()),
+                format_integer(val.denom())
+            ),
+            Number::Decimal { value, .. } => write!(f, "{}", format_decimal(value)),
+            Number::Float(val) => write!(f, "{}", format_f64(*val)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_factorials() {
+        let factorial = |n: u64| Number::from_integer(n).factorial();
+        assert_eq!(factorial(0), Ok(Number::one()));
+        let expected = Number::from_integer(2_432_902_008_176_640_000u64);
+        assert_eq!(factorial(20), Ok(expected));
+        // Well past the f64 range and still exact
+        let large = factorial(30_000).unwrap();
+        assert!(large.is_exact());
+        assert!((large.bits() as f64 - factorial_bits(30_000)).abs() < 1.0);
+        assert_eq!(
+            factorial(100_000).unwrap_err(),
+            "100000! is too large to compute exactly"
+        );
+        let float = Number::Float(1e5).factorial().unwrap();
+        assert!(float.to_f64().is_infinite());
+    }
+}
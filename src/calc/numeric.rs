@@ -0,0 +1,877 @@
+use std::fmt;
+
+use crate::calc::{
+    ast::Expr,
+    value::{Value, format_f64},
+};
+
+const MAX_NEWTON_STEPS: usize = 64;
+const MAX_BRACKET_STEPS: usize = 64;
+/// Subintervals before the adaptive integration gives up on the tolerance.
+const MAX_SEGMENTS: usize = 2000;
+/// Samples per variable when looking for the global extremum before refining.
+const INTERVAL_SAMPLES: usize = 200;
+const REGION_SAMPLES: usize = 40;
+/// Adaptive ODE steps are capped so the trajectory has at least this many samples.
+const TRAJECTORY_SAMPLES: usize = 200;
+const MAX_ODE_STEPS: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumericMethod {
+    Root,
+    Integral,
+    Minimum,
+    Maximum,
+    Trajectory,
+}
+
+/// Outcome of a numerical method, kept with its inputs so it can be drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericResult {
+    pub method: NumericMethod,
+    /// The integrand or objective, `lhs - rhs` of the equations for roots
+    pub func: Expr,
+    pub vars: Vec<String>,
+    /// Integration interval or search region, one range per variable
+    pub bounds: Vec<(f64, f64)>,
+    /// The root, integral or extreme value, rows of `t` and the state for trajectories
+    pub value: Value,
+    /// Where the root or extremum was found, the final state of a trajectory
+    pub at: Vec<f64>,
+    /// Estimated absolute error of the root, integral, extremum location or final state
+    pub error: f64,
+}
+
+impl NumericResult {
+    fn fmt_at(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coords: Vec<String> = self
+            .vars
+            .iter()
+            .zip(&self.at)
+            .map(|(var, val)| format!("{} ≈ {}", var, format_f64(*val)))
+            .collect();
+        write!(f, "{} ± {}", coords.join(", "), format_error(self.error))
+    }
+
+    /// `x(10) ≈ 1, y(10) ≈ 2` for named states, `y(10) ≈ [1, 2]` for a vector state.
+    fn fmt_final_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let t1 = format_f64(self.bounds.first().map_or(0.0, |(_, t1)| *t1));
+        let values: Vec<String> = self.at.iter().map(|val| format_f64(*val)).collect();
+        let state: Vec<String> = if self.vars.len() == self.at.len() {
+            self.vars
+                .iter()
+                .zip(&values)
+                .map(|(var, val)| format!("{}({}) ≈ {}", var, t1, val))
+                .collect()
+        } else {
+            vec![format!(
+                "{}({}) ≈ [{}]",
+                self.vars[0],
+                t1,
+                values.join(", ")
+            )]
+        };
+        let steps = match &self.value {
+            Value::Matrix(samples) => samples.rows().saturating_sub(1),
+            _ => 0,
+        };
+        write!(
+            f,
+            "{} ± {} in {} steps",
+            state.join(", "),
+            format_error(self.error),
+            steps
+        )
+    }
+}
+
+fn format_error(error: f64) -> String {
+    if error == 0.0 {
+        "0".to_owned()
+    } else {
+        format!("{:.1e}", error)
+    }
+}
+
+/// `x ≈ 1.41421356237 ± 2.2e-16` for roots, `≈ 0.25 ± 2.8e-15` for integrals and
+/// `≈ -1 at x ≈ 3.14159265359 ± 1.5e-9` for extrema. Trajectories show their final state.
+impl fmt::Display for NumericResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.method {
+            NumericMethod::Root => self.fmt_at(f),
+            NumericMethod::Integral => write!(f, "≈ {} ± {}", self.value, format_error(self.error)),
+            NumericMethod::Minimum | NumericMethod::Maximum => {
+                write!(f, "≈ {} at ", self.value)?;
+                self.fmt_at(f)
+            }
+            NumericMethod::Trajectory => self.fmt_final_state(f),
+        }
+    }
+}
+
+/// Central difference with a step scaled to `x`.
+pub fn derivative(f: &impl Fn(f64) -> Result<f64, String>, x: f64) -> Result<f64, String> {
+    let h = f64::EPSILON.cbrt() * x.abs().max(1.0);
+    Ok((f(x + h)? - f(x - h)?) / (2.0 * h))
+}
+
+/// Newton's method from `guess`, falling back to Brent's method once a sign change
+/// is found. Returns the root and the size of the last step or bracket.
+pub fn find_root(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    df: &impl Fn(f64) -> Result<f64, String>,
+    guess: f64,
+) -> Result<(f64, f64), String> {
+    let mut x = guess;
+    let mut fx = f(x).unwrap_or(f64::NAN);
+    let mut bracket = None;
+    for _ in 0..MAX_NEWTON_STEPS {
+        if fx == 0.0 {
+            return Ok((x, 0.0));
+        }
+        // Leaving the domain or a flat spot hands over to the bracketing search
+        let next = match df(x) {
+            Ok(slope) => x - fx / slope,
+            Err(_) => break,
+        };
+        if !next.is_finite() {
+            break;
+        }
+        let f_next = f(next).unwrap_or(f64::NAN);
+        if !f_next.is_finite() {
+            break;
+        }
+        if fx.signum() != f_next.signum() {
+            bracket = Some((x, next));
+        }
+        let step = (next - x).abs();
+        (x, fx) = (next, f_next);
+        if step <= 4.0 * f64::EPSILON * x.abs().max(1.0) {
+            return Ok((x, step));
+        }
+    }
+    let (a, b) = match bracket {
+        Some(bracket) => bracket,
+        None => expand_bracket(f, guess)?,
+    };
+    find_root_in(f, a, b)
+}
+
+/// Walks outwards from `center` until the sign of `f` changes.
+fn expand_bracket(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    center: f64,
+) -> Result<(f64, f64), String> {
+    let mut step = 0.1 * center.abs().max(1.0);
+    let (mut left, mut right) = (center, center);
+    let f_center = f(center).unwrap_or(f64::NAN);
+    let (mut f_left, mut f_right) = (f_center, f_center);
+    for _ in 0..MAX_BRACKET_STEPS {
+        let (next_left, next_right) = (left - step, right + step);
+        // Points where the function is undefined are stepped over
+        let f_next = f(next_left).unwrap_or(f64::NAN);
+        if f_next.is_finite() {
+            if f_left.is_finite() && f_left.signum() != f_next.signum() {
+                return Ok((next_left, left));
+            }
+            f_left = f_next;
+        }
+        left = next_left;
+        let f_next = f(next_right).unwrap_or(f64::NAN);
+        if f_next.is_finite() {
+            if f_right.is_finite() && f_right.signum() != f_next.signum() {
+                return Ok((right, next_right));
+            }
+            f_right = f_next;
+        }
+        right = next_right;
+        step *= 1.5;
+    }
+    Err(format!("No root found near {}", format_f64(center)))
+}
+
+/// Brent's method on a bracket where `f` changes sign.
+pub fn find_root_in(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64), String> {
+    let (mut a, mut b) = (a, b);
+    let (mut fa, mut fb) = (f(a)?, f(b)?);
+    if fa == 0.0 {
+        return Ok((a, 0.0));
+    }
+    if fb == 0.0 {
+        return Ok((b, 0.0));
+    }
+    if fa.signum() == fb.signum() {
+        return Err(format!(
+            "No sign change between {} and {}",
+            format_f64(a),
+            format_f64(b)
+        ));
+    }
+    let scale = fa.abs().min(fb.abs());
+    let (mut c, mut fc) = (a, fa);
+    let mut d = b - a;
+    let mut e = d;
+    for _ in 0..200 {
+        if fb.signum() == fc.signum() {
+            (c, fc) = (a, fa);
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            (a, fa) = (b, fb);
+            (b, fb) = (c, fc);
+            (c, fc) = (a, fa);
+        }
+        let tol = 2.0 * f64::EPSILON * b.abs() + 1e-300;
+        let mid = (c - b) / 2.0;
+        if fb == 0.0 {
+            return Ok((b, 0.0));
+        }
+        if mid.abs() <= tol {
+            // A sign change that doesn't shrink the function is a pole like 1/x
+            if fb.abs() > scale.max(1.0) {
+                return Err(format!(
+                    "The sign change at {} is a discontinuity, not a root",
+                    format_f64(b)
+                ));
+            }
+            return Ok((b, mid.abs()));
+        }
+        if e.abs() >= tol && fa.abs() > fb.abs() {
+            // Secant or inverse quadratic interpolation
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * mid * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * mid * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+            if 2.0 * p < (3.0 * mid * q - (tol * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                d = mid;
+                e = d;
+            }
+        } else {
+            d = mid;
+            e = d;
+        }
+        (a, fa) = (b, fb);
+        b += if d.abs() > tol { d } else { tol.copysign(mid) };
+        fb = f(b).unwrap_or(f64::NAN);
+        if !fb.is_finite() {
+            return Err(format!("The function is undefined at {}", format_f64(b)));
+        }
+    }
+    Ok((b, (c - b).abs()))
+}
+
+/// Damped Newton's method for a square system, the Jacobian is taken numerically.
+/// Returns the solution and the size of the last step.
+pub fn find_root_system(
+    f: &impl Fn(&[f64]) -> Result<Vec<f64>, String>,
+    guess: &[f64],
+) -> Result<(Vec<f64>, f64), String> {
+    let n = guess.len();
+    let mut x = guess.to_vec();
+    let mut fx = f(&x)?;
+    if fx.len() != n {
+        return Err(format!(
+            "nsolve needs as many equations as variables, got {} and {}",
+            fx.len(),
+            n
+        ));
+    }
+    for _ in 0..MAX_NEWTON_STEPS * 2 {
+        let residual = norm(&fx);
+        if residual == 0.0 {
+            return Ok((x, 0.0));
+        }
+        let mut jacobian = vec![vec![0.0; n]; n];
+        for col in 0..n {
+            let h = f64::EPSILON.sqrt() * x[col].abs().max(1.0);
+            let mut shifted = x.clone();
+            shifted[col] += h;
+            let f_shifted = f(&shifted)?;
+            for (row, val) in f_shifted.iter().enumerate() {
+                jacobian[row][col] = (val - fx[row]) / h;
+            }
+        }
+        let rhs: Vec<f64> = fx.iter().map(|val| -val).collect();
+        let Some(step) = solve_linear(jacobian, rhs) else {
+            return Err("The Jacobian is singular, try another initial guess".to_owned());
+        };
+
+        // Halve the step until the residual shrinks
+        let mut scale = 1.0;
+        loop {
+            let next: Vec<f64> = x.iter().zip(&step).map(|(x, dx)| x + scale * dx).collect();
+            let f_next = f(&next)
+                .ok()
+                .filter(|vals| vals.iter().all(|val| val.is_finite()));
+            if let Some(f_next) = f_next
+                && (norm(&f_next) < residual || scale < 1e-3)
+            {
+                let step_size = scale * norm(&step);
+                (x, fx) = (next, f_next);
+                if step_size <= 4.0 * f64::EPSILON * norm(&x).max(1.0) {
+                    return Ok((x, step_size));
+                }
+                break;
+            }
+            scale /= 2.0;
+            if scale < 1e-10 {
+                return Err("nsolve did not converge, try another initial guess".to_owned());
+            }
+        }
+    }
+    Err("nsolve did not converge, try another initial guess".to_owned())
+}
+
+fn norm(vals: &[f64]) -> f64 {
+    vals.iter().map(|val| val * val).sum::<f64>().sqrt()
+}
+
+/// Gaussian elimination with partial pivoting, `None` for singular matrices.
+fn solve_linear(mut rows: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|a, b| rows[*a][col].abs().total_cmp(&rows[*b][col].abs()))?;
+        if rows[pivot][col].abs() < 1e-300 {
+            return None;
+        }
+        rows.swap(col, pivot);
+        rhs.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = rows[row][col] / rows[col][col];
+            let pivot_row = rows[col].clone();
+            for (val, pivot_val) in rows[row].iter_mut().zip(&pivot_row).skip(col) {
+                *val -= factor * pivot_val;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut solution = vec![0.0; n];
+    for row in (0..n).rev() {
+        let known: f64 = (row + 1..n).map(|col| rows[row][col] * solution[col]).sum();
+        solution[row] = (rhs[row] - known) / rows[row][row];
+    }
+    Some(solution)
+}
+
+/// 7 point Gauss and 15 point Kronrod nodes on [0, 1], the negatives are implied.
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6,
+    0.949_107_912_342_758_5,
+    0.864_864_423_359_769_1,
+    0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1,
+    0.405_845_151_377_397_2,
+    0.207_784_955_007_898_5,
+    0.0,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22,
+    0.063_092_092_629_978_55,
+    0.104_790_010_322_250_2,
+    0.140_653_259_715_525_9,
+    0.169_004_726_639_267_9,
+    0.190_350_578_064_785_4,
+    0.204_432_940_075_298_9,
+    0.209_482_141_084_727_8,
+];
+/// Weights of the Gauss nodes, which are the odd Kronrod nodes.
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7,
+    0.279_705_391_489_276_7,
+    0.381_830_050_505_118_9,
+    0.417_959_183_673_469_4,
+];
+
+/// Integral over one segment and the difference to the embedded Gauss rule.
+fn gauss_kronrod(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64), String> {
+    let center = (a + b) / 2.0;
+    let half = (b - a) / 2.0;
+    let (mut kronrod, mut gauss) = (0.0, 0.0);
+    for (i, (node, weight)) in KRONROD_NODES.iter().zip(KRONROD_WEIGHTS).enumerate() {
+        let sum = if *node == 0.0 {
+            f(center)?
+        } else {
+            f(center - half * node)? + f(center + half * node)?
+        };
+        if !sum.is_finite() {
+            return Err(format!(
+                "The integrand is not finite near {}",
+                format_f64(center + half * node)
+            ));
+        }
+        kronrod += weight * sum;
+        if i % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[i / 2] * sum;
+        }
+    }
+    Ok((kronrod * half, ((kronrod - gauss) * half).abs()))
+}
+
+/// Adaptive Gauss–Kronrod quadrature, infinite bounds are mapped onto a finite
+/// interval. Returns the integral and its estimated error.
+pub fn integrate(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64), String> {
+    if a == b {
+        return Ok((0.0, 0.0));
+    }
+    if a > b {
+        return integrate(f, b, a).map(|(val, err)| (-val, err));
+    }
+    match (a.is_finite(), b.is_finite()) {
+        (true, true) => integrate_finite(f, a, b),
+        (true, false) => integrate_finite(
+            &|t: f64| f(a + t / (1.0 - t)).map(|val| val / ((1.0 - t) * (1.0 - t))),
+            0.0,
+            1.0,
+        ),
+        (false, true) => integrate_finite(
+            &|t: f64| f(b - t / (1.0 - t)).map(|val| val / ((1.0 - t) * (1.0 - t))),
+            0.0,
+            1.0,
+        ),
+        (false, false) => integrate_finite(
+            &|t: f64| {
+                let d = 1.0 - t * t;
+                f(t / d).map(|val| val * (1.0 + t * t) / (d * d))
+            },
+            -1.0,
+            1.0,
+        ),
+    }
+}
+
+fn integrate_finite(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64), String> {
+    let (val, err) = gauss_kronrod(f, a, b)?;
+    let mut segments = vec![(a, b, val, err)];
+    loop {
+        let total: f64 = segments.iter().map(|segment| segment.2).sum();
+        let error: f64 = segments.iter().map(|segment| segment.3).sum();
+        if error <= 1e-12_f64.max(1e-10 * total.abs()) || segments.len() >= MAX_SEGMENTS {
+            return Ok((total, error));
+        }
+        // Split the segment with the largest error
+        let worst = (0..segments.len())
+            .max_by(|i, j| segments[*i].3.total_cmp(&segments[*j].3))
+            .unwrap_or(0);
+        let (a, b, _, worst_error) = segments.swap_remove(worst);
+        let mid = (a + b) / 2.0;
+        if mid <= a || mid >= b {
+            // Out of floating point resolution, keep what we have
+            segments.push((a, b, 0.0, worst_error));
+            let total: f64 = segments.iter().map(|segment| segment.2).sum();
+            return Ok((total, error));
+        }
+        let (left, left_error) = gauss_kronrod(f, a, mid)?;
+        let (right, right_error) = gauss_kronrod(f, mid, b)?;
+        segments.push((a, mid, left, left_error));
+        segments.push((mid, b, right, right_error));
+    }
+}
+
+/// Global minimum on `a..b`, found by sampling and refined by golden section search.
+/// Returns the location, the value and the uncertainty of the location.
+pub fn minimize_interval(
+    f: &impl Fn(f64) -> Result<f64, String>,
+    a: f64,
+    b: f64,
+) -> Result<(f64, f64, f64), String> {
+    let (a, b) = (a.min(b), a.max(b));
+    let sample = |i: usize| a + (b - a) * i as f64 / INTERVAL_SAMPLES as f64;
+    let mut best = None;
+    for i in 0..=INTERVAL_SAMPLES {
+        let val = f(sample(i)).unwrap_or(f64::NAN);
+        if val.is_finite() && best.is_none_or(|(_, best_val)| val < best_val) {
+            best = Some((i, val));
+        }
+    }
+    let Some((best, _)) = best else {
+        return Err("The function is undefined on the whole interval".to_owned());
+    };
+    let value = |x: f64| f(x).map(|val| if val.is_finite() { val } else { f64::INFINITY });
+    let (mut lo, mut hi) = (
+        sample(best.saturating_sub(1)),
+        sample((best + 1).min(INTERVAL_SAMPLES)),
+    );
+
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut x1 = hi - ratio * (hi - lo);
+    let mut x2 = lo + ratio * (hi - lo);
+    let (mut f1, mut f2) = (value(x1)?, value(x2)?);
+    while hi - lo > 2.0 * f64::EPSILON.sqrt() * lo.abs().max(hi.abs()).max(1e-3) {
+        if f1 < f2 {
+            (hi, x2, f2) = (x2, x1, f1);
+            x1 = hi - ratio * (hi - lo);
+            f1 = value(x1)?;
+        } else {
+            (lo, x1, f1) = (x1, x2, f2);
+            x2 = lo + ratio * (hi - lo);
+            f2 = value(x2)?;
+        }
+    }
+    let x = (lo + hi) / 2.0;
+    // The minimum may sit on a bound the search only approaches
+    let candidates = [(x, value(x)?), (a, value(a)?), (b, value(b)?)];
+    let (x, val) = candidates
+        .into_iter()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap_or((x, f64::INFINITY));
+    Ok((x, val, (hi - lo) / 2.0))
+}
+
+/// Global minimum over a rectangle, found on a grid and refined by the Nelder–Mead
+/// method with points clamped to the region. Returns the location, the value and
+/// the size of the final simplex.
+pub fn minimize_region(
+    f: &impl Fn(&[f64]) -> Result<f64, String>,
+    bounds: [(f64, f64); 2],
+) -> Result<([f64; 2], f64, f64), String> {
+    let bounds = bounds.map(|(lo, hi)| (lo.min(hi), lo.max(hi)));
+    let clamp = |p: [f64; 2]| [0, 1].map(|i| p[i].clamp(bounds[i].0, bounds[i].1));
+    let value =
+        |p: [f64; 2]| f(&clamp(p)).map(|val| if val.is_finite() { val } else { f64::INFINITY });
+    let cell = bounds.map(|(lo, hi)| (hi - lo) / REGION_SAMPLES as f64);
+
+    let mut best: Option<([f64; 2], f64)> = None;
+    for row in 0..=REGION_SAMPLES {
+        for col in 0..=REGION_SAMPLES {
+            let p = [
+                bounds[0].0 + cell[0] * col as f64,
+                bounds[1].0 + cell[1] * row as f64,
+            ];
+            let val = f(&p).unwrap_or(f64::NAN);
+            if val.is_finite() && best.is_none_or(|(_, best_val)| val < best_val) {
+                best = Some((p, val));
+            }
+        }
+    }
+    let Some((start, start_val)) = best else {
+        return Err("The function is undefined on the whole region".to_owned());
+    };
+
+    let mut simplex = vec![
+        (start, start_val),
+        (clamp([start[0] + cell[0], start[1]]), 0.0),
+        (clamp([start[0], start[1] + cell[1]]), 0.0),
+    ];
+    for vertex in &mut simplex[1..] {
+        vertex.1 = value(vertex.0)?;
+    }
+    let size = |simplex: &[([f64; 2], f64)]| {
+        simplex[1..]
+            .iter()
+            .map(|(p, _)| (p[0] - simplex[0].0[0]).hypot(p[1] - simplex[0].0[1]))
+            .fold(0.0, f64::max)
+    };
+    let scale = cell[0].hypot(cell[1]).max(f64::MIN_POSITIVE);
+    for _ in 0..2000 {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if size(&simplex) <= 1e-10 * scale * REGION_SAMPLES as f64 {
+            break;
+        }
+        let centroid = [0, 1].map(|i| (simplex[0].0[i] + simplex[1].0[i]) / 2.0);
+        let along =
+            |t: f64| clamp([0, 1].map(|i| centroid[i] + t * (simplex[2].0[i] - centroid[i])));
+        let reflected = along(-1.0);
+        let reflected_val = value(reflected)?;
+        if reflected_val < simplex[0].1 {
+            let expanded = along(-2.0);
+            let expanded_val = value(expanded)?;
+            simplex[2] = if expanded_val < reflected_val {
+                (expanded, expanded_val)
+            } else {
+                (reflected, reflected_val)
+            };
+        } else if reflected_val < simplex[1].1 {
+            simplex[2] = (reflected, reflected_val);
+        } else {
+            let contracted = along(0.5);
+            let contracted_val = value(contracted)?;
+            if contracted_val < simplex[2].1 {
+                simplex[2] = (contracted, contracted_val);
+            } else {
+                // Shrink towards the best point
+                let best = simplex[0].0;
+                for vertex in &mut simplex[1..] {
+                    vertex.0 = [0, 1].map(|i| (vertex.0[i] + best[i]) / 2.0);
+                    vertex.1 = value(vertex.0)?;
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    Ok((simplex[0].0, simplex[0].1, size(&simplex)))
+}
+
+/// Stages of an explicit Runge–Kutta method as `(c, a)` rows of the Butcher tableau.
+type Tableau<'a> = [(f64, &'a [f64])];
+
+const RK4: [(f64, &[f64]); 4] = [
+    (0.0, &[]),
+    (0.5, &[0.5]),
+    (0.5, &[0.0, 0.5]),
+    (1.0, &[0.0, 0.0, 1.0]),
+];
+const RK4_WEIGHTS: [f64; 4] = [1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0];
+
+const DORMAND_PRINCE: [(f64, &[f64]); 7] = [
+    (0.0, &[]),
+    (1.0 / 5.0, &[1.0 / 5.0]),
+    (3.0 / 10.0, &[3.0 / 40.0, 9.0 / 40.0]),
+    (4.0 / 5.0, &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0]),
+    (
+        8.0 / 9.0,
+        &[
+            19372.0 / 6561.0,
+            -25360.0 / 2187.0,
+            64448.0 / 6561.0,
+            -212.0 / 729.0,
+        ],
+    ),
+    (
+        1.0,
+        &[
+            9017.0 / 3168.0,
+            -355.0 / 33.0,
+            46732.0 / 5247.0,
+            49.0 / 176.0,
+            -5103.0 / 18656.0,
+        ],
+    ),
+    (
+        1.0,
+        &[
+            35.0 / 384.0,
+            0.0,
+            500.0 / 1113.0,
+            125.0 / 192.0,
+            -2187.0 / 6784.0,
+            11.0 / 84.0,
+        ],
+    ),
+];
+/// Fifth order weights, which advance the solution.
+const DORMAND_PRINCE_WEIGHTS: [f64; 7] = [
+    35.0 / 384.0,
+    0.0,
+    500.0 / 1113.0,
+    125.0 / 192.0,
+    -2187.0 / 6784.0,
+    11.0 / 84.0,
+    0.0,
+];
+/// Fifth minus the embedded fourth order weights, the local error estimate.
+const DORMAND_PRINCE_ERROR: [f64; 7] = [
+    35.0 / 384.0 - 5179.0 / 57600.0,
+    0.0,
+    500.0 / 1113.0 - 7571.0 / 16695.0,
+    125.0 / 192.0 - 393.0 / 640.0,
+    -2187.0 / 6784.0 + 92097.0 / 339200.0,
+    11.0 / 84.0 - 187.0 / 2100.0,
+    -1.0 / 40.0,
+];
+
+fn stages(
+    f: &impl Fn(f64, &[f64]) -> Result<Vec<f64>, String>,
+    t: f64,
+    y: &[f64],
+    h: f64,
+    tableau: &Tableau,
+) -> Result<Vec<Vec<f64>>, String> {
+    let mut slopes: Vec<Vec<f64>> = Vec::with_capacity(tableau.len());
+    for (c, a) in tableau {
+        let point = combine(y, h, a, &slopes);
+        let slope = f(t + c * h, &point)?;
+        if slope.len() != y.len() {
+            return Err(format!(
+                "The derivative has {} component(s) but the state has {}",
+                slope.len(),
+                y.len()
+            ));
+        }
+        slopes.push(slope);
+    }
+    Ok(slopes)
+}
+
+/// `y + h * sum(weight * slope)`.
+fn combine(y: &[f64], h: f64, weights: &[f64], slopes: &[Vec<f64>]) -> Vec<f64> {
+    y.iter()
+        .enumerate()
+        .map(|(i, y)| {
+            let slope: f64 = weights
+                .iter()
+                .zip(slopes)
+                .map(|(weight, slope)| weight * slope[i])
+                .sum();
+            y + h * slope
+        })
+        .collect()
+}
+
+/// Integrates `y' = f(t, y)` from `t0` to `t1`. A fixed `step` uses the classic
+/// Runge–Kutta method with the error estimated against twice the step, otherwise the
+/// Dormand–Prince pair adapts the step. Returns rows of `t` followed by the state and
+/// the estimated error of the final state.
+pub fn solve_ode(
+    f: &impl Fn(f64, &[f64]) -> Result<Vec<f64>, String>,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+    step: Option<f64>,
+) -> Result<(Vec<Vec<f64>>, f64), String> {
+    if !t0.is_finite() || !t1.is_finite() {
+        return Err("The time span of an ODE must be finite".to_owned());
+    }
+    match step {
+        Some(step) => {
+            let steps = ((t1 - t0) / step).abs().ceil();
+            if !(1.0..=MAX_ODE_STEPS as f64).contains(&steps) {
+                return Err(format!(
+                    "A step of {} doesn't fit the time span",
+                    format_f64(step)
+                ));
+            }
+            let samples = runge_kutta(f, y0, t0, t1, steps as usize)?;
+            let coarse = runge_kutta(f, y0, t0, t1, (steps as usize).div_ceil(2))?;
+            let (fine_end, coarse_end) = (&samples[samples.len() - 1], &coarse[coarse.len() - 1]);
+            // Halving the step of a fourth order method shrinks the error 16 times
+            let error = fine_end[1..]
+                .iter()
+                .zip(&coarse_end[1..])
+                .map(|(fine, coarse)| (fine - coarse).abs() / 15.0)
+                .fold(0.0, f64::max);
+            Ok((samples, error))
+        }
+        None => dormand_prince(f, y0, t0, t1),
+    }
+}
+
+fn sample_row(t: f64, y: &[f64]) -> Vec<f64> {
+    std::iter::once(t).chain(y.iter().copied()).collect()
+}
+
+fn check_finite(t: f64, y: &[f64]) -> Result<(), String> {
+    if y.iter().all(|val| val.is_finite()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "The solution is not finite at t = {}",
+            format_f64(t)
+        ))
+    }
+}
+
+fn runge_kutta(
+    f: &impl Fn(f64, &[f64]) -> Result<Vec<f64>, String>,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+    steps: usize,
+) -> Result<Vec<Vec<f64>>, String> {
+    let h = (t1 - t0) / steps as f64;
+    let mut y = y0.to_vec();
+    let mut samples = vec![sample_row(t0, &y)];
+    for i in 0..steps {
+        let t = t0 + h * i as f64;
+        let slopes = stages(f, t, &y, h, &RK4)?;
+        y = combine(&y, h, &RK4_WEIGHTS, &slopes);
+        check_finite(t + h, &y)?;
+        samples.push(sample_row(t + h, &y));
+    }
+    Ok(samples)
+}
+
+fn dormand_prince(
+    f: &impl Fn(f64, &[f64]) -> Result<Vec<f64>, String>,
+    y0: &[f64],
+    t0: f64,
+    t1: f64,
+) -> Result<(Vec<Vec<f64>>, f64), String> {
+    const RELATIVE_TOLERANCE: f64 = 1e-9;
+    const ABSOLUTE_TOLERANCE: f64 = 1e-12;
+
+    let direction = (t1 - t0).signum();
+    let max_step = (t1 - t0).abs() / TRAJECTORY_SAMPLES as f64;
+    let mut h = max_step / 10.0;
+    let (mut t, mut y) = (t0, y0.to_vec());
+    let mut samples = vec![sample_row(t, &y)];
+    let mut error = 0.0;
+    for _ in 0..MAX_ODE_STEPS {
+        let remaining = (t1 - t) * direction;
+        if remaining <= 0.0 {
+            return Ok((samples, error));
+        }
+        h = h.min(remaining);
+        let signed = h * direction;
+        let slopes = stages(f, t, &y, signed, &DORMAND_PRINCE)?;
+        let next = combine(&y, signed, &DORMAND_PRINCE_WEIGHTS, &slopes);
+        let local = combine(&vec![0.0; y.len()], signed, &DORMAND_PRINCE_ERROR, &slopes);
+        let ratio = local
+            .iter()
+            .zip(y.iter().zip(&next))
+            .map(|(err, (y, next))| {
+                err.abs() / (ABSOLUTE_TOLERANCE + RELATIVE_TOLERANCE * y.abs().max(next.abs()))
+            })
+            .fold(0.0, f64::max);
+        if ratio.is_nan() {
+            return Err(format!(
+                "The solution is not finite at t = {}",
+                format_f64(t)
+            ));
+        }
+        if ratio <= 1.0 {
+            // The last step lands exactly on the end
+            t = if h == remaining { t1 } else { t + signed };
+            y = next;
+            check_finite(t, &y)?;
+            error += local.iter().map(|err| err.abs()).fold(0.0, f64::max);
+            samples.push(sample_row(t, &y));
+        }
+        let factor = if ratio == 0.0 {
+            5.0
+        } else {
+            (0.9 * ratio.powf(-0.2)).clamp(0.2, 5.0)
+        };
+        h = (h * factor).min(max_step);
+        if h <= f64::EPSILON * t.abs().max(1.0) {
+            return Err(format!(
+                "The step size vanished at t = {}, the solution may blow up",
+                format_f64(t)
+            ));
+        }
+    }
+    Err(format!(
+        "The ODE needs more than {} steps, it may be stiff",
+        MAX_ODE_STEPS
+    ))
+}
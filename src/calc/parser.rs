@@ -0,0 +1,434 @@
+use crate::calc::{
+    ast::{
+        BinaryOp, ConditionOp, Expr, ExprKind, Statement, UNARY_PRECEDENCE, UNIT_PRECEDENCE,
+        UnaryOp,
+    },
+    error::CalcError,
+    token::{Token, TokenKind, tokenize},
+    unit::is_unit,
+};
+
+pub fn parse(input: &str) -> Result<Expr, CalcError> {
+    parse_with(input, &no_variables)
+}
+
+/// Parses with the names `is_variable` accepts bound to values, they are not read
+/// as units, so `1/2 m` after `m = 2` is `(1/2) m`.
+pub fn parse_with(input: &str, is_variable: &dyn Fn(&str) -> bool) -> Result<Expr, CalcError> {
+    let mut parser = Parser::new(tokenize(input)?);
+    parser.is_variable = is_variable;
+    let expr = parser.parse_equation()?;
+    parser.expect_eof()?;
+    Ok(expr)
+}
+
+pub fn parse_statement(input: &str) -> Result<Statement, CalcError> {
+    Ok(Statement::from_expr(parse(input)?))
+}
+
+fn no_variables(_: &str) -> bool {
+    false
+}
+
+pub struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    is_variable: &'a dyn Fn(&str) -> bool,
+    /// Parameters of the function being defined, which shadow units in its body
+    params: Vec<String>,
+}
+
+impl Parser<'_> {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            is_variable: &no_variables,
+            params: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, CalcError> {
+        let token = self.peek().clone();
+        if token.kind != kind {
+            return Err(CalcError::new(
+                format!("Expected {}, found {}", kind, token.kind),
+                token.span,
+            ));
+        }
+        Ok(self.advance())
+    }
+
+    pub fn expect_eof(&mut self) -> Result<(), CalcError> {
+        let token = self.peek();
+        if token.kind != TokenKind::Eof {
+            return Err(CalcError::new(
+                format!("Unexpected {}", token.kind),
+                token.span,
+            ));
+        }
+        Ok(())
+    }
+
+    /// An expression optionally followed by `= expression`.
+    pub fn parse_equation(&mut self) -> Result<Expr, CalcError> {
+        let lhs = self.parse_or()?;
+        if self.peek().kind != TokenKind::Equal {
+            return Ok(lhs);
+        }
+        self.advance();
+        if let ExprKind::Call(_, args) = &lhs.kind {
+            self.params = args
+                .iter()
+                .filter_map(|arg| match &arg.kind {
+                    ExprKind::Ident(param) => Some(param.clone()),
+                    _ => None,
+                })
+                .collect();
+        }
+        let rhs = self.parse_or()?;
+        let span = lhs.span.join(rhs.span);
+        Ok(Expr::new(
+            ExprKind::Equation(Box::new(lhs), Box::new(rhs)),
+            span,
+        ))
+    }
+
+    /// Conditions joined by `or`, which binds looser than `and`.
+    fn parse_or(&mut self) -> Result<Expr, CalcError> {
+        let mut lhs = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.advance();
+            let rhs = self.parse_and()?;
+            lhs = condition(ConditionOp::Or, lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, CalcError> {
+        let mut lhs = self.parse_comparison()?;
+        while self.peek_keyword("and") {
+            self.advance();
+            let rhs = self.parse_comparison()?;
+            lhs = condition(ConditionOp::And, lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    /// `a < b`, a chain like `0 < x <= 1` means `0 < x and x <= 1`.
+    fn parse_comparison(&mut self) -> Result<Expr, CalcError> {
+        let mut lhs = self.parse_conversion()?;
+        let mut chain: Option<Expr> = None;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Less => ConditionOp::Less,
+                TokenKind::LessEqual => ConditionOp::LessEqual,
+                TokenKind::Greater => ConditionOp::Greater,
+                TokenKind::GreaterEqual => ConditionOp::GreaterEqual,
+                _ => break,
+            };
+            self.advance();
+            let rhs = self.parse_conversion()?;
+            let comparison = condition(op, lhs, rhs.clone());
+            chain = Some(match chain {
+                Some(chain) => condition(ConditionOp::And, chain, comparison),
+                None => comparison,
+            });
+            lhs = rhs;
+        }
+        Ok(chain.unwrap_or(lhs))
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(name) if name == keyword)
+    }
+
+    /// `3 km to m`, the conversion binds looser than any operator.
+    fn parse_conversion(&mut self) -> Result<Expr, CalcError> {
+        let value = self.parse_expr(0)?;
+        if !matches!(&self.peek().kind, TokenKind::Ident(name) if name == "to") {
+            return Ok(value);
+        }
+        self.advance();
+        let target = self.parse_expr(0)?;
+        let span = value.span.join(target.span);
+        Ok(Expr::new(
+            ExprKind::Call("to".to_owned(), vec![value, target]),
+            span,
+        ))
+    }
+
+    /// Precedence climbing, only binds operators with precedence of at least `min_precedence`.
+    pub fn parse_expr(&mut self, min_precedence: u8) -> Result<Expr, CalcError> {
+        let mut lhs = self.parse_prefix()?;
+
+        loop {
+            let (op, implicit) = match &self.peek().kind {
+                TokenKind::Plus => (BinaryOp::Add, false),
+                TokenKind::Minus => (BinaryOp::Sub, false),
+                TokenKind::Star => (BinaryOp::Mul, false),
+                TokenKind::Slash => (BinaryOp::Div, false),
+                TokenKind::Caret => (BinaryOp::Pow, false),
+                TokenKind::Ident(name) if matches!(name.as_str(), "to" | "and" | "or") => break,
+                // Implicit multiplication, `2x`, `3(x + 1)`, `(a)(b)`, `A [1, 2]`
+                TokenKind::Ident(_) | TokenKind::LParen | TokenKind::LBracket => {
+                    (BinaryOp::Mul, true)
+                }
+                _ => break,
+            };
+
+            // `20 min` in `3 km / 20 min` is a single operand
+            let literal = matches!(lhs.kind, ExprKind::Number(_));
+            let precedence = if implicit && literal && self.is_unit_operand() {
+                UNIT_PRECEDENCE
+            } else {
+                op.precedence()
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            if !implicit {
+                self.advance();
+            }
+
+            let next_min = if op.is_right_assoc() {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let rhs = self.parse_expr(next_min)?;
+            let span = lhs.span.join(rhs.span);
+            lhs = Expr::new(ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), span);
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expr, CalcError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Minus => {
+                self.advance();
+                let operand = self.parse_expr(UNARY_PRECEDENCE)?;
+                let span = token.span.join(operand.span);
+                Ok(Expr::new(
+                    ExprKind::Unary(UnaryOp::Neg, Box::new(operand)),
+                    span,
+                ))
+            }
+            TokenKind::Plus => {
+                self.advance();
+                self.parse_expr(UNARY_PRECEDENCE)
+            }
+            TokenKind::Ident(ref name) if name == "d" => match self.derivative_variable() {
+                Some(var) => {
+                    let var_span = self.tokens[self.pos + 2].span;
+                    self.pos += 3;
+                    let operand = self.parse_expr(BinaryOp::Mul.precedence())?;
+                    let var = Expr::new(ExprKind::Ident(var), var_span);
+                    let span = token.span.join(operand.span);
+                    Ok(Expr::new(
+                        ExprKind::Call("diff".to_owned(), vec![operand, var]),
+                        span,
+                    ))
+                }
+                None => self.parse_postfix(),
+            },
+            _ => self.parse_postfix(),
+        }
+    }
+
+    /// Whether the next token is a unit symbol rather than a call like `min(1, 2)`
+    /// or a variable or parameter named like a unit.
+    fn is_unit_operand(&self) -> bool {
+        let TokenKind::Ident(name) = &self.peek().kind else {
+            return false;
+        };
+        let called = self
+            .tokens
+            .get(self.pos + 1)
+            .is_some_and(|next| next.kind == TokenKind::LParen);
+        let shadowed = (self.is_variable)(name) || self.params.contains(name);
+        !called && !shadowed && is_unit(name)
+    }
+
+    /// Recognises the `d/dx` prefix operator and returns the variable name.
+    fn derivative_variable(&self) -> Option<String> {
+        let slash = self.tokens.get(self.pos + 1)?;
+        let var = self.tokens.get(self.pos + 2)?;
+        let next = self.tokens.get(self.pos + 3)?;
+        if slash.kind != TokenKind::Slash {
+            return None;
+        }
+        let TokenKind::Ident(var) = &var.kind else {
+            return None;
+        };
+        let var = var.strip_prefix('d').filter(|var| !var.is_empty())?;
+        match next.kind {
+            TokenKind::Number(_) | TokenKind::Ident(_) | TokenKind::LParen | TokenKind::Minus => {
+                Some(var.to_owned())
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.parse_primary()?;
+        while self.peek().kind == TokenKind::Bang {
+            let bang = self.advance();
+            let span = expr.span.join(bang.span);
+            expr = Expr::new(ExprKind::Unary(UnaryOp::Factorial, Box::new(expr)), span);
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, CalcError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Number(val) => Ok(Expr::new(ExprKind::Number(val), token.span)),
+            TokenKind::Ident(name) => {
+                if self.peek().kind != TokenKind::LParen {
+                    return Ok(Expr::new(ExprKind::Ident(name), token.span));
+                }
+                self.advance();
+                let args = self.parse_items(TokenKind::RParen)?;
+                let close = self.expect(TokenKind::RParen)?;
+                Ok(Expr::new(
+                    ExprKind::Call(name, args),
+                    token.span.join(close.span),
+                ))
+            }
+            TokenKind::LParen => {
+                let mut inner = self.parse_or()?;
+                let close = self.expect(TokenKind::RParen)?;
+                inner.span = token.span.join(close.span);
+                Ok(inner)
+            }
+            TokenKind::LBracket => {
+                let items = self.parse_items(TokenKind::RBracket)?;
+                let close = self.expect(TokenKind::RBracket)?;
+                Ok(Expr::new(
+                    ExprKind::Vector(items),
+                    token.span.join(close.span),
+                ))
+            }
+            TokenKind::Eof => Err(CalcError::new("Unexpected end of input", token.span)),
+            kind => Err(CalcError::new(format!("Unexpected {}", kind), token.span)),
+        }
+    }
+
+    /// Comma separated expressions up to, but not including, `close`. A range `a..b`
+    /// is shorthand for the interval `[a, b]`.
+    fn parse_items(&mut self, close: TokenKind) -> Result<Vec<Expr>, CalcError> {
+        let mut args = Vec::new();
+        if self.peek().kind == close {
+            return Ok(args);
+        }
+        loop {
+            let item = self.parse_equation()?;
+            if self.peek().kind == TokenKind::DotDot {
+                self.advance();
+                let end = self.parse_equation()?;
+                let span = item.span.join(end.span);
+                args.push(Expr::new(ExprKind::Vector(vec![item, end]), span));
+            } else {
+                args.push(item);
+            }
+            if self.peek().kind != TokenKind::Comma {
+                break;
+            }
+            self.advance();
+        }
+        Ok(args)
+    }
+}
+
+fn condition(op: ConditionOp, lhs: Expr, rhs: Expr) -> Expr {
+    let span = lhs.span.join(rhs.span);
+    Expr::new(ExprKind::Condition(op, Box::new(lhs), Box::new(rhs)), span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::token::Span;
+
+    fn binary(expr: &Expr) -> (BinaryOp, &Expr, &Expr) {
+        match &expr.kind {
+            ExprKind::Binary(op, lhs, rhs) => (*op, lhs, rhs),
+            kind => panic!("expected a binary operation, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let expr = parse("1 + 2 * 3 ^ 4").unwrap();
+        let (op, _, rhs) = binary(&expr);
+        assert_eq!(op, BinaryOp::Add);
+        let (op, _, rhs) = binary(rhs);
+        assert_eq!(op, BinaryOp::Mul);
+        assert_eq!(binary(rhs).0, BinaryOp::Pow);
+
+        let expr = parse("1 - 2 - 3").unwrap();
+        let (op, lhs, _) = binary(&expr);
+        assert_eq!(op, BinaryOp::Sub);
+        assert_eq!(binary(lhs).0, BinaryOp::Sub);
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        for input in ["2x", "2(x + 1)", "(x)(y)", "2 sin(x)"] {
+            let expr = parse(input).unwrap();
+            assert_eq!(binary(&expr).0, BinaryOp::Mul, "{}", input);
+        }
+        // A name followed by parentheses is a call, not a product
+        assert!(matches!(parse("f(x)").unwrap().kind, ExprKind::Call(..)));
+    }
+
+    #[test]
+    fn units_bind_to_a_preceding_number() {
+        // `3 km / 20 min` divides by the whole duration
+        let expr = parse("3 km / 20 min").unwrap();
+        let (op, _, rhs) = binary(&expr);
+        assert_eq!(op, BinaryOp::Div);
+        assert_eq!(binary(rhs).0, BinaryOp::Mul);
+
+        let expr = parse_with("1/2 m", &|name| name == "m").unwrap();
+        assert_eq!(binary(&expr).0, BinaryOp::Mul);
+        let expr = parse("f(m) = 1/2 m").unwrap();
+        let ExprKind::Equation(_, body) = &expr.kind else {
+            panic!("expected a definition");
+        };
+        assert_eq!(binary(body).0, BinaryOp::Mul);
+    }
+
+    #[test]
+    fn statements() {
+        let assign = Statement::from_expr(parse("a = 2").unwrap());
+        assert!(matches!(assign, Statement::Assign(name, _) if name == "a"));
+        let function = Statement::from_expr(parse("f(x, y) = x y").unwrap());
+        assert!(matches!(
+            function,
+            Statement::Function { name, params, .. } if name == "f" && params == ["x", "y"]
+        ));
+    }
+
+    #[test]
+    fn errors_point_at_the_input() {
+        let err = parse("1 + * 2").unwrap_err();
+        assert_eq!(err.span, Span::new(4, 5));
+        assert!(parse("(1 + 2").is_err());
+    }
+}
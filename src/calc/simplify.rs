@@ -0,0 +1,566 @@
+use num_traits::{One, Signed};
+
+use crate::calc::{
+    ast::{BinaryOp, Expr, ExprKind, UnaryOp},
+    builtin::get_builtin,
+    number::Number,
+    value::Value,
+};
+
+const MAX_PASSES: usize = 8;
+
+/// Rewrites an expression into a more readable, equivalent form: folds constants,
+/// collects like terms and powers, and applies a few trig/log identities.
+pub fn simplify(expr: &Expr) -> Expr {
+    let mut current = expr.clone();
+    for _ in 0..MAX_PASSES {
+        let next = simplify_node(&current);
+        if next.to_string() == current.to_string() {
+            return next;
+        }
+        current = next;
+    }
+    current
+}
+
+fn simplify_node(expr: &Expr) -> Expr {
+    match &expr.kind {
+        ExprKind::Number(_) | ExprKind::Ident(_) => expr.clone(),
+        ExprKind::Unary(UnaryOp::Factorial, operand) => {
+            let operand = simplify_node(operand);
+            match operand.as_exact() {
+                Some(val)
+                    if val.is_exact() && val.to_i64().is_some_and(|n| (0..=20).contains(&n)) =>
+                {
+                    val.factorial()
+                        .map(Expr::exact)
+                        .unwrap_or_else(|_| Expr::unary(UnaryOp::Factorial, operand))
+                }
+                _ => Expr::unary(UnaryOp::Factorial, operand),
+            }
+        }
+        ExprKind::Unary(UnaryOp::Neg, _)
+        | ExprKind::Binary(BinaryOp::Add | BinaryOp::Sub, _, _) => {
+            let mut terms = Vec::new();
+            collect_sum(expr, &Coef::one(), &mut terms);
+            build_sum(merge_terms(terms))
+        }
+        // Divisions by zero are left for evaluation to report
+        ExprKind::Binary(op @ (BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow), lhs, rhs) => {
+            match term_of(expr) {
+                Some(term) => build_term(&term),
+                None => Expr::binary(*op, simplify_node(lhs), simplify_node(rhs)),
+            }
+        }
+        ExprKind::Call(name, args) => simplify_call(name, args.iter().map(simplify_node).collect()),
+        ExprKind::Vector(items) => Expr::new(
+            ExprKind::Vector(items.iter().map(simplify_node).collect()),
+            expr.span,
+        ),
+        ExprKind::Equation(lhs, rhs) => Expr::new(
+            ExprKind::Equation(Box::new(simplify_node(lhs)), Box::new(simplify_node(rhs))),
+            expr.span,
+        ),
+        ExprKind::Condition(op, lhs, rhs) => Expr::new(
+            ExprKind::Condition(
+                *op,
+                Box::new(simplify_node(lhs)),
+                Box::new(simplify_node(rhs)),
+            ),
+            expr.span,
+        ),
+    }
+}
+
+fn simplify_call(name: &str, args: Vec<Expr>) -> Expr {
+    // Fold only when the result stays readable, `sin(0)` but not `sqrt(2)`
+    if let Some(builtin) = get_builtin(name)
+        && let Some(vals) = args
+            .iter()
+            .map(|arg| arg.as_exact().cloned().map(Value::Number))
+            .collect::<Option<Vec<_>>>()
+        && let Ok(Value::Number(result)) = builtin.call(&vals)
+        && result.is_integer()
+    {
+        return Expr::exact(result);
+    }
+
+    if let [arg] = args.as_slice() {
+        let inner_call = match &arg.kind {
+            ExprKind::Call(inner, inner_args) if inner_args.len() == 1 => {
+                Some((inner.as_str(), &inner_args[0]))
+            }
+            _ => None,
+        };
+        let negated = match &arg.kind {
+            ExprKind::Unary(UnaryOp::Neg, operand) => Some(operand.as_ref()),
+            _ => None,
+        };
+        match (name, inner_call, negated) {
+            ("ln", Some(("exp", inner)), _) | ("exp", Some(("ln", inner)), _) => {
+                return inner.clone();
+            }
+            ("ln", _, _) if matches!(&arg.kind, ExprKind::Ident(name) if name == "e") => {
+                return Expr::number(1.0);
+            }
+            ("sin" | "tan" | "asin" | "atan" | "sinh" | "tanh", _, Some(operand)) => {
+                return Expr::unary(UnaryOp::Neg, Expr::call(name, vec![operand.clone()]));
+            }
+            ("cos" | "cosh" | "abs", _, Some(operand)) => {
+                return Expr::call(name, vec![operand.clone()]);
+            }
+            _ => {}
+        }
+        if name == "sqrt"
+            && let ExprKind::Binary(BinaryOp::Pow, base, exp) = &arg.kind
+            && exp.as_number() == Some(2.0)
+        {
+            return Expr::call("abs", vec![base.as_ref().clone()]);
+        }
+    }
+    Expr::call(name, args)
+}
+
+/// Numeric coefficient of a term, exact whenever its inputs were.
+#[derive(Debug, Clone, PartialEq)]
+struct Coef(Number);
+
+impl Coef {
+    fn one() -> Self {
+        Coef(Number::one())
+    }
+
+    fn mul(&self, other: &Coef) -> Self {
+        Coef(self.0.mul(&other.0))
+    }
+
+    fn add(&self, other: &Coef) -> Self {
+        Coef(self.0.add(&other.0))
+    }
+
+    /// `None` for negative powers of zero.
+    fn powi(&self, exp: i64) -> Option<Self> {
+        if exp < 0 && self.is_zero() {
+            return None;
+        }
+        Some(Coef(self.0.pow(&Number::from_integer(exp))))
+    }
+
+    fn recip(&self) -> Option<Self> {
+        Number::one().div(&self.0).ok().map(Coef)
+    }
+
+    fn neg(&self) -> Self {
+        Coef(self.0.neg())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+
+    /// Numerator and denominator of the absolute value, floats and decimals have a denominator of 1.
+    fn fraction(&self) -> (Number, Number) {
+        match &self.0 {
+            Number::Rational(val) => (
+                Number::from_integer(val.numer().abs()),
+                Number::from_integer(val.denom().clone()),
+            ),
+            val => (val.abs(), Number::one()),
+        }
+    }
+}
+
+fn is_one(val: &Number) -> bool {
+    match val.as_rational() {
+        Some(val) => val.is_one(),
+        None => val.to_f64() == 1.0,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Factor {
+    base: Expr,
+    exp: Expr,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    coef: Coef,
+    factors: Vec<Factor>,
+}
+
+impl Term {
+    fn key(&self) -> String {
+        let keys: Vec<String> = self
+            .factors
+            .iter()
+            .map(|factor| format!("({})^({})", factor.base, factor.exp))
+            .collect();
+        keys.join("*")
+    }
+}
+
+fn collect_sum(expr: &Expr, sign: &Coef, terms: &mut Vec<Term>) {
+    match &expr.kind {
+        ExprKind::Binary(BinaryOp::Add, lhs, rhs) => {
+            collect_sum(lhs, sign, terms);
+            collect_sum(rhs, sign, terms);
+        }
+        ExprKind::Binary(BinaryOp::Sub, lhs, rhs) => {
+            collect_sum(lhs, sign, terms);
+            collect_sum(rhs, &sign.neg(), terms);
+        }
+        ExprKind::Unary(UnaryOp::Neg, operand) => collect_sum(operand, &sign.neg(), terms),
+        _ => {
+            let simplified = simplify_node(expr);
+            let mut term = term_of(&simplified).unwrap_or_else(|| Term {
+                coef: Coef::one(),
+                factors: vec![Factor {
+                    base: simplified,
+                    exp: Expr::number(1.0),
+                }],
+            });
+            term.coef = term.coef.mul(sign);
+            if term.factors.len() == 1
+                && term.factors[0].exp.as_number() == Some(1.0)
+                && matches!(
+                    term.factors[0].base.kind,
+                    ExprKind::Binary(BinaryOp::Add | BinaryOp::Sub, _, _)
+                        | ExprKind::Unary(UnaryOp::Neg, _)
+                )
+            {
+                // A simplified factor turned back into a sum, flatten it
+                let inner = term.factors[0].base.clone();
+                collect_sum(&inner, &term.coef, terms);
+                return;
+            }
+            terms.push(term);
+        }
+    }
+}
+
+fn merge_terms(terms: Vec<Term>) -> Vec<Term> {
+    let mut merged: Vec<Term> = Vec::new();
+    for term in terms {
+        let key = term.key();
+        match merged.iter_mut().find(|other| other.key() == key) {
+            Some(other) => other.coef = other.coef.add(&term.coef),
+            None => merged.push(term),
+        }
+    }
+    merged.retain(|term| !term.coef.is_zero());
+    apply_pythagorean_identity(merged)
+}
+
+/// `a sin(u)^2 + a cos(u)^2` becomes `a`.
+fn apply_pythagorean_identity(mut terms: Vec<Term>) -> Vec<Term> {
+    let square_of = |factor: &Factor, name: &str| -> Option<String> {
+        match &factor.base.kind {
+            ExprKind::Call(func, args)
+                if func == name && args.len() == 1 && factor.exp.as_number() == Some(2.0) =>
+            {
+                Some(args[0].to_string())
+            }
+            _ => None,
+        }
+    };
+    let split = |term: &Term, name: &str| -> Option<(String, Term)> {
+        let index = term
+            .factors
+            .iter()
+            .position(|factor| square_of(factor, name).is_some())?;
+        let arg = square_of(&term.factors[index], name)?;
+        let mut rest = term.clone();
+        rest.factors.remove(index);
+        Some((arg, rest))
+    };
+
+    let mut i = 0;
+    while i < terms.len() {
+        if let Some((arg, rest)) = split(&terms[i], "sin") {
+            let partner = terms.iter().position(|other| {
+                split(other, "cos").is_some_and(|(other_arg, other_rest)| {
+                    other_arg == arg
+                        && other_rest.key() == rest.key()
+                        && other_rest.coef == rest.coef
+                })
+            });
+            if let Some(j) = partner {
+                terms[i] = rest;
+                terms.remove(j);
+                return merge_terms(terms);
+            }
+        }
+        i += 1;
+    }
+    terms
+}
+
+/// `None` when the product divides by zero.
+fn term_of(expr: &Expr) -> Option<Term> {
+    let mut coef = Coef::one();
+    let mut factors: Vec<Factor> = Vec::new();
+    collect_product(expr, 1.0, &mut coef, &mut factors)?;
+
+    // exp(a) * exp(b) = exp(a + b)
+    let mut exp_args = Vec::new();
+    factors.retain(|factor| match (&factor.base.kind, factor.exp.as_exact()) {
+        (ExprKind::Call(name, args), Some(_)) if name == "exp" && args.len() == 1 => {
+            exp_args.push(Expr::binary(
+                BinaryOp::Mul,
+                factor.exp.clone(),
+                args[0].clone(),
+            ));
+            false
+        }
+        _ => true,
+    });
+    if !exp_args.is_empty() {
+        let sum = exp_args
+            .into_iter()
+            .reduce(|lhs, rhs| Expr::binary(BinaryOp::Add, lhs, rhs))
+            .unwrap();
+        let arg = simplify_node(&sum);
+        match arg.as_number() {
+            Some(0.0) => {}
+            _ => factors.push(Factor {
+                base: Expr::call("exp", vec![arg]),
+                exp: Expr::number(1.0),
+            }),
+        }
+    }
+
+    factors.retain(|factor| factor.exp.as_number() != Some(0.0));
+    factors.sort_by_key(|factor| {
+        let rank = match factor.base.kind {
+            ExprKind::Ident(_) => 0,
+            ExprKind::Call(_, _) => 1,
+            _ => 2,
+        };
+        (rank, factor.base.to_string())
+    });
+    if coef.is_zero() {
+        factors.clear();
+    }
+    Some(Term { coef, factors })
+}
+
+fn collect_product(
+    expr: &Expr,
+    sign: f64,
+    coef: &mut Coef,
+    factors: &mut Vec<Factor>,
+) -> Option<()> {
+    match &expr.kind {
+        ExprKind::Binary(BinaryOp::Mul, lhs, rhs) => {
+            collect_product(lhs, sign, coef, factors)?;
+            collect_product(rhs, sign, coef, factors)?;
+        }
+        ExprKind::Binary(BinaryOp::Div, lhs, rhs) => {
+            collect_product(lhs, sign, coef, factors)?;
+            collect_product(rhs, -sign, coef, factors)?;
+        }
+        ExprKind::Unary(UnaryOp::Neg, operand) => {
+            *coef = coef.neg();
+            collect_product(operand, sign, coef, factors)?;
+        }
+        ExprKind::Number(val) => {
+            let val = Coef(val.clone());
+            *coef = coef.mul(&if sign > 0.0 { val } else { val.recip()? });
+        }
+        ExprKind::Binary(BinaryOp::Pow, base, exp) => {
+            let base = simplify_node(base);
+            let exp = simplify_node(exp);
+            push_power(base, exp, sign, coef, factors)?;
+        }
+        _ => {
+            let simplified = simplify_node(expr);
+            if simplified.to_string() != expr.to_string()
+                && matches!(
+                    simplified.kind,
+                    ExprKind::Number(_)
+                        | ExprKind::Unary(UnaryOp::Neg, _)
+                        | ExprKind::Binary(BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow, _, _)
+                )
+            {
+                collect_product(&simplified, sign, coef, factors)?;
+            } else {
+                add_factor(factors, simplified, Expr::number(sign));
+            }
+        }
+    }
+    Some(())
+}
+
+fn push_power(
+    base: Expr,
+    exp: Expr,
+    sign: f64,
+    coef: &mut Coef,
+    factors: &mut Vec<Factor>,
+) -> Option<()> {
+    let exp_val = exp.as_number();
+    if exp_val == Some(0.0) {
+        return Some(());
+    }
+    // Fold numeric powers that stay exact, `4^(1/2)` but not `2^(1/2)`
+    if let (Some(val), Some(exp)) = (base.as_exact(), exp.as_exact()) {
+        let exp = if sign > 0.0 { exp.clone() } else { exp.neg() };
+        let result = val.pow(&exp);
+        let integral = exp.to_i64().is_some_and(|n| n.abs() <= 64);
+        if result.to_f64().is_finite() && (result.is_exact() || integral) {
+            *coef = coef.mul(&Coef(result));
+            return Some(());
+        }
+    }
+    if matches!(&base.kind, ExprKind::Ident(name) if name == "e") {
+        let arg = simplify_node(&Expr::binary(BinaryOp::Mul, Expr::number(sign), exp));
+        add_factor(factors, Expr::call("exp", vec![arg]), Expr::number(1.0));
+        return Some(());
+    }
+    match (&base.kind, exp_val) {
+        // (a b)^n = a^n b^n and (a^m)^n = a^(m n) for integral n
+        (ExprKind::Binary(BinaryOp::Mul | BinaryOp::Div | BinaryOp::Pow, _, _), Some(n))
+            if n.fract() == 0.0 =>
+        {
+            let mut inner_coef = Coef::one();
+            let mut inner = Vec::new();
+            collect_product(&base, 1.0, &mut inner_coef, &mut inner)?;
+            *coef = coef.mul(&inner_coef.powi((n * sign) as i64)?);
+            for factor in inner {
+                let exp = simplify_node(&Expr::binary(
+                    BinaryOp::Mul,
+                    factor.exp,
+                    Expr::number(n * sign),
+                ));
+                add_factor(factors, factor.base, exp);
+            }
+        }
+        (ExprKind::Unary(UnaryOp::Neg, operand), Some(n)) if n.fract() == 0.0 => {
+            if n % 2.0 != 0.0 {
+                *coef = coef.neg();
+            }
+            push_power(operand.as_ref().clone(), exp, sign, coef, factors)?;
+        }
+        _ => {
+            let exp = simplify_node(&Expr::binary(BinaryOp::Mul, exp, Expr::number(sign)));
+            add_factor(factors, base, exp);
+        }
+    }
+    Some(())
+}
+
+fn add_factor(factors: &mut Vec<Factor>, base: Expr, exp: Expr) {
+    let key = base.to_string();
+    match factors
+        .iter_mut()
+        .find(|factor| factor.base.to_string() == key)
+    {
+        Some(factor) => {
+            factor.exp = simplify_node(&Expr::binary(BinaryOp::Add, factor.exp.clone(), exp));
+        }
+        None => factors.push(Factor { base, exp }),
+    }
+}
+
+fn build_power(factor: &Factor, positive: bool) -> Expr {
+    let exp = match factor.exp.as_exact() {
+        Some(val) if !positive => Expr::exact(val.neg()),
+        _ => factor.exp.clone(),
+    };
+    if exp.as_number() == Some(1.0) {
+        factor.base.clone()
+    } else if exp.as_number() == Some(0.5) {
+        Expr::call("sqrt", vec![factor.base.clone()])
+    } else {
+        Expr::binary(BinaryOp::Pow, factor.base.clone(), exp)
+    }
+}
+
+fn build_product(items: Vec<Expr>) -> Option<Expr> {
+    items
+        .into_iter()
+        .reduce(|lhs, rhs| Expr::binary(BinaryOp::Mul, lhs, rhs))
+}
+
+fn build_term(term: &Term) -> Expr {
+    if term.coef.is_zero() {
+        return Expr::number(0.0);
+    }
+    if term.factors.is_empty() {
+        return Expr::exact(term.coef.0.clone());
+    }
+    let (num, den) = term.coef.fraction();
+    let negative = term.coef.is_negative();
+
+    let mut numerator = Vec::new();
+    let mut denominator = Vec::new();
+    if !is_one(&num) {
+        numerator.push(Expr::exact(num));
+    }
+    if !is_one(&den) {
+        denominator.push(Expr::exact(den));
+    }
+    for factor in &term.factors {
+        match factor.exp.as_number() {
+            Some(val) if val < 0.0 => denominator.push(build_power(factor, false)),
+            _ => numerator.push(build_power(factor, true)),
+        }
+    }
+
+    let numerator = build_product(numerator).unwrap_or_else(|| Expr::number(1.0));
+    let result = match build_product(denominator) {
+        Some(denominator) => Expr::binary(BinaryOp::Div, numerator, denominator),
+        None => numerator,
+    };
+    if negative {
+        Expr::unary(UnaryOp::Neg, result)
+    } else {
+        result
+    }
+}
+
+fn build_sum(terms: Vec<Term>) -> Expr {
+    // Constants go last, `x + 1` rather than `1 + x`
+    let (constants, mut terms): (Vec<Term>, Vec<Term>) =
+        terms.into_iter().partition(|term| term.factors.is_empty());
+    terms.extend(constants);
+
+    let mut result: Option<Expr> = None;
+    for term in terms {
+        result = Some(match result {
+            None => build_term(&term),
+            Some(lhs) if term.coef.is_negative() => {
+                let positive = Term {
+                    coef: term.coef.neg(),
+                    factors: term.factors,
+                };
+                Expr::binary(BinaryOp::Sub, lhs, build_term(&positive))
+            }
+            Some(lhs) => Expr::binary(BinaryOp::Add, lhs, build_term(&term)),
+        });
+    }
+    result.unwrap_or_else(|| Expr::number(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::parser::parse;
+
+    fn simplified(input: &str) -> String {
+        simplify(&parse(input).unwrap()).to_string()
+    }
+
+    #[test]
+    fn division_by_zero_is_kept() {
+        assert_eq!(simplified("x / 0"), "x / 0");
+        assert_eq!(simplified("2 x / (1 - 1)"), "2 * x / 0");
+        assert_eq!(simplified("1 + (0 x)^-1"), "1 / 0 + 1");
+        assert_eq!(simplified("x / 2"), "x / 2");
+    }
+}
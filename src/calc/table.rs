@@ -0,0 +1,157 @@
+use crate::calc::{eval::Environment, matrix::Matrix, unit::is_unit, value::Value};
+
+/// Columns of numbers imported from a file of comma, semicolon or tab separated values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTable {
+    pub name: String,
+    /// Variable name and values of each column, cells that aren't numbers are NaN
+    pub columns: Vec<(String, Vec<f64>)>,
+}
+
+impl DataTable {
+    /// Reads a table named after its file. The first line holds the headers unless
+    /// its cells are all numbers or empty, a column without one is numbered instead.
+    /// Names of constants and units get a `_data` suffix, columns named like one are
+    /// numbered. With semicolons between cells a decimal comma is accepted.
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let builtins = Environment::new();
+        let reserved = |name: &str| builtins.get_variable(name).is_some() || is_unit(name);
+        let name = match identifier(name) {
+            Some(name) if reserved(&name) => format!("{}_data", name),
+            Some(name) => name,
+            None => "data".to_owned(),
+        };
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .peekable();
+        let first = *lines.peek().ok_or("The file is empty")?;
+        let separator = ['\t', ';', ',']
+            .into_iter()
+            .find(|sep| first.contains(*sep));
+        let split = |line: &str| -> Vec<String> {
+            let cells = match separator {
+                Some(sep) => line.split(sep).collect(),
+                None => line.split_whitespace().collect::<Vec<_>>(),
+            };
+            cells
+                .iter()
+                .map(|cell| cell.trim().trim_matches('"').trim().to_owned())
+                .collect()
+        };
+        let number = |cell: &str| match separator {
+            Some(';') => cell.replace(',', ".").parse::<f64>(),
+            _ => cell.parse::<f64>(),
+        };
+
+        let headers = split(first);
+        let has_headers = headers
+            .iter()
+            .any(|cell| !cell.is_empty() && number(cell).is_err());
+        if has_headers {
+            lines.next();
+        }
+        let rows: Vec<Vec<f64>> = lines
+            .map(|line| {
+                split(line)
+                    .iter()
+                    .map(|cell| number(cell).unwrap_or(f64::NAN))
+                    .collect()
+            })
+            .collect();
+        if rows.is_empty() {
+            return Err("The table has no rows".to_owned());
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut columns: Vec<(String, Vec<f64>)> = Vec::with_capacity(width);
+        for col in 0..width {
+            let header = has_headers
+                .then(|| headers.get(col).and_then(|header| identifier(header)))
+                .flatten()
+                .map(|header| format!("{}_{}", name, header))
+                .filter(|header| {
+                    !reserved(header) && columns.iter().all(|(other, _)| other != header)
+                })
+                .unwrap_or_else(|| format!("{}_{}", name, col + 1));
+            let values = rows
+                .iter()
+                .map(|row| row.get(col).copied().unwrap_or(f64::NAN))
+                .collect();
+            columns.push((header, values));
+        }
+        Ok(Self { name, columns })
+    }
+
+    /// Defines each column as a list and the table as a matrix with a row per line.
+    pub fn define(&self, env: &mut Environment) {
+        for (header, values) in &self.columns {
+            let items = values.iter().map(|val| Value::from(*val)).collect();
+            env.set_variable(header, Value::Vector(items));
+        }
+        let rows = self.columns.first().map_or(0, |(_, values)| values.len());
+        let entries = (0..rows)
+            .flat_map(|row| {
+                self.columns
+                    .iter()
+                    .map(move |(_, values)| Value::from(values[row]))
+            })
+            .collect();
+        let matrix = Matrix::new(rows, self.columns.len(), entries);
+        env.set_variable(&self.name, Value::Matrix(matrix));
+    }
+}
+
+/// `text` turned into a variable name, with other characters replaced by underscores.
+fn identifier(text: &str) -> Option<String> {
+    let name: String = text
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        None => None,
+        Some(c) if c.is_numeric() => Some(format!("_{}", name)),
+        Some(_) => Some(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(table: &DataTable) -> Vec<&str> {
+        table
+            .columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn headers() {
+        let table = DataTable::parse("fall", "time,height\n0,1\n1,4").unwrap();
+        assert_eq!(names(&table), ["fall_time", "fall_height"]);
+
+        // An empty trailing cell doesn't make a line of numbers a header
+        let table = DataTable::parse("points", "1,2,\n3,4,5").unwrap();
+        assert_eq!(names(&table), ["points_1", "points_2", "points_3"]);
+        assert_eq!(table.columns[0].1, [1.0, 3.0]);
+        assert!(table.columns[2].1[0].is_nan());
+    }
+
+    #[test]
+    fn names_of_constants_are_not_taken() {
+        let table = DataTable::parse("pi", "x\n1").unwrap();
+        assert_eq!(table.name, "pi_data");
+        assert_eq!(names(&table), ["pi_data_x"]);
+
+        // `q_e` is the elementary charge
+        let table = DataTable::parse("q", "e,f\n1,2").unwrap();
+        assert_eq!(names(&table), ["q_1", "q_f"]);
+
+        let mut env = Environment::new();
+        DataTable::parse("e", "1\n2").unwrap().define(&mut env);
+        assert_eq!(env.get_variable("e"), Environment::new().get_variable("e"));
+    }
+}
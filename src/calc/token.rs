@@ -0,0 +1,259 @@
+use std::fmt;
+
+use crate::calc::{error::CalcError, number::Number};
+
+/// Byte range inside the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn join(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(Number),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Bang,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    DotDot,
+    Equal,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Number(val) => write!(f, "number {}", val),
+            TokenKind::Ident(name) => write!(f, "identifier '{}'", name),
+            TokenKind::Plus => write!(f, "'+'"),
+            TokenKind::Minus => write!(f, "'-'"),
+            TokenKind::Star => write!(f, "'*'"),
+            TokenKind::Slash => write!(f, "'/'"),
+            TokenKind::Caret => write!(f, "'^'"),
+            TokenKind::Bang => write!(f, "'!'"),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::LBracket => write!(f, "'['"),
+            TokenKind::RBracket => write!(f, "']'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::DotDot => write!(f, "'..'"),
+            TokenKind::Equal => write!(f, "'='"),
+            TokenKind::Less => write!(f, "'<'"),
+            TokenKind::LessEqual => write!(f, "'<='"),
+            TokenKind::Greater => write!(f, "'>'"),
+            TokenKind::GreaterEqual => write!(f, "'>='"),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, CalcError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if input[start..].starts_with("..") {
+            chars.next();
+            chars.next();
+            tokens.push(Token {
+                kind: TokenKind::DotDot,
+                span: Span::new(start, start + 2),
+            });
+            continue;
+        }
+
+        if input[start..].starts_with("<=") || input[start..].starts_with(">=") {
+            chars.next();
+            chars.next();
+            tokens.push(Token {
+                kind: if c == '<' {
+                    TokenKind::LessEqual
+                } else {
+                    TokenKind::GreaterEqual
+                },
+                span: Span::new(start, start + 2),
+            });
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let end = scan_number(input, start);
+            let text = &input[start..end];
+            let val = Number::parse_literal(text).ok_or_else(|| {
+                CalcError::new(format!("Invalid number '{}'", text), Span::new(start, end))
+            })?;
+            tokens.push(Token {
+                kind: TokenKind::Number(val),
+                span: Span::new(start, end),
+            });
+            while chars.peek().is_some_and(|&(i, _)| i < end) {
+                chars.next();
+            }
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            // Primes belong to the name, `f'` is the derivative of `f`
+            while let Some(&(i, '\'')) = chars.peek() {
+                end = i + 1;
+                chars.next();
+            }
+            tokens.push(Token {
+                kind: TokenKind::Ident(input[start..end].to_owned()),
+                span: Span::new(start, end),
+            });
+            continue;
+        }
+
+        let kind = match c {
+            '+' => TokenKind::Plus,
+            '-' | '−' => TokenKind::Minus,
+            '*' | '×' | '·' => TokenKind::Star,
+            '/' | '÷' => TokenKind::Slash,
+            '^' => TokenKind::Caret,
+            '!' => TokenKind::Bang,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            ',' => TokenKind::Comma,
+            '=' => TokenKind::Equal,
+            '<' => TokenKind::Less,
+            '>' => TokenKind::Greater,
+            '≤' => TokenKind::LessEqual,
+            '≥' => TokenKind::GreaterEqual,
+            _ => {
+                return Err(CalcError::new(
+                    format!("Unknown character '{}'", c),
+                    Span::new(start, start + c.len_utf8()),
+                ));
+            }
+        };
+        chars.next();
+        tokens.push(Token {
+            kind,
+            span: Span::new(start, start + c.len_utf8()),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(input.len(), input.len()),
+    });
+    Ok(tokens)
+}
+
+/// Returns the end of the number literal starting at `start`.
+/// An `e` only starts an exponent when digits follow, so `2e` stays `2 * e`.
+fn scan_number(input: &str, start: usize) -> usize {
+    let bytes = input.as_bytes();
+    let mut end = start;
+    while end < bytes.len() && bytes[end].is_ascii_digit() {
+        end += 1;
+    }
+    // `0..10` is a range, not the number `0.`
+    if end < bytes.len() && bytes[end] == b'.' && bytes.get(end + 1) != Some(&b'.') {
+        end += 1;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+    }
+    if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+        let mut exp_end = end + 1;
+        if exp_end < bytes.len() && (bytes[exp_end] == b'+' || bytes[exp_end] == b'-') {
+            exp_end += 1;
+        }
+        if exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+            while exp_end < bytes.len() && bytes[exp_end].is_ascii_digit() {
+                exp_end += 1;
+            }
+            end = exp_end;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn numbers_and_names() {
+        let spans: Vec<Span> = tokenize("12.5 * x_1")
+            .unwrap()
+            .into_iter()
+            .map(|token| token.span)
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                Span::new(0, 4),
+                Span::new(5, 6),
+                Span::new(7, 10),
+                Span::new(10, 10)
+            ]
+        );
+        // An `e` without digits is Euler's number
+        assert_eq!(kinds("2e3").len(), 2);
+        assert_eq!(
+            kinds("2e")[1..],
+            [TokenKind::Ident("e".to_owned()), TokenKind::Eof]
+        );
+    }
+
+    #[test]
+    fn unknown_characters() {
+        let err = tokenize("1 + $").unwrap_err();
+        assert_eq!(err.span, Span::new(4, 5));
+    }
+}
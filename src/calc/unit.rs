@@ -0,0 +1,563 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+
+use crate::calc::{
+    ast::{BinaryOp, Expr},
+    number::Number,
+    value::format_f64,
+};
+
+/// Symbols of the SI base units, in the order of [`Dimension`] exponents.
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Exponents of length, mass, time, current, temperature, amount and luminous intensity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Dimension(pub [i8; 7]);
+
+const fn dim(length: i8, mass: i8, time: i8, current: i8, temperature: i8) -> Dimension {
+    Dimension([length, mass, time, current, temperature, 0, 0])
+}
+
+const NONE: Dimension = dim(0, 0, 0, 0, 0);
+const LENGTH: Dimension = dim(1, 0, 0, 0, 0);
+const AREA: Dimension = dim(2, 0, 0, 0, 0);
+const VOLUME: Dimension = dim(3, 0, 0, 0, 0);
+const MASS: Dimension = dim(0, 1, 0, 0, 0);
+const TIME: Dimension = dim(0, 0, 1, 0, 0);
+const CURRENT: Dimension = dim(0, 0, 0, 1, 0);
+const TEMPERATURE: Dimension = dim(0, 0, 0, 0, 1);
+const AMOUNT: Dimension = Dimension([0, 0, 0, 0, 0, 1, 0]);
+const LUMINOSITY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 1]);
+const FREQUENCY: Dimension = dim(0, 0, -1, 0, 0);
+const VELOCITY: Dimension = dim(1, 0, -1, 0, 0);
+const FORCE: Dimension = dim(1, 1, -2, 0, 0);
+const PRESSURE: Dimension = dim(-1, 1, -2, 0, 0);
+const ENERGY: Dimension = dim(2, 1, -2, 0, 0);
+const POWER: Dimension = dim(2, 1, -3, 0, 0);
+const CHARGE: Dimension = dim(0, 0, 1, 1, 0);
+const VOLTAGE: Dimension = dim(2, 1, -3, -1, 0);
+const RESISTANCE: Dimension = dim(2, 1, -3, -2, 0);
+const CAPACITANCE: Dimension = dim(-2, -1, 4, 2, 0);
+const INDUCTANCE: Dimension = dim(2, 1, -2, -2, 0);
+const MAGNETIC_FLUX: Dimension = dim(2, 1, -2, -1, 0);
+const MAGNETIC_FIELD: Dimension = dim(0, 1, -2, -1, 0);
+
+/// Derived units used to name results, e.g. `kg m^2/s^2` is shown as `J`.
+const DERIVED_NAMES: [(&str, Dimension); 12] = [
+    ("Hz", FREQUENCY),
+    ("N", FORCE),
+    ("Pa", PRESSURE),
+    ("J", ENERGY),
+    ("W", POWER),
+    ("C", CHARGE),
+    ("V", VOLTAGE),
+    ("Ω", RESISTANCE),
+    ("F", CAPACITANCE),
+    ("H", INDUCTANCE),
+    ("Wb", MAGNETIC_FLUX),
+    ("T", MAGNETIC_FIELD),
+];
+
+impl Dimension {
+    pub fn is_none(&self) -> bool {
+        *self == NONE
+    }
+
+    pub fn mul(&self, other: &Dimension) -> Result<Dimension, String> {
+        self.combine(other, i8::checked_add)
+    }
+
+    pub fn div(&self, other: &Dimension) -> Result<Dimension, String> {
+        self.combine(other, i8::checked_sub)
+    }
+
+    fn combine(
+        &self,
+        other: &Dimension,
+        op: impl Fn(i8, i8) -> Option<i8>,
+    ) -> Result<Dimension, String> {
+        let mut exponents = [0i8; 7];
+        for (exponent, (a, b)) in exponents.iter_mut().zip(self.0.into_iter().zip(other.0)) {
+            *exponent = op(a, b).ok_or_else(exponent_out_of_range)?;
+        }
+        Ok(Dimension(exponents))
+    }
+
+    /// Raises to `numer / denom`, `None` if an exponent would become fractional.
+    pub fn pow(&self, numer: i64, denom: i64) -> Result<Option<Dimension>, String> {
+        let mut exponents = [0i8; 7];
+        for (exponent, base) in exponents.iter_mut().zip(self.0) {
+            let scaled = (base as i64)
+                .checked_mul(numer)
+                .ok_or_else(exponent_out_of_range)?;
+            if scaled % denom != 0 {
+                return Ok(None);
+            }
+            *exponent = (scaled / denom)
+                .try_into()
+                .map_err(|_| exponent_out_of_range())?;
+        }
+        Ok(Some(Dimension(exponents)))
+    }
+
+    /// Product of base units, used when a result is turned back into an expression.
+    pub fn to_expr(&self) -> Expr {
+        let mut result: Option<Expr> = None;
+        for (name, exponent) in BASE_UNITS.iter().zip(self.0) {
+            if exponent == 0 {
+                continue;
+            }
+            let mut factor = Expr::ident(name);
+            if exponent != 1 {
+                factor = Expr::binary(
+                    BinaryOp::Pow,
+                    factor,
+                    Expr::exact(Number::from_integer(exponent)),
+                );
+            }
+            result = Some(match result {
+                Some(lhs) => Expr::binary(BinaryOp::Mul, lhs, factor),
+                None => factor,
+            });
+        }
+        result.unwrap_or_else(|| Expr::exact(Number::one()))
+    }
+}
+
+/// `kg m^2/s^2`, or the name of a derived unit with the same dimension.
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((name, _)) = DERIVED_NAMES.iter().find(|(_, dim)| dim == self) {
+            return write!(f, "{}", name);
+        }
+        let factors = |sign: i8| {
+            BASE_UNITS
+                .iter()
+                .zip(self.0)
+                .filter(|(_, exponent)| exponent.signum() == sign)
+                .map(|(name, exponent)| match exponent.abs() {
+                    1 => name.to_string(),
+                    exponent => format!("{}^{}", name, exponent),
+                })
+                .collect::<Vec<_>>()
+        };
+        let (numer, denom) = (factors(1), factors(-1));
+        let numer = if numer.is_empty() {
+            "1".to_owned()
+        } else {
+            numer.join(" ")
+        };
+        match denom.len() {
+            0 => write!(f, "{}", numer),
+            1 => write!(f, "{}/{}", numer, denom[0]),
+            _ => write!(f, "{}/({})", numer, denom.join(" ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Factor {
+    /// Exact decimal literal
+    Exact(&'static str),
+    Float(f64),
+}
+
+struct UnitDef {
+    name: &'static str,
+    factor: Factor,
+    dimension: Dimension,
+    /// Whether SI prefixes apply, `km` but not `kmin`
+    prefixable: bool,
+}
+
+const fn unit(name: &'static str, factor: &'static str, dimension: Dimension) -> UnitDef {
+    UnitDef {
+        name,
+        factor: Factor::Exact(factor),
+        dimension,
+        prefixable: false,
+    }
+}
+
+const fn si(name: &'static str, factor: &'static str, dimension: Dimension) -> UnitDef {
+    UnitDef {
+        name,
+        factor: Factor::Exact(factor),
+        dimension,
+        prefixable: true,
+    }
+}
+
+/// Unit symbols, factors convert to the coherent SI unit of the dimension.
+const UNITS: &[UnitDef] = &[
+    si("m", "1", LENGTH),
+    si("g", "1e-3", MASS),
+    si("s", "1", TIME),
+    si("A", "1", CURRENT),
+    si("K", "1", TEMPERATURE),
+    si("mol", "1", AMOUNT),
+    si("cd", "1", LUMINOSITY),
+    si("Hz", "1", FREQUENCY),
+    si("N", "1", FORCE),
+    si("Pa", "1", PRESSURE),
+    si("J", "1", ENERGY),
+    si("W", "1", POWER),
+    si("C", "1", CHARGE),
+    si("V", "1", VOLTAGE),
+    si("Ω", "1", RESISTANCE),
+    si("ohm", "1", RESISTANCE),
+    si("F", "1", CAPACITANCE),
+    si("H", "1", INDUCTANCE),
+    si("Wb", "1", MAGNETIC_FLUX),
+    si("T", "1", MAGNETIC_FIELD),
+    si("L", "1e-3", VOLUME),
+    si("eV", "1.602176634e-19", ENERGY),
+    si("cal", "4.184", ENERGY),
+    si("Wh", "3600", ENERGY),
+    si("bar", "1e5", PRESSURE),
+    unit("min", "60", TIME),
+    unit("h", "3600", TIME),
+    unit("hr", "3600", TIME),
+    unit("hour", "3600", TIME),
+    unit("day", "86400", TIME),
+    unit("week", "604800", TIME),
+    unit("year", "31557600", TIME),
+    unit("in", "0.0254", LENGTH),
+    unit("ft", "0.3048", LENGTH),
+    unit("yd", "0.9144", LENGTH),
+    unit("mi", "1609.344", LENGTH),
+    unit("nmi", "1852", LENGTH),
+    unit("Å", "1e-10", LENGTH),
+    unit("au", "149597870700", LENGTH),
+    unit("ly", "9460730472580800", LENGTH),
+    unit("ha", "1e4", AREA),
+    unit("lb", "0.45359237", MASS),
+    unit("oz", "0.028349523125", MASS),
+    unit("mph", "0.44704", VELOCITY),
+    unit("atm", "101325", PRESSURE),
+    unit("psi", "6894.757293168361", PRESSURE),
+    unit("rad", "1", NONE),
+    UnitDef {
+        name: "deg",
+        factor: Factor::Float(std::f64::consts::PI / 180.0),
+        dimension: NONE,
+        prefixable: false,
+    },
+];
+
+/// SI prefixes with their power of ten, `da` comes first so it wins over `d`.
+const PREFIXES: [(&str, i32); 23] = [
+    ("da", 1),
+    ("Q", 30),
+    ("R", 27),
+    ("Y", 24),
+    ("Z", 21),
+    ("E", 18),
+    ("P", 15),
+    ("T", 12),
+    ("G", 9),
+    ("M", 6),
+    ("k", 3),
+    ("h", 2),
+    ("d", -1),
+    ("c", -2),
+    ("m", -3),
+    ("µ", -6),
+    ("μ", -6),
+    ("u", -6),
+    ("n", -9),
+    ("p", -12),
+    ("f", -15),
+    ("a", -18),
+    ("z", -21),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    /// Size of one unit in coherent SI units
+    pub factor: Number,
+    pub dimension: Dimension,
+}
+
+fn exponent_out_of_range() -> String {
+    "unit exponent out of range".to_owned()
+}
+
+fn power_of_ten(exponent: i32) -> Number {
+    let power = BigInt::from(10).pow(exponent.unsigned_abs());
+    if exponent >= 0 {
+        Number::from_integer(power)
+    } else {
+        Number::Rational(BigRational::new(BigInt::from(1), power))
+    }
+}
+
+/// Resolves a unit symbol such as `m`, `km`, `µs` or `kWh`.
+pub fn lookup_unit(name: &str) -> Option<Unit> {
+    let to_unit = |def: &UnitDef, scale: Number| {
+        let factor = match def.factor {
+            Factor::Exact(text) => Number::parse_literal(text)?,
+            Factor::Float(val) => Number::Float(val),
+        };
+        Some(Unit {
+            name: name.to_owned(),
+            factor: factor.mul(&scale),
+            dimension: def.dimension,
+        })
+    };
+    if let Some(def) = UNITS.iter().find(|def| def.name == name) {
+        return to_unit(def, Number::one());
+    }
+    PREFIXES.iter().find_map(|(prefix, exponent)| {
+        let rest = name.strip_prefix(prefix)?;
+        let def = UNITS
+            .iter()
+            .find(|def| def.prefixable && def.name == rest)?;
+        to_unit(def, power_of_ten(*exponent))
+    })
+}
+
+pub fn is_unit(name: &str) -> bool {
+    lookup_unit(name).is_some()
+}
+
+/// A number with a physical dimension, `value` is in coherent SI units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: Number,
+    pub dimension: Dimension,
+    /// Unit the value is shown in, SI when `None`
+    pub unit: Option<Unit>,
+}
+
+impl Quantity {
+    pub fn new(value: Number, dimension: Dimension) -> Self {
+        Self {
+            value,
+            dimension,
+            unit: None,
+        }
+    }
+
+    /// One of `unit`, shown in that unit.
+    pub fn from_unit(unit: Unit) -> Self {
+        Self {
+            value: unit.factor.clone(),
+            dimension: unit.dimension,
+            unit: Some(unit),
+        }
+    }
+
+    pub fn dimensionless(value: Number) -> Self {
+        Self::new(value, NONE)
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.value.is_exact() && self.unit.as_ref().is_none_or(|unit| unit.factor.is_exact())
+    }
+
+    pub fn map_value(&self, f: impl Fn(&Number) -> Number) -> Self {
+        Self {
+            value: f(&self.value),
+            ..self.clone()
+        }
+    }
+
+    /// Value in the display unit.
+    pub fn display_value(&self) -> Number {
+        match &self.unit {
+            Some(unit) => self
+                .value
+                .div(&unit.factor)
+                .unwrap_or(Number::Float(f64::NAN)),
+            None => self.value.clone(),
+        }
+    }
+
+    pub fn unit_name(&self) -> String {
+        match &self.unit {
+            Some(unit) => unit.name.clone(),
+            None => self.dimension.to_string(),
+        }
+    }
+
+    /// Whether the digits shown by `Display` are the exact value.
+    pub fn displays_exactly(&self) -> bool {
+        let val = self.display_value();
+        if !self.is_exact() || val.is_integer() {
+            return self.is_exact();
+        }
+        let shown = Number::parse_literal(&format_f64(val.to_f64()));
+        shown.as_ref().and_then(Number::as_rational) == val.as_rational()
+    }
+
+    fn check_same(&self, other: &Quantity, verb: &str) -> Result<(), String> {
+        if self.dimension == other.dimension {
+            return Ok(());
+        }
+        let describe = |q: &Quantity| {
+            if q.dimension.is_none() {
+                "a number".to_owned()
+            } else {
+                q.dimension.to_string()
+            }
+        };
+        Err(format!(
+            "Dimension mismatch, can't {} {} and {}",
+            verb,
+            describe(self),
+            describe(other)
+        ))
+    }
+
+    pub fn add(&self, other: &Quantity) -> Result<Quantity, String> {
+        self.check_same(other, "add")?;
+        Ok(self.map_value(|val| val.add(&other.value)))
+    }
+
+    pub fn sub(&self, other: &Quantity) -> Result<Quantity, String> {
+        self.check_same(other, "subtract")?;
+        Ok(self.map_value(|val| val.sub(&other.value)))
+    }
+
+    /// A plain factor keeps the unit of the other operand, `2 * 3 km` is `6 km`.
+    pub fn mul(&self, other: &Quantity) -> Result<Quantity, String> {
+        Ok(Quantity {
+            value: self.value.mul(&other.value),
+            dimension: self.dimension.mul(&other.dimension)?,
+            unit: match (&self.unit, &other.unit) {
+                (Some(unit), None) if other.dimension.is_none() => Some(unit.clone()),
+                (None, Some(unit)) if self.dimension.is_none() => Some(unit.clone()),
+                _ => None,
+            },
+        })
+    }
+
+    pub fn div(&self, other: &Quantity) -> Result<Quantity, String> {
+        Ok(Quantity {
+            value: self.value.div(&other.value)?,
+            dimension: self.dimension.div(&other.dimension)?,
+            unit: match &self.unit {
+                Some(unit) if other.dimension.is_none() && other.unit.is_none() => {
+                    Some(unit.clone())
+                }
+                _ => None,
+            },
+        })
+    }
+
+    /// Integer and rational powers, `sqrt(m^2)` is fine but `sqrt(m)` is not.
+    pub fn pow(&self, exp: &Number) -> Result<Quantity, String> {
+        if self.dimension.is_none() {
+            return Ok(Quantity::dimensionless(self.value.pow(exp)));
+        }
+        let cant_raise = || format!("Can't raise {} to the power {}", self.dimension, exp);
+        let (numer, denom) = exp
+            .as_rational()
+            .and_then(|exp| Some((exp.numer().to_i64()?, exp.denom().to_i64()?)))
+            .ok_or_else(cant_raise)?;
+        let dimension = self.dimension.pow(numer, denom)?.ok_or_else(cant_raise)?;
+        Ok(Quantity::new(self.value.pow(exp), dimension))
+    }
+
+    /// Expresses the quantity in `target`, which must have the same dimension.
+    pub fn convert(&self, target: Unit) -> Result<Quantity, String> {
+        if self.dimension != target.dimension {
+            return Err(format!(
+                "Can't convert {} to {}",
+                self.unit_name(),
+                target.name
+            ));
+        }
+        Ok(Quantity {
+            value: self.value.clone(),
+            dimension: self.dimension,
+            unit: Some(target),
+        })
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        let value = Expr::exact(self.value.clone());
+        if self.dimension.is_none() {
+            return value;
+        }
+        Expr::binary(BinaryOp::Mul, value, self.dimension.to_expr())
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let val = self.display_value();
+        let text = match &val {
+            Number::Rational(ratio) if ratio.is_integer() => val.to_string(),
+            _ => format_f64(val.to_f64()),
+        };
+        write!(f, "{} {}", text, self.unit_name())
+    }
+}
+
+/// Physical constants with their units (CODATA 2018). Names stay clear of numbers
+/// and unit symbols, `e` is Euler's number and `h` the hour, so the elementary
+/// charge is `q_e` and Planck's constant `h_P`.
+pub fn physical_constants() -> Vec<(&'static str, Quantity)> {
+    let constant = |value: &str, dimension: Dimension| {
+        Quantity::new(Number::parse_literal(value).unwrap(), dimension)
+    };
+    let planck = constant("6.62607015e-34", dim(2, 1, -1, 0, 0));
+    vec![
+        ("c", constant("299792458", VELOCITY)),
+        (
+            "hbar",
+            planck.map_value(|val| val.div(&Number::Float(std::f64::consts::TAU)).unwrap()),
+        ),
+        ("h_P", planck),
+        ("G", constant("6.67430e-11", dim(3, -1, -2, 0, 0))),
+        ("g_0", constant("9.80665", dim(1, 0, -2, 0, 0))),
+        ("k_B", constant("1.380649e-23", dim(2, 1, -2, 0, -1))),
+        ("q_e", constant("1.602176634e-19", CHARGE)),
+        (
+            "N_A",
+            constant("6.02214076e23", Dimension([0, 0, 0, 0, 0, -1, 0])),
+        ),
+        (
+            "R",
+            constant("8.31446261815324", Dimension([2, 1, -2, 0, -1, -1, 0])),
+        ),
+        ("m_e", constant("9.1093837015e-31", MASS)),
+        ("m_p", constant("1.67262192369e-27", MASS)),
+        (
+            "eps_0",
+            constant("8.8541878128e-12", CAPACITANCE.div(&LENGTH).unwrap()),
+        ),
+        (
+            "mu_0",
+            constant("1.25663706212e-6", INDUCTANCE.div(&LENGTH).unwrap()),
+        ),
+        ("sigma", constant("5.670374419e-8", dim(0, 1, -3, 0, -4))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponents_out_of_range() {
+        let big = LENGTH.pow(100, 1).unwrap().unwrap();
+        assert_eq!(big.div(&LENGTH).ok(), LENGTH.pow(99, 1).unwrap());
+        assert_eq!(big.mul(&big).unwrap_err(), "unit exponent out of range");
+        assert!(big.pow(-1, 1).unwrap().unwrap().div(&big).is_err());
+        assert!(LENGTH.pow(i64::MAX, 1).is_err());
+        assert_eq!(LENGTH.pow(1, 2), Ok(None));
+
+        let quantity = Quantity::new(Number::one(), big);
+        let err = quantity.mul(&quantity).unwrap_err();
+        assert_eq!(err, "unit exponent out of range");
+        let two = Number::from_integer(BigInt::from(2));
+        assert!(quantity.pow(&two).is_err());
+    }
+}
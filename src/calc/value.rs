@@ -0,0 +1,230 @@
+use std::fmt;
+
+use crate::calc::{
+    ast::ExprKind,
+    ast::{BinaryOp, Expr},
+    complex::Complex,
+    matrix::Matrix,
+    number::Number,
+    unit::Quantity,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(Number),
+    /// Always has a non-zero imaginary part, see [`Value::complex`]
+    Complex(Complex),
+    /// Number with a physical dimension, e.g. `3 km`, see [`Value::quantity`]
+    Quantity(Quantity),
+    /// `[1, 2, 3]`, entries are scalars
+    Vector(Vec<Value>),
+    Matrix(Matrix),
+    /// Symbolic result with free variables, e.g. `diff(x^2, x)`
+    Expression(Box<Expr>),
+}
+
+impl Value {
+    /// Complex value that collapses to a real number when the imaginary part
+    /// vanishes, round-off below `1e-14` of the real part counts as zero.
+    pub fn complex(val: Complex) -> Value {
+        let negligible = |part: &Number, other: &Number| match part {
+            Number::Float(x) => x.abs() <= 1e-14 * other.to_f64().abs(),
+            _ => false,
+        };
+        if val.im.is_zero() || negligible(&val.im, &val.re) {
+            return Value::Number(val.re);
+        }
+        if negligible(&val.re, &val.im) {
+            return Value::Complex(Complex::new(Number::Float(0.0), val.im));
+        }
+        Value::Complex(val)
+    }
+
+    /// Quantity that collapses to a number when it has neither a dimension nor a unit.
+    pub fn quantity(val: Quantity) -> Value {
+        if val.dimension.is_none() && val.unit.is_none() {
+            return Value::Number(val.value);
+        }
+        Value::Quantity(val)
+    }
+
+    /// Numbers are dimensionless quantities.
+    pub fn to_quantity(&self) -> Option<Quantity> {
+        match self {
+            Value::Number(val) => Some(Quantity::dimensionless(val.clone())),
+            Value::Quantity(val) => Some(val.clone()),
+            Value::Complex(_) | Value::Expression(_) | Value::Vector(_) | Value::Matrix(_) => None,
+        }
+    }
+
+    /// Components of a real 3-vector, e.g. to draw it as an arrow.
+    pub fn as_vec3(&self) -> Option<[f64; 3]> {
+        match self {
+            Value::Vector(items) if items.len() == 3 => {
+                let mut components = [0.0; 3];
+                for (component, item) in components.iter_mut().zip(items) {
+                    *component = item.as_f64()?;
+                }
+                Some(components)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(val) => Some(val.to_f64()),
+            Value::Complex(_)
+            | Value::Quantity(_)
+            | Value::Vector(_)
+            | Value::Matrix(_)
+            | Value::Expression(_) => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(val) => Some(val),
+            Value::Complex(_)
+            | Value::Quantity(_)
+            | Value::Vector(_)
+            | Value::Matrix(_)
+            | Value::Expression(_) => None,
+        }
+    }
+
+    /// Real numbers are complex numbers with a zero imaginary part.
+    pub fn to_complex(&self) -> Option<Complex> {
+        match self {
+            Value::Number(val) => Some(Complex::from_real(val.clone())),
+            Value::Complex(val) => Some(val.clone()),
+            Value::Quantity(_) | Value::Vector(_) | Value::Matrix(_) | Value::Expression(_) => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Complex(_) => "complex number",
+            Value::Quantity(_) => "quantity",
+            Value::Vector(_) => "vector",
+            Value::Matrix(_) => "matrix",
+            Value::Expression(_) => "expression",
+        }
+    }
+
+    /// Whether the value is known without rounding, symbolic results are exact.
+    pub fn is_exact(&self) -> bool {
+        match self {
+            Value::Number(val) => val.is_exact(),
+            Value::Complex(val) => val.is_exact(),
+            Value::Quantity(val) => val.is_exact(),
+            Value::Vector(items) => items.iter().all(Value::is_exact),
+            Value::Matrix(matrix) => matrix.entries().iter().all(Value::is_exact),
+            Value::Expression(_) => true,
+        }
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            Value::Number(val) => Expr::exact(val.clone()),
+            Value::Complex(val) => {
+                let im = Expr::binary(BinaryOp::Mul, Expr::exact(val.im.clone()), Expr::ident("i"));
+                Expr::binary(BinaryOp::Add, Expr::exact(val.re.clone()), im)
+            }
+            Value::Quantity(val) => val.to_expr(),
+            Value::Vector(items) => vector_expr(items),
+            Value::Matrix(matrix) => Expr::new(
+                ExprKind::Vector(
+                    (0..matrix.rows())
+                        .map(|row| vector_expr(matrix.row(row)))
+                        .collect(),
+                ),
+                Default::default(),
+            ),
+            Value::Expression(expr) => expr.as_ref().clone(),
+        }
+    }
+}
+
+fn vector_expr(items: &[Value]) -> Expr {
+    Expr::new(
+        ExprKind::Vector(items.iter().map(Value::to_expr).collect()),
+        Default::default(),
+    )
+}
+
+fn write_items(f: &mut fmt::Formatter<'_>, items: &[Value]) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    write!(f, "]")
+}
+
+impl From<f64> for Value {
+    fn from(val: f64) -> Self {
+        Value::Number(Number::Float(val))
+    }
+}
+
+impl From<Number> for Value {
+    fn from(val: Number) -> Self {
+        Value::Number(val)
+    }
+}
+
+/// Formats with at most 12 significant digits and without trailing zeros.
+pub fn format_f64(val: f64) -> String {
+    if val.is_nan() {
+        return "NaN".to_owned();
+    }
+    if val.is_infinite() {
+        return if val > 0.0 { "∞" } else { "-∞" }.to_owned();
+    }
+    if val == 0.0 {
+        return "0".to_owned();
+    }
+
+    let exponent = val.abs().log10().floor() as i32;
+    if !(-6..15).contains(&exponent) {
+        let text = format!("{:.11e}", val);
+        let (mantissa, exp) = text.split_once('e').unwrap_or((&text, "0"));
+        return format!("{}e{}", trim_zeros(mantissa), exp);
+    }
+    let decimals = (11 - exponent).max(0) as usize;
+    trim_zeros(&format!("{:.*}", decimals, val)).to_owned()
+}
+
+fn trim_zeros(text: &str) -> &str {
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(val) => write!(f, "{}", val),
+            Value::Complex(val) => write!(f, "{}", val),
+            Value::Quantity(val) => write!(f, "{}", val),
+            Value::Vector(items) => write_items(f, items),
+            Value::Matrix(matrix) => {
+                write!(f, "[")?;
+                for row in 0..matrix.rows() {
+                    if row > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_items(f, matrix.row(row))?;
+                }
+                write!(f, "]")
+            }
+            Value::Expression(expr) => write!(f, "{}", expr),
+        }
+    }
+}
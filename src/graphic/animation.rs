@@ -17,6 +17,13 @@ impl<T> Animated<T> {
     pub fn done_to(target: T) -> Self {
         Animated::Done(target)
     }
+
+    pub fn value(&self) -> &T {
+        match self {
+            Animated::Animating(val, _, _) => val,
+            Animated::Done(val) => val,
+        }
+    }
 }
 
 impl<T: Clone> Animated<T> {
@@ -30,64 +37,80 @@ impl<T: Clone> Animated<T> {
 
 impl Animated<f32> {
     pub fn ensure_frame(&mut self, frame_time: f32) -> &f32 {
-        if let Animated::Animating(val, target, speed) = &self {
-            let new_val = val + speed * frame_time;
-            let normalized_speed = speed / speed;
-            if (target - new_val) * normalized_speed <= 0.0 {
-                *self = Animated::Done(val.clone());
+        if let Animated::Animating(val, target, speed) = self {
+            let new_val = *val + *speed * frame_time;
+            if (*target - new_val) * speed.signum() <= 0.0 {
+                *self = Animated::Done(*target);
+            } else {
+                *val = new_val;
             }
         }
-        match self {
-            Animated::Animating(val, _, _) => val,
-            Animated::Done(val) => val,
-        }
+        self.value()
     }
 }
 
 impl Animated<i32> {
     pub fn ensure_frame(&mut self, frame_time: f32) -> &i32 {
-        if let Animated::Animating(val, target, speed) = &self {
-            let new_val = val + speed * frame_time.round() as i32;
-            let normalized_speed = speed / speed;
-            if (target - new_val) * normalized_speed <= 0 {
-                *self = Animated::Done(val.clone());
+        if let Animated::Animating(val, target, speed) = self {
+            // Short frames still move by one, a step rounded to 0 would never arrive
+            let step = (*speed as f32 * frame_time).round() as i32;
+            let new_val = *val + if step == 0 { speed.signum() } else { step };
+            if (*target - new_val) * speed.signum() <= 0 {
+                *self = Animated::Done(*target);
+            } else {
+                *val = new_val;
             }
         }
-        match self {
-            Animated::Animating(val, _, _) => val,
-            Animated::Done(val) => val,
-        }
+        self.value()
     }
 }
 
 impl Animated<Vec2> {
+    /// Done once a step passes the target in the direction of `speed`.
     pub fn ensure_frame(&mut self, frame_time: f32) -> &Vec2 {
-        if let Animated::Animating(val, target, speed) = &self {
-            let new_val = val + speed * frame_time;
-            let normalized_speed = speed / speed;
-            if (target - new_val).x * normalized_speed.x <= 0.0 {
-                *self = Animated::Done(val.clone());
+        if let Animated::Animating(val, target, speed) = self {
+            let new_val = *val + *speed * frame_time;
+            if (*target - new_val).dot(*speed) <= 0.0 {
+                *self = Animated::Done(*target);
+            } else {
+                *val = new_val;
             }
         }
-        match self {
-            Animated::Animating(val, _, _) => val,
-            Animated::Done(val) => val,
-        }
+        self.value()
     }
 }
 
 impl Animated<Vec3> {
+    /// Done once a step passes the target in the direction of `speed`.
     pub fn ensure_frame(&mut self, frame_time: f32) -> &Vec3 {
-        if let Animated::Animating(val, target, speed) = &self {
-            let new_val = val + speed * frame_time;
-            let normalized_speed = speed / speed;
-            if (target - new_val).x * normalized_speed.x <= 0.0 {
-                *self = Animated::Done(val.clone());
+        if let Animated::Animating(val, target, speed) = self {
+            let new_val = *val + *speed * frame_time;
+            if (*target - new_val).dot(*speed) <= 0.0 {
+                *self = Animated::Done(*target);
+            } else {
+                *val = new_val;
             }
         }
-        match self {
-            Animated::Animating(val, _, _) => val,
-            Animated::Done(val) => val,
+        self.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_arrive_with_short_frames() {
+        let mut animated = Animated::done_to(0).animate_to(3, 10);
+        for _ in 0..3 {
+            animated.ensure_frame(0.001);
         }
+        assert!(matches!(animated, Animated::Done(3)));
+
+        let mut animated = Animated::done_to(0).animate_to(-100, -1000);
+        animated.ensure_frame(0.05);
+        assert_eq!(*animated.value(), -50);
+        animated.ensure_frame(0.05);
+        assert!(matches!(animated, Animated::Done(-100)));
     }
 }
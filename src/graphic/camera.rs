@@ -1,17 +1,28 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CameraDirection {
     Focal(Vec3),
     Facing(Vec3),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraProjection {
+    Perspective,
+    /// Looking down the z-axis at the xy-plane, `half_height` units above and below
+    /// the centre of the view
+    Orthographic { half_height: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GraphicCamera {
     pub position: Vec3,
     pub direction: CameraDirection,
+    pub projection: CameraProjection,
     pub view_field: f32,
     pub aspect_ratio: f32,
+    /// Height of the viewport in pixels
+    pub viewport_height: f32,
     pub z_near: f32,
     pub z_far: f32,
 }
@@ -21,28 +32,116 @@ impl GraphicCamera {
         Self {
             position: Vec3::new(-1.0,2.0, 5.0),
             direction: CameraDirection::Focal(Vec3::ZERO),
+            projection: CameraProjection::Perspective,
             view_field: 45.0,
             aspect_ratio: 1.0,
+            viewport_height: 600.0,
             z_near: 0.1,
             z_far: 100.0,
         }
     }
+    /// The 2D graphing view of the xy-plane around the origin.
+    pub fn plane() -> Self {
+        Self {
+            position: Vec3::new(0.0, 0.0, 50.0),
+            direction: CameraDirection::Facing(Vec3::NEG_Z),
+            projection: CameraProjection::Orthographic { half_height: 5.0 },
+            ..Self::default()
+        }
+    }
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self.projection, CameraProjection::Orthographic { .. })
+    }
     pub fn view_matrix(&self) -> Mat4 {
+        // Looking down the z-axis the y-axis points up the screen
+        let up = if self.is_orthographic() { Vec3::Y } else { Vec3::Z };
         match self.direction {
             CameraDirection::Focal(focal_point) => {
-                Mat4::look_at_rh(self.position, focal_point, Vec3::Z)
+                Mat4::look_at_rh(self.position, focal_point, up)
             }
             CameraDirection::Facing(facing_direction) => {
-                Mat4::look_to_rh(self.position, facing_direction, Vec3::Z)
+                Mat4::look_to_rh(self.position, facing_direction, up)
             }
         }
     }
+    /// Cube around the focal point roughly filling the view, covered by plots without bounds.
+    /// Looking at the plane it is the visible rectangle, as deep as it is wide.
+    pub fn view_box(&self) -> (Vec3, Vec3) {
+        if let CameraProjection::Orthographic { half_height } = self.projection {
+            let half_width = half_height * self.aspect_ratio;
+            let half_size = Vec3::new(half_width, half_height, half_width.max(half_height));
+            let centre = self.position.truncate().extend(0.0);
+            return (centre - half_size, centre + half_size);
+        }
+        let (focal_point, distance) = match self.direction {
+            CameraDirection::Focal(focal_point) => {
+                (focal_point, self.position.distance(focal_point))
+            }
+            CameraDirection::Facing(facing_direction) => {
+                (self.position + facing_direction.normalize_or_zero() * 5.0, 5.0)
+            }
+        };
+        let half_size = distance * (self.view_field.to_radians() / 2.0).tan();
+        (focal_point - Vec3::splat(half_size), focal_point + Vec3::splat(half_size))
+    }
+    /// Normalised device coordinates of `point`, `None` behind the camera.
+    pub fn project(&self, point: Vec3) -> Option<Vec3> {
+        let clip = self.projection_matrix() * self.view_matrix() * point.extend(1.0);
+        (clip.w > 0.0).then(|| clip.truncate() / clip.w)
+    }
+    /// Point of the plane `z = 0` shown at `ndc`, `None` when the view ray misses it.
+    pub fn plane_point(&self, ndc: Vec2) -> Option<Vec3> {
+        let inverse = (self.projection_matrix() * self.view_matrix()).inverse();
+        let near = inverse.project_point3(ndc.extend(-1.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+        let t = near.z / (near.z - far.z);
+        (0.0..=1.0).contains(&t).then(|| near.lerp(far, t))
+    }
+    /// World length covered by a pixel at the depth of `point`.
+    pub fn pixel_size(&self, point: Vec3) -> f32 {
+        if let CameraProjection::Orthographic { half_height } = self.projection {
+            return 2.0 * half_height / self.viewport_height;
+        }
+        let depth = -self.view_matrix().transform_point3(point).z;
+        2.0 * depth.max(self.z_near) * (self.view_field.to_radians() / 2.0).tan()
+            / self.viewport_height
+    }
+    /// Moves the plane view by `motion` pixels, following a drag.
+    pub fn pan(&mut self, motion: Vec2) {
+        let pixel = self.pixel_size(self.position);
+        self.position.x -= motion.x * pixel;
+        self.position.y += motion.y * pixel;
+    }
+    /// Scales the plane view by `factor`, keeping the point under `cursor` in
+    /// normalised device coordinates in place.
+    pub fn zoom(&mut self, factor: f32, cursor: Vec2) {
+        if let CameraProjection::Orthographic { half_height } = &mut self.projection {
+            let zoomed = (*half_height * factor).clamp(1e-4, 1e4);
+            let offset = cursor * Vec2::new(self.aspect_ratio, 1.0) * (*half_height - zoomed);
+            self.position.x += offset.x;
+            self.position.y += offset.y;
+            *half_height = zoomed;
+        }
+    }
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective_rh_gl(
-            self.view_field.to_radians(),
-            self.aspect_ratio,
-            self.z_near,
-            self.z_far,
-        )
+        match self.projection {
+            CameraProjection::Perspective => Mat4::perspective_rh_gl(
+                self.view_field.to_radians(),
+                self.aspect_ratio,
+                self.z_near,
+                self.z_far,
+            ),
+            CameraProjection::Orthographic { half_height } => {
+                let half_width = half_height * self.aspect_ratio;
+                Mat4::orthographic_rh_gl(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.z_near,
+                    self.z_far,
+                )
+            }
+        }
     }
 }
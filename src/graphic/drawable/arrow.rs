@@ -18,6 +18,7 @@ pub struct DrawableArrow {
     vao: glow::VertexArray,
     vbo: glow::Buffer,
     ebo: glow::Buffer,
+    ind_count: i32,
 }
 
 impl DrawableArrow {
@@ -33,7 +34,7 @@ impl DrawableArrow {
             gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
 
             // Original Position (f32;3)
-            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 7 * size_of::<f32>() as i32, 0);
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * size_of::<f32>() as i32, 0);
             gl.enable_vertex_attrib_array(0);
 
             gl.bind_vertex_array(None);
@@ -47,20 +48,29 @@ impl DrawableArrow {
                 vao,
                 vbo,
                 ebo,
+                ind_count: 0,
             }
         }
     }
 
+    /// Shaft from `start` to `end` with four barbs forming the head at `end`.
     pub fn set_points(&mut self, gl: &glow::Context, start: Vec3, end: Vec3) {
-        // let dir = (start - end).normalize();
-
-        let vertices = [
-            start.x, start.y, start.z, // Start
-            end.x, end.y, end.z, // End
-        ];
-
-        let indices = [0, 1];
-        // let indices = [0, 1, 1, 2, 1, 3];
+        let shaft = end - start;
+
+        let mut vertices = vec![start.x, start.y, start.z, end.x, end.y, end.z];
+        let mut indices: Vec<u32> = vec![0, 1];
+        if let Some(dir) = shaft.try_normalize() {
+            let head_length = shaft.length() * 0.15;
+            let (side, up) = dir.any_orthonormal_pair();
+            for i in 0..4 {
+                let angle = i as f32 * PI / 2.0;
+                let spread = (side * angle.cos() + up * angle.sin()) * 0.4;
+                let barb = end + (spread - dir) * head_length;
+                vertices.extend_from_slice(&[barb.x, barb.y, barb.z]);
+                indices.extend_from_slice(&[1, i + 2]);
+            }
+        }
+        self.ind_count = indices.len() as i32;
 
         unsafe {
             gl.bind_vertex_array(Some(self.vao));
@@ -111,7 +121,7 @@ impl GraphicDrawable for DrawableArrow {
 
             gl.bind_vertex_array(Some(self.vao));
             gl.depth_func(glow::LEQUAL);
-            gl.draw_elements(glow::LINES, 2, glow::UNSIGNED_INT, 0);
+            gl.draw_elements(glow::LINES, self.ind_count, glow::UNSIGNED_INT, 0);
 
             gl.bind_vertex_array(None);
         }
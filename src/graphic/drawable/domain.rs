@@ -0,0 +1,213 @@
+use glam::{Mat4, Vec2};
+use glow::HasContext;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Floats per vertex, position followed by texture coordinate.
+const VERTEX_SIZE: i32 = 5;
+
+/// Textured grid over a rectangle of the xy plane, optionally lifted by a height per vertex.
+pub struct DrawableDomainColoring {
+    color: [f32; 4],
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    ebo: glow::Buffer,
+    texture: glow::Texture,
+
+    ind_count: i32,
+
+    program: glow::NativeProgram,
+}
+
+impl DrawableDomainColoring {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array");
+
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+            let ebo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+            let stride = VERTEX_SIZE * size_of::<f32>() as i32;
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(
+                1,
+                2,
+                glow::FLOAT,
+                false,
+                stride,
+                3 * size_of::<f32>() as i32,
+            );
+            gl.enable_vertex_attrib_array(1);
+
+            gl.bind_vertex_array(None);
+
+            let texture = gl.create_texture().expect("Unable to create texture");
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MIN_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAG_FILTER,
+                glow::LINEAR as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_WRAP_S,
+                glow::CLAMP_TO_EDGE as i32,
+            );
+            gl.tex_parameter_i32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_WRAP_T,
+                glow::CLAMP_TO_EDGE as i32,
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+
+            Self {
+                color: [1.0f32; 4],
+                vao,
+                vbo,
+                ebo,
+                texture,
+                ind_count: 0,
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::DrawableDomainColoring)
+                    .expect("Drawable Domain Coloring program not created"),
+            }
+        }
+    }
+
+    /// Uploads an RGBA image, row 0 is the bottom edge of the rectangle.
+    pub fn set_image(&mut self, gl: &glow::Context, width: usize, height: usize, pixels: &[u8]) {
+        unsafe {
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(Some(pixels)),
+            );
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+
+    /// Builds a `cols` x `rows` grid spanning `min..max`, `heights` are row-major z values.
+    pub fn set_grid(
+        &mut self,
+        gl: &glow::Context,
+        min: Vec2,
+        max: Vec2,
+        cols: usize,
+        rows: usize,
+        heights: Option<&[f32]>,
+    ) {
+        let (cols, rows) = (cols.max(2), rows.max(2));
+        let mut vertices = Vec::with_capacity(cols * rows * VERTEX_SIZE as usize);
+        for row in 0..rows {
+            let v = row as f32 / (rows - 1) as f32;
+            for col in 0..cols {
+                let u = col as f32 / (cols - 1) as f32;
+                let position = min + (max - min) * Vec2::new(u, v);
+                let z = heights.map_or(0.0, |heights| heights[row * cols + col]);
+                vertices.extend_from_slice(&[position.x, position.y, z, u, v]);
+            }
+        }
+
+        let mut indices = Vec::<u32>::with_capacity((cols - 1) * (rows - 1) * 6);
+        for row in 0..rows - 1 {
+            for col in 0..cols - 1 {
+                let corner = (row * cols + col) as u32;
+                let above = corner + cols as u32;
+                indices.extend_from_slice(&[
+                    corner,
+                    corner + 1,
+                    above + 1,
+                    corner,
+                    above + 1,
+                    above,
+                ]);
+            }
+        }
+        self.ind_count = indices.len() as i32;
+
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&vertices[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.ebo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&indices[..]);
+            gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn set_program(&mut self, gl: &glow::Context, id: &ProgramId) -> Result<(), String> {
+        let program = PROGRAM_MANAGER.get_program(gl, id.clone());
+        if program.is_none() {
+            return Err(format!("Program {} is None.", id));
+        }
+        self.program = program.unwrap();
+        Ok(())
+    }
+}
+
+impl GraphicDrawable for DrawableDomainColoring {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            let image_location = gl.get_uniform_location(self.program, "image");
+            gl.uniform_1_i32(image_location.as_ref(), 0);
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LESS);
+            gl.draw_elements(glow::TRIANGLES, self.ind_count, glow::UNSIGNED_INT, 0);
+
+            gl.bind_vertex_array(None);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_buffer(self.ebo);
+            gl.delete_texture(self.texture);
+        };
+    }
+}
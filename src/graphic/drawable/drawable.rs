@@ -3,4 +3,6 @@ use crate::graphic::camera::GraphicCamera;
 pub trait GraphicDrawable {
     fn draw(&self, gl: &glow::Context, camera: &GraphicCamera);
     fn destroy(&self, gl: &glow::Context);
+    /// Advances animations by the seconds since the last frame.
+    fn update(&mut self, _frame_time: f32) {}
 }
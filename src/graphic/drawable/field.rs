@@ -0,0 +1,158 @@
+use glam::{Mat4, Vec3};
+use glow::HasContext;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Floats per arrow, origin, vector and colour.
+const INSTANCE_SIZE: i32 = 9;
+
+/// Unit arrow along x, a shaft with four barbs like [`DrawableArrow`](super::arrow::DrawableArrow).
+const TEMPLATE: [f32; 18] = [
+    0.0, 0.0, 0.0, //
+    1.0, 0.0, 0.0, //
+    0.75, 0.1, 0.0, //
+    0.75, -0.1, 0.0, //
+    0.75, 0.0, 0.1, //
+    0.75, 0.0, -0.1,
+];
+const TEMPLATE_INDICES: [u32; 10] = [0, 1, 1, 2, 1, 3, 1, 4, 1, 5];
+
+/// Many arrows drawn with one instanced call, the shader orients the template
+/// along each vector.
+pub struct DrawableVectorField {
+    program: glow::NativeProgram,
+    color: [f32; 4],
+    line_width: f32,
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    ebo: glow::Buffer,
+    instance_vbo: glow::Buffer,
+    instance_count: i32,
+}
+
+impl DrawableVectorField {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array");
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+            let ebo = gl.create_buffer().expect("Unable to create buffer");
+            let instance_vbo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&TEMPLATE[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * size_of::<f32>() as i32, 0);
+            gl.enable_vertex_attrib_array(0);
+
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&TEMPLATE_INDICES[..]);
+            gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(instance_vbo));
+            let stride = INSTANCE_SIZE * size_of::<f32>() as i32;
+            for attrib in 1..=3 {
+                let offset = (attrib as i32 - 1) * 3 * size_of::<f32>() as i32;
+                gl.vertex_attrib_pointer_f32(attrib, 3, glow::FLOAT, false, stride, offset);
+                gl.enable_vertex_attrib_array(attrib);
+                gl.vertex_attrib_divisor(attrib, 1);
+            }
+
+            gl.bind_vertex_array(None);
+
+            Self {
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::DrawableVectorField)
+                    .expect("Drawable Vector Field program not created"),
+                color: [1.0f32; 4],
+                line_width: 1.0,
+                vao,
+                vbo,
+                ebo,
+                instance_vbo,
+                instance_count: 0,
+            }
+        }
+    }
+
+    /// One arrow from each origin along its vector, in its own colour.
+    pub fn set_arrows(
+        &mut self,
+        gl: &glow::Context,
+        origins: &[Vec3],
+        vectors: &[Vec3],
+        colors: &[[f32; 3]],
+    ) {
+        let instances: Vec<f32> = origins
+            .iter()
+            .zip(vectors)
+            .zip(colors)
+            .flat_map(|((origin, vector), color)| {
+                [
+                    origin.x, origin.y, origin.z, vector.x, vector.y, vector.z, color[0], color[1],
+                    color[2],
+                ]
+            })
+            .collect();
+        self.instance_count = instances.len() as i32 / INSTANCE_SIZE;
+
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.instance_vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&instances[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+
+    /// Only the alpha is used, arrows carry their own colour.
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn set_line_width(&mut self, line_width: f32) {
+        self.line_width = line_width;
+    }
+}
+
+impl GraphicDrawable for DrawableVectorField {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+
+            gl.line_width(self.line_width);
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LEQUAL);
+            gl.draw_elements_instanced(
+                glow::LINES,
+                TEMPLATE_INDICES.len() as i32,
+                glow::UNSIGNED_INT,
+                0,
+                self.instance_count,
+            );
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_buffer(self.ebo);
+            gl.delete_buffer(self.instance_vbo);
+        }
+    }
+}
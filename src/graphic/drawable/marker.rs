@@ -0,0 +1,111 @@
+use glam::{Mat4, Vec3};
+use glow::HasContext;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Points marked by small crosses along the three axes.
+#[derive(Debug, Clone)]
+pub struct DrawableMarkers {
+    program: glow::NativeProgram,
+    color: [f32; 4],
+    line_width: f32,
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    vert_count: i32,
+}
+
+impl DrawableMarkers {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array.");
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+
+            // Original Position (f32;3)
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * size_of::<f32>() as i32, 0);
+            gl.enable_vertex_attrib_array(0);
+
+            gl.bind_vertex_array(None);
+
+            Self {
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::Default)
+                    .expect("Default program not created"),
+                color: [1.0f32; 4],
+                line_width: 1.0,
+                vao,
+                vbo,
+                vert_count: 0,
+            }
+        }
+    }
+
+    /// Crosses reaching `size` from each point.
+    pub fn set_points(&mut self, gl: &glow::Context, points: &[Vec3], size: f32) {
+        let vertices: Vec<f32> = points
+            .iter()
+            .flat_map(|point| {
+                [Vec3::X, Vec3::Y, Vec3::Z]
+                    .into_iter()
+                    .flat_map(move |axis| [*point - axis * size, *point + axis * size])
+            })
+            .flat_map(|vert| [vert.x, vert.y, vert.z])
+            .collect();
+        self.vert_count = (vertices.len() / 3) as i32;
+
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            let u8_buffer = bytemuck::cast_slice(&vertices[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn set_line_width(&mut self, line_width: f32) {
+        self.line_width = line_width;
+    }
+}
+
+impl GraphicDrawable for DrawableMarkers {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+
+            gl.line_width(self.line_width);
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LEQUAL);
+            gl.draw_arrays(glow::LINES, 0, self.vert_count);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+        }
+    }
+}
@@ -1,4 +1,10 @@
 pub mod arrow;
+pub mod domain;
 pub mod drawable;
+pub mod field;
 pub mod line;
+pub mod marker;
 pub mod polygon;
+pub mod polyline;
+pub mod scatter;
+pub mod surface;
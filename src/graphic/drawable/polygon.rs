@@ -126,11 +126,23 @@ impl GraphicDrawable for DrawablePolygon {
             let color_location = gl.get_uniform_location(self.program, "color");
             gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
 
+            // Translucent fills don't hide what is drawn behind them later
+            let translucent = self.color[3] < 1.0;
+            if translucent {
+                gl.enable(glow::BLEND);
+                gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+                gl.depth_mask(false);
+            }
+
             gl.bind_vertex_array(Some(self.vao));
             gl.depth_func(glow::LESS);
             gl.draw_elements(glow::TRIANGLES, self.ind_count * 3, glow::UNSIGNED_INT, 0);
 
             gl.bind_vertex_array(None);
+            if translucent {
+                gl.depth_mask(true);
+                gl.disable(glow::BLEND);
+            }
         }
     }
     fn destroy(&self, gl: &glow::Context) {
@@ -0,0 +1,127 @@
+use glam::{Mat4, Vec3};
+use glow::HasContext;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Paths through any number of points drawn as line strips, or as a loop when closed.
+#[derive(Debug, Clone)]
+pub struct DrawablePolyline {
+    program: glow::NativeProgram,
+    color: [f32; 4],
+    line_width: f32,
+    closed: bool,
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    /// First vertex and vertex count of each strip
+    strips: Vec<(i32, i32)>,
+}
+
+impl DrawablePolyline {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array.");
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+
+            // Original Position (f32;3)
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 3 * size_of::<f32>() as i32, 0);
+            gl.enable_vertex_attrib_array(0);
+
+            gl.bind_vertex_array(None);
+
+            Self {
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::DrawableLine)
+                    .expect("Drawable Line program not created"),
+                color: [1.0f32; 4],
+                line_width: 1.0,
+                closed: false,
+                vao,
+                vbo,
+                strips: Vec::new(),
+            }
+        }
+    }
+
+    pub fn set_points(&mut self, gl: &glow::Context, points: &[Vec3]) {
+        self.set_strips(gl, &[points.to_vec()]);
+    }
+
+    /// Separate pieces of one path, like a curve broken where it is undefined.
+    pub fn set_strips(&mut self, gl: &glow::Context, strips: &[Vec<Vec3>]) {
+        let mut vertices = Vec::new();
+        self.strips.clear();
+        for strip in strips {
+            self.strips
+                .push(((vertices.len() / 3) as i32, strip.len() as i32));
+            vertices.extend(strip.iter().flat_map(|point| [point.x, point.y, point.z]));
+        }
+
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            let u8_buffer = bytemuck::cast_slice(&vertices[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    pub fn set_line_width(&mut self, line_width: f32) {
+        self.line_width = line_width;
+    }
+
+    /// Joins the last point of each strip back to its first.
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+}
+
+impl GraphicDrawable for DrawablePolyline {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+
+            gl.line_width(self.line_width);
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LEQUAL);
+            let mode = if self.closed {
+                glow::LINE_LOOP
+            } else {
+                glow::LINE_STRIP
+            };
+            for &(first, count) in &self.strips {
+                gl.draw_arrays(mode, first, count);
+            }
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use glam::{Mat4, Vec3};
+use glow::HasContext;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Floats per point, centre, colour and size.
+const INSTANCE_SIZE: i32 = 7;
+
+/// Square around a point as a triangle strip, cut round by the shader.
+const TEMPLATE: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+/// Many round markers drawn with one instanced call, each in its own colour and
+/// with a size in pixels that doesn't change with the distance.
+pub struct DrawableScatter {
+    program: glow::NativeProgram,
+    color: [f32; 4],
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    instance_vbo: glow::Buffer,
+    instance_count: i32,
+}
+
+impl DrawableScatter {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array");
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+            let instance_vbo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&TEMPLATE[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, 2 * size_of::<f32>() as i32, 0);
+            gl.enable_vertex_attrib_array(0);
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(instance_vbo));
+            let stride = INSTANCE_SIZE * size_of::<f32>() as i32;
+            for (attrib, size, offset) in [(1, 3, 0), (2, 3, 3), (3, 1, 6)] {
+                let offset = offset * size_of::<f32>() as i32;
+                gl.vertex_attrib_pointer_f32(attrib, size, glow::FLOAT, false, stride, offset);
+                gl.enable_vertex_attrib_array(attrib);
+                gl.vertex_attrib_divisor(attrib, 1);
+            }
+
+            gl.bind_vertex_array(None);
+
+            Self {
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::DrawableScatter)
+                    .expect("Drawable Scatter program not created"),
+                color: [1.0f32; 4],
+                vao,
+                vbo,
+                instance_vbo,
+                instance_count: 0,
+            }
+        }
+    }
+
+    /// One marker at each point with its colour and diameter in pixels.
+    pub fn set_points(
+        &mut self,
+        gl: &glow::Context,
+        points: &[Vec3],
+        colors: &[[f32; 3]],
+        sizes: &[f32],
+    ) {
+        let instances: Vec<f32> = points
+            .iter()
+            .zip(colors)
+            .zip(sizes)
+            .flat_map(|((point, color), size)| {
+                [
+                    point.x, point.y, point.z, color[0], color[1], color[2], *size,
+                ]
+            })
+            .collect();
+        self.instance_count = instances.len() as i32 / INSTANCE_SIZE;
+
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.instance_vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&instances[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+
+    /// Only the alpha is used, points carry their own colour.
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+}
+
+impl GraphicDrawable for DrawableScatter {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+            let viewport_location = gl.get_uniform_location(self.program, "viewport");
+            gl.uniform_2_f32(
+                viewport_location.as_ref(),
+                camera.viewport_height * camera.aspect_ratio,
+                camera.viewport_height,
+            );
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LEQUAL);
+            gl.draw_arrays_instanced(glow::TRIANGLE_STRIP, 0, 4, self.instance_count);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_buffer(self.instance_vbo);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use glam::Mat4;
+use glow::HasContext;
+
+use crate::graphic::{
+    animation::Animated,
+    camera::GraphicCamera,
+    drawable::drawable::GraphicDrawable,
+    graphic::GraphicMVPMatrix,
+    plot::mesh::SurfaceMesh,
+    program::{PROGRAM_MANAGER, ProgramId},
+};
+
+/// Floats per vertex, position followed by normal.
+const VERTEX_SIZE: i32 = 6;
+
+/// Shaded triangle mesh of a sampled surface, translucent when the colour is.
+pub struct DrawableSurface {
+    color: [f32; 4],
+    vao: glow::VertexArray,
+    vbo: glow::Buffer,
+    ebo: glow::Buffer,
+
+    ind_count: i32,
+    /// Fraction of the triangles drawn, growing while the mesh sweeps in
+    sweep: Animated<f32>,
+
+    program: glow::NativeProgram,
+}
+
+impl DrawableSurface {
+    pub fn new(gl: &glow::Context) -> Self {
+        unsafe {
+            let vao = gl
+                .create_vertex_array()
+                .expect("Unable to create vertex array");
+
+            let vbo = gl.create_buffer().expect("Unable to create buffer");
+            let ebo = gl.create_buffer().expect("Unable to create buffer");
+
+            gl.bind_vertex_array(Some(vao));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ebo));
+            let stride = VERTEX_SIZE * size_of::<f32>() as i32;
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(
+                1,
+                3,
+                glow::FLOAT,
+                false,
+                stride,
+                3 * size_of::<f32>() as i32,
+            );
+            gl.enable_vertex_attrib_array(1);
+
+            gl.bind_vertex_array(None);
+
+            Self {
+                color: [1.0f32; 4],
+                vao,
+                vbo,
+                ebo,
+                ind_count: 0,
+                sweep: Animated::done_to(1.0),
+                program: PROGRAM_MANAGER
+                    .get_program(gl, ProgramId::DrawableSurface)
+                    .expect("Drawable Surface program not created"),
+            }
+        }
+    }
+
+    pub fn set_mesh(&mut self, gl: &glow::Context, mesh: &SurfaceMesh) {
+        let vertices: Vec<f32> = mesh
+            .positions
+            .iter()
+            .zip(&mesh.normals)
+            .flat_map(|(position, normal)| {
+                [
+                    position.x, position.y, position.z, normal.x, normal.y, normal.z,
+                ]
+            })
+            .collect();
+        self.ind_count = mesh.indices.len() as i32;
+
+        unsafe {
+            gl.bind_vertex_array(Some(self.vao));
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&vertices[..]);
+            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.ebo));
+            let u8_buffer: &[u8] = bytemuck::cast_slice(&mesh.indices[..]);
+            gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, u8_buffer, glow::STATIC_DRAW);
+
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        self.color = color;
+    }
+
+    /// Reveals the triangles in their order over `duration` seconds.
+    pub fn set_sweep(&mut self, duration: f32) {
+        self.sweep = Animated::done_to(0.0).animate_to(1.0, 1.0 / duration);
+    }
+}
+
+impl GraphicDrawable for DrawableSurface {
+    fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        unsafe {
+            gl.use_program(Some(self.program));
+            let mvp_transform = GraphicMVPMatrix::from_camera(camera, Mat4::IDENTITY);
+            mvp_transform.assign_gl_program(gl, self.program);
+
+            let color_location = gl.get_uniform_location(self.program, "color");
+            gl.uniform_4_f32_slice(color_location.as_ref(), &self.color);
+
+            // Translucent meshes don't hide what is drawn behind them later
+            let translucent = self.color[3] < 1.0;
+            if translucent {
+                gl.enable(glow::BLEND);
+                gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+                gl.depth_mask(false);
+            }
+
+            gl.bind_vertex_array(Some(self.vao));
+            gl.depth_func(glow::LESS);
+            let triangles = (self.ind_count / 3) as f32 * self.sweep.value();
+            gl.draw_elements(glow::TRIANGLES, triangles as i32 * 3, glow::UNSIGNED_INT, 0);
+
+            gl.bind_vertex_array(None);
+            if translucent {
+                gl.depth_mask(true);
+                gl.disable(glow::BLEND);
+            }
+        }
+    }
+
+    fn update(&mut self, frame_time: f32) {
+        self.sweep.ensure_frame(frame_time);
+    }
+
+    fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_vertex_array(self.vao);
+            gl.delete_buffer(self.vbo);
+            gl.delete_buffer(self.ebo);
+        };
+    }
+}
@@ -3,8 +3,9 @@ use glow::HasContext;
 
 use crate::graphic::{
     camera::GraphicCamera,
-    drawable::{arrow::DrawableArrow, drawable::GraphicDrawable, line::DrawableLine, polygon::DrawablePolygon},
-    program::{PROGRAM_MANAGER, ProgramId},
+    grid::GraphicGrid,
+    program::PROGRAM_MANAGER,
+    scene::GraphicScene,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -48,61 +49,40 @@ impl GraphicMVPMatrix {
 pub struct GraphicUpdateOptions {
     pub drag_motion: Vec2,
     pub drag_button: Option<egui::PointerButton>,
+    /// Scrolled pixels, upwards is positive
+    pub scroll: f32,
+    /// Pointer over the view in normalised device coordinates
+    pub cursor: Option<Vec2>,
 }
 
+/// Zoom factor of the plane view per scrolled pixel.
+const ZOOM_SPEED: f32 = 0.002;
+
 pub struct GraphicRenderer {
     pub camera: GraphicCamera,
+    pub scene: GraphicScene,
+    pub grid: GraphicGrid,
 
     pub drag_scale: f32,
 
     last_frame_time: std::time::Instant,
     frame_time: f32,
     depth_buffer: Option<glow::Renderbuffer>,
-
-    test_line: DrawableLine,
-    test_polygon: DrawablePolygon,
-    test_arrow: DrawableArrow,
 }
 
 impl GraphicRenderer {
     pub fn default<'a>(cc: &'a eframe::CreationContext<'a>) -> Option<Self> {
-        let gl = cc.gl.as_ref().expect("Unable to use gl");
-
-        let mut test_line = DrawableLine::new(gl);
-        test_line.set_color([1.0, 0.0, 1.0, 1.0]);
-        test_line.set_points(gl, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
-        test_line.set_program(gl, &ProgramId::Default).unwrap();
-
-        let mut test_polygon = DrawablePolygon::new(gl);
-        test_polygon.set_color([1.0, 0.0, 0.0, 1.0]);
-        test_polygon.set_verts(
-            gl,
-            &vec![
-                Vec3::new(0.0, 0.5, 1.0),
-                Vec3::new(0.3, 0.3, 1.0),
-                Vec3::new(0.5, 0.0, 1.0),
-                Vec3::new(0.3, -0.3, 1.0),
-                Vec3::new(0.0, -0.5, 1.0),
-                Vec3::new(-0.3, -0.3, 1.0),
-                Vec3::new(-0.5, 0.0, 1.0),
-                Vec3::new(-0.3, 0.3, 1.0),
-            ],
-        );
-
-        let mut test_arrow = DrawableArrow::new(gl);
-        test_arrow.set_color([0.0,0.0,1.0,1.0]);
-        test_arrow.set_line_width(4.0);
-        test_arrow.set_points(gl, Vec3::new(0.0, 0.0, -1.0), Vec3::new(1.0, 1.5, 0.5));
+        // Drawables are created by the scene once painting starts
+        cc.gl.as_ref()?;
 
         Some(Self {
             camera: GraphicCamera::default(),
+            scene: GraphicScene::new(),
+            grid: GraphicGrid::default(),
             drag_scale: 0.05,
             last_frame_time: std::time::Instant::now(),
             frame_time: 0.0f32,
             depth_buffer: None,
-            test_line,
-            test_polygon,
-            test_arrow
         })
     }
 
@@ -112,25 +92,30 @@ impl GraphicRenderer {
         self.frame_time += elapsed_time;
 
         // Update
-        // let green_val = ((self.frame_time).sin() / 2.0) + 0.5;
-        let mut end_point = self.test_line.get_end_point();
-        end_point.x = (self.frame_time * 3.0).cos() / 2.0;
-        end_point.y = (self.frame_time * 3.0).sin() / 2.0;
-        self.test_line
-            .set_points(gl, self.test_line.get_start_point(), end_point);
-        self.test_line.set_line_width((self.frame_time) % 6.0);
         // self.camera.position.z = (self.frame_time * 0.5).cos() * -10.0;
 
-        if let Some(drag_button) = opt.drag_button {
-            match drag_button {
-                egui::PointerButton::Middle => {
-                    self.camera.position.x += opt.drag_motion.x * self.drag_scale * -1.0;
-                    self.camera.position.z += opt.drag_motion.y * self.drag_scale;
+        if self.camera.is_orthographic() {
+            // The plane follows any drag and zooms around the pointer
+            if opt.drag_button.is_some() {
+                self.camera.pan(opt.drag_motion);
+            }
+            if let Some(cursor) = opt.cursor
+                && opt.scroll != 0.0
+            {
+                self.camera.zoom((-opt.scroll * ZOOM_SPEED).exp(), cursor);
+            }
+        } else {
+            if let Some(drag_button) = opt.drag_button {
+                match drag_button {
+                    egui::PointerButton::Middle => {
+                        self.camera.position.x += opt.drag_motion.x * self.drag_scale * -1.0;
+                        self.camera.position.z += opt.drag_motion.y * self.drag_scale;
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
+            self.camera.direction = super::camera::CameraDirection::Focal(Vec3::new(0.0, 0.0, 0.0));
         }
-        self.camera.direction = super::camera::CameraDirection::Focal(Vec3::new(0.0, 0.0, 0.0));
         // self.camera.direction =
         //     super::camera::CameraDirection::Focal(end_point.clone().normalize());
 
@@ -142,16 +127,14 @@ impl GraphicRenderer {
             gl.clear_depth(1.0);
             gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
 
-            // 正交投影
-            // Mat4::orthographic_rh_gl(left, right, bottom, top, near, far);
-
             gl.enable(glow::DEPTH_TEST);
             gl.depth_mask(true);
             gl.depth_range_f32(0.0, 1.0);
 
-            self.test_polygon.draw(gl, &self.camera);
-            self.test_line.draw(gl, &self.camera);
-            self.test_arrow.draw(gl, &self.camera);
+            self.scene.sync(gl);
+            self.scene.update(elapsed_time);
+            self.grid.draw(gl, &self.camera);
+            self.scene.draw(gl, &self.camera);
 
             gl.use_program(None);
         }
@@ -167,8 +150,8 @@ impl GraphicRenderer {
                 gl.delete_renderbuffer(rb);
             }
         }
-        self.test_line.destroy(gl);
-        self.test_polygon.destroy(gl);
+        self.scene.destroy(gl);
+        self.grid.destroy(gl);
     }
 
     fn ensure_depth_buffer(&mut self, gl: &glow::Context) {
@@ -0,0 +1,120 @@
+use std::f32::consts::TAU;
+
+use glam::{IVec2, Vec2, Vec3};
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::{drawable::GraphicDrawable, polyline::DrawablePolyline},
+};
+
+/// Segments of a full ring.
+const RING_SEGMENTS: usize = 128;
+/// Radial lines of the polar grid.
+const SPOKES: usize = 12;
+const LINE_COLOR: [f32; 4] = [0.85, 0.85, 0.85, 1.0];
+const AXIS_COLOR: [f32; 4] = [0.45, 0.45, 0.45, 1.0];
+
+/// Reference lines in the xy-plane drawn under the plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridStyle {
+    #[default]
+    Cartesian,
+    /// Rings around the origin and radial lines
+    Polar,
+    Hidden,
+}
+
+/// The grid and axes across the part of the plane in view, rebuilt when the view
+/// needs a different spacing or moves past a line.
+#[derive(Default)]
+pub struct GraphicGrid {
+    pub style: GridStyle,
+    /// Style, spacing and range of lines in multiples of it the drawables were built for
+    built: Option<(GridStyle, f32, IVec2, IVec2)>,
+    lines: Option<DrawablePolyline>,
+    axes: Option<DrawablePolyline>,
+}
+
+impl GraphicGrid {
+    pub fn draw(&mut self, gl: &glow::Context, camera: &GraphicCamera) {
+        if self.style == GridStyle::Hidden {
+            return;
+        }
+        let (min, max) = camera.view_box();
+        let extent = (max - min).truncate().min_element() / 2.0;
+        let spacing = 10f32.powf((extent / 2.0).log10().floor());
+        let lower = (min.truncate() / spacing).floor().as_ivec2();
+        let upper = (max.truncate() / spacing).ceil().as_ivec2();
+        let layout = (self.style, spacing, lower, upper);
+        if self.built != Some(layout) {
+            self.destroy(gl);
+            let (min, max) = (lower.as_vec2() * spacing, upper.as_vec2() * spacing);
+            let mut lines = DrawablePolyline::new(gl);
+            lines.set_color(LINE_COLOR);
+            lines.set_strips(gl, &grid_lines(self.style, spacing, lower, upper));
+            let mut axes = DrawablePolyline::new(gl);
+            axes.set_color(AXIS_COLOR);
+            axes.set_line_width(1.5);
+            let reach = min.abs().max(max.abs()).max_element();
+            axes.set_strips(
+                gl,
+                &[
+                    vec![Vec3::new(min.x, 0.0, 0.0), Vec3::new(max.x, 0.0, 0.0)],
+                    vec![Vec3::new(0.0, min.y, 0.0), Vec3::new(0.0, max.y, 0.0)],
+                    vec![Vec3::new(0.0, 0.0, -reach), Vec3::new(0.0, 0.0, reach)],
+                ],
+            );
+            self.lines = Some(lines);
+            self.axes = Some(axes);
+            self.built = Some(layout);
+        }
+        for drawable in [&self.lines, &self.axes].into_iter().flatten() {
+            drawable.draw(gl, camera);
+        }
+    }
+
+    pub fn destroy(&mut self, gl: &glow::Context) {
+        for drawable in [self.lines.take(), self.axes.take()].into_iter().flatten() {
+            drawable.destroy(gl);
+        }
+        self.built = None;
+    }
+}
+
+/// Lines `spacing` apart over the rectangle from `lower` to `upper` times the spacing,
+/// or the rings and radial lines crossing it.
+fn grid_lines(style: GridStyle, spacing: f32, lower: IVec2, upper: IVec2) -> Vec<Vec<Vec3>> {
+    let (min, max) = (lower.as_vec2() * spacing, upper.as_vec2() * spacing);
+    match style {
+        GridStyle::Cartesian => {
+            let columns = (lower.x..=upper.x).filter(|&i| i != 0).map(|i| {
+                let x = i as f32 * spacing;
+                vec![Vec3::new(x, min.y, 0.0), Vec3::new(x, max.y, 0.0)]
+            });
+            let rows = (lower.y..=upper.y).filter(|&j| j != 0).map(|j| {
+                let y = j as f32 * spacing;
+                vec![Vec3::new(min.x, y, 0.0), Vec3::new(max.x, y, 0.0)]
+            });
+            columns.chain(rows).collect()
+        }
+        GridStyle::Polar => {
+            let nearest = Vec2::ZERO.clamp(min, max).length();
+            let farthest = min.abs().max(max.abs()).length();
+            let direction = |angle: f32| Vec3::new(angle.cos(), angle.sin(), 0.0);
+            let first = ((nearest / spacing).ceil() as usize).max(1);
+            let last = (farthest / spacing).ceil() as usize;
+            let rings = (first..=last).map(|i| {
+                let radius = i as f32 * spacing;
+                (0..=RING_SEGMENTS)
+                    .map(|k| direction(TAU * k as f32 / RING_SEGMENTS as f32) * radius)
+                    .collect()
+            });
+            let spokes = (0..SPOKES).map(|k| {
+                let direction = direction(TAU * k as f32 / SPOKES as f32);
+                vec![direction * nearest, direction * farthest]
+            });
+            rings.chain(spokes).collect()
+        }
+        GridStyle::Hidden => Vec::new(),
+    }
+}
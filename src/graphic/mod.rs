@@ -2,4 +2,7 @@ pub mod animation;
 pub mod camera;
 pub mod drawable;
 pub mod graphic;
+pub mod grid;
+pub mod plot;
 pub mod program;
+pub mod scene;
@@ -0,0 +1,117 @@
+use glam::Vec3;
+
+use crate::{calc::error::CalcError, graphic::camera::GraphicCamera};
+
+/// How often a step between the initial samples may be halved.
+const MAX_DEPTH: u32 = 12;
+/// Largest distance in pixels between the curve and a drawn segment.
+const PIXEL_ERROR: f32 = 0.5;
+/// Evaluations after which the remaining steps are drawn as they are.
+const MAX_EVALUATIONS: usize = 200_000;
+/// Segments are only refined within this many view boxes around the view.
+const VIEW_MARGIN: f32 = 1.0;
+
+/// Samples `point(t)` over `a..b` into strips, starting from `samples` even steps.
+///
+/// A step is halved until its midpoint lies within [`PIXEL_ERROR`] of the segment
+/// as seen by `camera`. Steps still bent at the finest scale hide a jump or a pole
+/// and break the strip there, as do undefined points, which `point` returns as
+/// `Ok(None)`.
+pub fn sample_curve(
+    camera: &GraphicCamera,
+    (a, b): (f64, f64),
+    samples: usize,
+    point: impl FnMut(f64) -> Result<Option<Vec3>, CalcError>,
+) -> Result<Vec<Vec<Vec3>>, CalcError> {
+    let (min, max) = camera.view_box();
+    let margin = (max - min) * VIEW_MARGIN;
+    let mut sampler = Sampler {
+        camera,
+        min: min - margin,
+        max: max + margin,
+        point,
+        strips: vec![Vec::new()],
+        evaluations: 0,
+    };
+    let mut previous = (a, sampler.eval(a)?);
+    sampler.push(previous.1);
+    for i in 1..samples {
+        let t = a + (b - a) * i as f64 / (samples - 1) as f64;
+        let next = (t, sampler.eval(t)?);
+        sampler.refine(previous, next, 0)?;
+        previous = next;
+    }
+    let mut strips = sampler.strips;
+    strips.retain(|strip| strip.len() > 1);
+    Ok(strips)
+}
+
+struct Sampler<'a, F> {
+    camera: &'a GraphicCamera,
+    /// Box outside which segments are not refined
+    min: Vec3,
+    max: Vec3,
+    point: F,
+    strips: Vec<Vec<Vec3>>,
+    evaluations: usize,
+}
+
+impl<F: FnMut(f64) -> Result<Option<Vec3>, CalcError>> Sampler<'_, F> {
+    fn eval(&mut self, t: f64) -> Result<Option<Vec3>, CalcError> {
+        self.evaluations += 1;
+        (self.point)(t)
+    }
+
+    /// Adds the points after `start` up to `end`, which already ends the last strip.
+    fn refine(
+        &mut self,
+        start: (f64, Option<Vec3>),
+        end: (f64, Option<Vec3>),
+        depth: u32,
+    ) -> Result<(), CalcError> {
+        let t = (start.0 + end.0) / 2.0;
+        let middle = (t, self.eval(t)?);
+        let resolved = match (start.1, middle.1, end.1) {
+            (Some(p0), Some(pm), Some(p1)) => self.is_flat(p0, pm, p1),
+            (None, None, None) => true,
+            // Bisect towards the edge of the domain
+            _ => false,
+        };
+        if resolved || self.evaluations >= MAX_EVALUATIONS {
+            self.push(middle.1);
+            self.push(end.1);
+        } else if depth == MAX_DEPTH {
+            self.break_strip();
+            self.push(end.1);
+        } else {
+            self.refine(start, middle, depth + 1)?;
+            self.refine(middle, end, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `middle` lies close enough to the segment to be left out, or all three
+    /// points lie on the far side of one face of the box.
+    fn is_flat(&self, start: Vec3, middle: Vec3, end: Vec3) -> bool {
+        let below = start.cmplt(self.min) & middle.cmplt(self.min) & end.cmplt(self.min);
+        let above = start.cmpgt(self.max) & middle.cmpgt(self.max) & end.cmpgt(self.max);
+        if (below | above).any() {
+            return true;
+        }
+        let error = middle.distance((start + end) / 2.0);
+        error <= PIXEL_ERROR * self.camera.pixel_size(middle)
+    }
+
+    fn push(&mut self, point: Option<Vec3>) {
+        match point {
+            Some(point) => self.strips.last_mut().unwrap().push(point),
+            None => self.break_strip(),
+        }
+    }
+
+    fn break_strip(&mut self) {
+        if !self.strips.last().unwrap().is_empty() {
+            self.strips.push(Vec::new());
+        }
+    }
+}
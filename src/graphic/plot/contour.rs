@@ -0,0 +1,160 @@
+use glam::DVec2;
+
+use crate::{
+    calc::{
+        ast::Expr,
+        error::CalcError,
+        eval::Environment,
+        value::{Value, format_f64},
+    },
+    graphic::{
+        plot::{
+            count_arg,
+            marching::marching_squares,
+            plot_function_of,
+            surface::{explicit_domain, height_limit},
+        },
+        scene::SceneGeometry,
+    },
+};
+
+/// Levels between the lowest and highest value unless given.
+const LEVELS: usize = 10;
+const MAX_LEVELS: usize = 100;
+/// Coarse cells per side when tracing a level and how often they are split near it.
+const CELLS: usize = 48;
+const DEPTH: u32 = 2;
+/// Lines on the surface are lifted by this fraction of its height so they aren't
+/// hidden in it.
+const LIFT: f64 = 0.002;
+
+/// `contour(f[, levels])`, `contour(f, levels, r)` or `contour(f, levels, x0, x1, y0, y1)`
+/// over the same rectangle as `surface`. `levels` is a vector of heights or their
+/// number. Each level is drawn on the graph and projected onto the floor below it.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let (func, levels, domain) = match args {
+        [func] => (func, None, vec![func.clone()]),
+        [func, levels, rest @ ..] if matches!(rest.len(), 0 | 1 | 4 | 5) => {
+            let domain = std::iter::once(func).chain(rest).cloned().collect();
+            (func, Some(levels), domain)
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!(
+                    "contour takes 1, 2, 3, 6 or 7 arguments, got {}",
+                    args.len()
+                ),
+                expr.span,
+            ));
+        }
+    };
+    let (_, bounds, resolution) = explicit_domain(env, expr, &domain, "contour")?;
+    let func = plot_function_of(env, func, &["x", "y"])?;
+
+    let limit = height_limit(bounds);
+    let height = |x: f64, y: f64| match env.eval_real(&func, &[("x", x), ("y", y)]) {
+        Ok(z) if z.is_finite() && z.abs() <= limit => Ok(z),
+        Ok(_) => Ok(f64::NAN),
+        Err(err) => Err(err),
+    };
+    let [x0, x1, y0, y1] = bounds;
+    let (mut lowest, mut highest) = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut first_error = None;
+    for row in 0..resolution {
+        let y = y0 + (y1 - y0) * row as f64 / (resolution - 1) as f64;
+        for col in 0..resolution {
+            let x = x0 + (x1 - x0) * col as f64 / (resolution - 1) as f64;
+            match height(x, y) {
+                Ok(z) if !z.is_nan() => {
+                    lowest = lowest.min(z);
+                    highest = highest.max(z);
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+    }
+    if lowest > highest {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The function is undefined on the whole domain", expr.span)
+        }));
+    }
+
+    let levels = match levels {
+        Some(arg) => match env.eval(arg)? {
+            Value::Vector(items) => items
+                .iter()
+                .map(|item| item.as_f64().filter(|level| level.is_finite()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| CalcError::new("Expected real levels", arg.span))?,
+            _ => spaced_levels(lowest, highest, count_arg(env, arg, 1, MAX_LEVELS)?),
+        },
+        None => spaced_levels(lowest, highest, LEVELS),
+    };
+
+    let lift = (highest - lowest) * LIFT;
+    let mut strips = Vec::new();
+    let mut labels = Vec::new();
+    for level in levels {
+        let traced = marching_squares(
+            |point| height(point.x, point.y).unwrap_or(f64::NAN) - level,
+            DVec2::new(x0, y0),
+            DVec2::new(x1, y1),
+            CELLS,
+            DEPTH,
+        );
+        let Some(longest) = traced.iter().max_by_key(|strip| strip.len()) else {
+            continue;
+        };
+        let anchor = longest[longest.len() / 2];
+        labels.push((
+            anchor.as_vec2().extend(level as f32),
+            format_f64(round_significant(level)),
+        ));
+        for strip in traced {
+            for z in [level + lift, lowest] {
+                strips.push(
+                    strip
+                        .iter()
+                        .map(|point| point.as_vec2().extend(z as f32))
+                        .collect(),
+                );
+            }
+        }
+    }
+    if strips.is_empty() {
+        return Err(CalcError::new(
+            format!(
+                "No level is reached, the function ranges from {} to {}",
+                format_f64(round_significant(lowest)),
+                format_f64(round_significant(highest))
+            ),
+            expr.span,
+        ));
+    }
+    Ok(vec![
+        SceneGeometry::Polyline {
+            strips,
+            closed: false,
+        },
+        SceneGeometry::Labels(labels),
+    ])
+}
+
+/// `count` levels evenly spaced strictly between `lowest` and `highest`.
+fn spaced_levels(lowest: f64, highest: f64, count: usize) -> Vec<f64> {
+    (1..=count)
+        .map(|i| lowest + (highest - lowest) * i as f64 / (count + 1) as f64)
+        .collect()
+}
+
+/// Four significant digits are plenty for a label.
+pub(super) fn round_significant(val: f64) -> f64 {
+    format!("{:.3e}", val).parse().unwrap_or(val)
+}
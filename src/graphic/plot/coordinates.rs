@@ -0,0 +1,139 @@
+use std::f64::consts::{PI, TAU};
+
+use glam::{DVec3, Vec3};
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+    },
+    graphic::{
+        camera::GraphicCamera,
+        plot::{
+            curve::{build_graph, close_loop, sample_strips},
+            surface::{RESOLUTION, height_limit, sample_grid},
+        },
+        scene::SceneGeometry,
+    },
+};
+
+/// Radius of the disc a cylindrical graph covers, like the square of `surface`.
+const CYLINDER_RADIUS: f64 = 2.0;
+
+/// The system of a plot like `r = f(theta)`, declared by the coordinate on the left.
+/// Angles may also be written `θ` and `φ`, `rho` as `ρ`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateSystem {
+    /// `y = f(x)`, the graph across the view
+    Cartesian,
+    /// `r = f(theta)` in the plane
+    Polar,
+    /// `z = f(r, theta)`
+    Cylindrical,
+    /// `rho = f(theta, phi)` with `phi` measured from the z-axis
+    Spherical,
+}
+
+impl CoordinateSystem {
+    /// The system of `lhs = rhs` when `lhs` is one of its coordinates and `rhs` a
+    /// function of the others, an assignment like `r = 2` is left alone.
+    pub fn of(env: &Environment, lhs: &Expr, rhs: &Expr) -> Option<Self> {
+        let ExprKind::Ident(name) = &lhs.kind else {
+            return None;
+        };
+        let system = match name.as_str() {
+            "y" => CoordinateSystem::Cartesian,
+            "r" => CoordinateSystem::Polar,
+            "z" => CoordinateSystem::Cylindrical,
+            "rho" | "ρ" => CoordinateSystem::Spherical,
+            _ => return None,
+        };
+        let free: Vec<String> = rhs
+            .identifiers()
+            .into_iter()
+            .filter(|name| env.get_variable(name).is_none())
+            .collect();
+        let params = system.params();
+        (!free.is_empty() && free.iter().all(|name| params.contains(&name.as_str())))
+            .then_some(system)
+    }
+
+    fn params(self) -> &'static [&'static str] {
+        match self {
+            CoordinateSystem::Cartesian => &["x"],
+            CoordinateSystem::Polar => &["theta", "θ"],
+            CoordinateSystem::Cylindrical => &["r", "theta", "θ"],
+            CoordinateSystem::Spherical => &["theta", "θ", "phi", "φ"],
+        }
+    }
+}
+
+/// Samples `y = f(x)` like `graph(f)`, `r = f(theta)` over `0..2π`, `z = f(r, theta)` over a disc or
+/// `rho = f(theta, phi)` over the sphere of directions, mapped to Cartesian points.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    camera: &GraphicCamera,
+) -> Result<SceneGeometry, CalcError> {
+    let system = match &expr.kind {
+        ExprKind::Equation(lhs, rhs) => {
+            CoordinateSystem::of(env, lhs, rhs).map(|system| (system, rhs))
+        }
+        _ => None,
+    };
+    let Some((system, func)) = system else {
+        return Err(CalcError::new("Not a coordinate plot", expr.span));
+    };
+    let eval = |r: f64, theta: f64, phi: f64| {
+        let bindings = [
+            ("r", r),
+            ("theta", theta),
+            ("θ", theta),
+            ("phi", phi),
+            ("φ", phi),
+        ];
+        env.eval_real(func, &bindings)
+            .map(|val| val.is_finite().then_some(val))
+    };
+
+    match system {
+        CoordinateSystem::Cartesian => build_graph(env, expr, std::slice::from_ref(func), camera),
+        CoordinateSystem::Polar => {
+            let mut strips =
+                sample_strips(env, expr, "curve", (0.0, TAU), None, camera, |theta| {
+                    Ok(eval(0.0, theta, 0.0).map(|r| {
+                        r.map(|r| {
+                            Vec3::new((r * theta.cos()) as f32, (r * theta.sin()) as f32, 0.0)
+                        })
+                    }))
+                })?;
+            let closed = close_loop(&mut strips);
+            Ok(SceneGeometry::Polyline { strips, closed })
+        }
+        CoordinateSystem::Cylindrical => {
+            let bounds = [0.0, CYLINDER_RADIUS, 0.0, TAU];
+            let limit = height_limit([-CYLINDER_RADIUS, CYLINDER_RADIUS, 0.0, 0.0]);
+            sample_grid(expr, bounds, RESOLUTION, |r, theta| {
+                Ok(eval(r, theta, 0.0).map(|z| {
+                    z.filter(|z| z.abs() <= limit)
+                        .map(|z| DVec3::new(r * theta.cos(), r * theta.sin(), z).as_vec3())
+                }))
+            })
+            .map(SceneGeometry::Surface)
+        }
+        // Sampled over (phi, theta) so that the front faces look outwards
+        CoordinateSystem::Spherical => {
+            sample_grid(expr, [0.0, PI, 0.0, TAU], RESOLUTION, |phi, theta| {
+                Ok(eval(0.0, theta, phi).map(|rho| {
+                    rho.map(|rho| {
+                        let (sin, cos) = phi.sin_cos();
+                        let direction = DVec3::new(sin * theta.cos(), sin * theta.sin(), cos);
+                        (direction * rho).as_vec3()
+                    })
+                }))
+            })
+            .map(SceneGeometry::Surface)
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::f64::consts::TAU;
+
+use glam::Vec3;
+
+use crate::{
+    calc::{ast::Expr, error::CalcError, eval::Environment, value::Value},
+    graphic::{
+        camera::GraphicCamera,
+        plot::{adaptive::sample_curve, count_arg, plot_function, real_arg, vector_point},
+        scene::SceneGeometry,
+    },
+};
+
+/// Even steps refined by [`sample_curve`] unless given.
+const SAMPLES: usize = 200;
+const MAX_SAMPLES: usize = 100_000;
+
+/// `curve(r)` over `0..2π`, `curve(r, a, b)` or `curve(r, a, b, n)` for a 2- or 3-vector `r(t)`.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    camera: &GraphicCamera,
+) -> Result<SceneGeometry, CalcError> {
+    let (func, a, b, samples) = match args {
+        [func] => (func, 0.0, TAU, None),
+        [func, a, b, rest @ ..] if rest.len() <= 1 => {
+            (func, real_arg(env, a)?, real_arg(env, b)?, rest.first())
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!("curve takes 1, 3 or 4 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let (func, var) = plot_function(env, func, "t")?;
+
+    let mut strips = sample_strips(env, expr, "curve", (a, b), samples, camera, |t| {
+        match env.eval_with(&func, &[(&var, Value::from(t))]) {
+            Ok(val) => match vector_point(&val, &[2, 3]) {
+                Some(point) => Ok(Ok(point)),
+                None => Err(CalcError::new(
+                    format!("Expected a 2- or 3-vector, got {}", val.type_name()),
+                    func.span,
+                )),
+            },
+            Err(err) => Ok(Err(err)),
+        }
+    })?;
+
+    let closed = close_loop(&mut strips);
+    Ok(SceneGeometry::Polyline { strips, closed })
+}
+
+/// Curves ending where they started like circles and knots are drawn as a loop,
+/// whether the single strip was closed.
+pub(super) fn close_loop(strips: &mut [Vec<Vec3>]) -> bool {
+    let [strip] = strips else {
+        return false;
+    };
+    let (min, max) = strip
+        .iter()
+        .fold((strip[0], strip[0]), |(min, max), point| {
+            (min.min(*point), max.max(*point))
+        });
+    let size = (max - min).length();
+    if strip.len() > 2 && strip[0].distance(strip[strip.len() - 1]) <= size * 1e-5 {
+        strip.pop();
+        return true;
+    }
+    false
+}
+
+/// `graph(f)` across the view or `graph(f, a, b[, n])` for `y = f(x)`. Complex values
+/// leave a gap like undefined ones.
+pub fn build_graph(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    camera: &GraphicCamera,
+) -> Result<SceneGeometry, CalcError> {
+    let (func, a, b, samples) = match args {
+        [func] => {
+            let (min, max) = camera.view_box();
+            (func, min.x as f64, max.x as f64, None)
+        }
+        [func, a, b, rest @ ..] if rest.len() <= 1 => {
+            (func, real_arg(env, a)?, real_arg(env, b)?, rest.first())
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!("graph takes 1, 3 or 4 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let (func, var) = plot_function(env, func, "x")?;
+
+    let strips = sample_strips(env, expr, "graph", (a, b), samples, camera, |x| {
+        match env.eval_with(&func, &[(&var, Value::from(x))]) {
+            Ok(val @ (Value::Number(_) | Value::Complex(_))) => Ok(Ok(val
+                .as_f64()
+                .filter(|y| y.is_finite())
+                .map(|y| Vec3::new(x as f32, y as f32, 0.0)))),
+            Ok(val) => Err(CalcError::new(
+                format!("Expected a real number, got {}", val.type_name()),
+                func.span,
+            )),
+            Err(err) => Ok(Err(err)),
+        }
+    })?;
+    Ok(SceneGeometry::Polyline {
+        strips,
+        closed: false,
+    })
+}
+
+/// Samples `point(t)` over `a..b` with [`sample_curve`]. The inner result is a failed
+/// evaluation which leaves a gap, the outer one aborts the plot.
+pub(super) fn sample_strips(
+    env: &Environment,
+    expr: &Expr,
+    cmd: &str,
+    (a, b): (f64, f64),
+    samples: Option<&Expr>,
+    camera: &GraphicCamera,
+    mut point: impl FnMut(f64) -> Result<Result<Option<Vec3>, CalcError>, CalcError>,
+) -> Result<Vec<Vec<Vec3>>, CalcError> {
+    if a == b || !a.is_finite() || !b.is_finite() {
+        return Err(CalcError::new(
+            format!("{cmd} needs an interval with a length"),
+            expr.span,
+        ));
+    }
+    let samples = match samples {
+        Some(arg) => count_arg(env, arg, 2, MAX_SAMPLES)?,
+        None => SAMPLES,
+    };
+    let mut first_error = None;
+    let strips = sample_curve(camera, (a, b), samples, |t| {
+        Ok(point(t)?.unwrap_or_else(|err| {
+            first_error.get_or_insert(err);
+            None
+        }))
+    })?;
+    if strips.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new(
+                format!("The {cmd} is undefined on the whole interval"),
+                expr.span,
+            )
+        }));
+    }
+    Ok(strips)
+}
@@ -0,0 +1,146 @@
+use std::f64::consts::{FRAC_2_PI, TAU};
+
+use glam::Vec2;
+use num_complex::Complex64;
+
+use crate::{
+    calc::{ast::Expr, error::CalcError, eval::Environment},
+    graphic::plot::{plot_function, real_arg},
+};
+
+/// Samples per side of the rectangle.
+const RESOLUTION: usize = 201;
+
+/// A complex function sampled over a rectangle of the plane.
+#[derive(Debug, Clone)]
+pub struct DomainColoringData {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub resolution: usize,
+    /// Row-major RGBA, hue is the argument and brightness the modulus
+    pub pixels: Vec<u8>,
+    /// Row-major `|f(z)|` when lifted into 3D
+    pub heights: Option<Vec<f32>>,
+}
+
+/// `domain(f)`, `domain(f, r)` or `domain(f, re0, re1, im0, im1)`.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    lifted: bool,
+) -> Result<DomainColoringData, CalcError> {
+    let (func, bounds) = match args {
+        [func] => (func, [-2.0, 2.0, -2.0, 2.0]),
+        [func, radius] => {
+            let radius = real_arg(env, radius)?.abs();
+            (func, [-radius, radius, -radius, radius])
+        }
+        [func, re0, re1, im0, im1] => (
+            func,
+            [
+                real_arg(env, re0)?,
+                real_arg(env, re1)?,
+                real_arg(env, im0)?,
+                real_arg(env, im1)?,
+            ],
+        ),
+        _ => {
+            return Err(CalcError::new(
+                format!("domain takes 1, 2 or 5 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let [re0, re1, im0, im1] = bounds;
+    if re0 == re1 || im0 == im1 || bounds.iter().any(|val| !val.is_finite()) {
+        return Err(CalcError::new(
+            "domain needs a rectangle with an area",
+            expr.span,
+        ));
+    }
+    let (func, var) = plot_function(env, func, "z")?;
+
+    let max_height = (re1 - re0).abs().max((im1 - im0).abs()) / 2.0;
+    let mut pixels = Vec::with_capacity(RESOLUTION * RESOLUTION * 4);
+    let mut heights = Vec::with_capacity(RESOLUTION * RESOLUTION);
+    let mut first_error = None;
+    let mut defined = false;
+    for row in 0..RESOLUTION {
+        let im = im0 + (im1 - im0) * row as f64 / (RESOLUTION - 1) as f64;
+        for col in 0..RESOLUTION {
+            let re = re0 + (re1 - re0) * col as f64 / (RESOLUTION - 1) as f64;
+            let z = Complex64::new(re, im);
+            // Failures are mostly poles like division by zero, they show as infinity
+            let w = match env.eval_complex(&func, &[(&var, z)]) {
+                Ok(w) => {
+                    defined = true;
+                    w
+                }
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                    Complex64::new(f64::INFINITY, 0.0)
+                }
+            };
+            pixels.extend_from_slice(&domain_color(w));
+            let modulus = w.norm();
+            heights.push(if modulus.is_finite() {
+                modulus.min(max_height) as f32
+            } else {
+                max_height as f32
+            });
+        }
+    }
+    if !defined && let Some(err) = first_error {
+        return Err(err);
+    }
+
+    Ok(DomainColoringData {
+        min: Vec2::new(re0 as f32, im0 as f32),
+        max: Vec2::new(re1 as f32, im1 as f32),
+        resolution: RESOLUTION,
+        pixels,
+        heights: lifted.then_some(heights),
+    })
+}
+
+/// Hue from the argument, lightness from the modulus with darker rings at powers of two.
+fn domain_color(w: Complex64) -> [u8; 4] {
+    let modulus = w.norm();
+    if modulus.is_nan() {
+        return [128, 128, 128, 255];
+    }
+    if modulus.is_infinite() {
+        return [255, 255, 255, 255];
+    }
+    let hue = (w.arg() / TAU).rem_euclid(1.0);
+    let rings = if modulus > 0.0 {
+        0.85 + 0.15 * modulus.log2().rem_euclid(1.0)
+    } else {
+        1.0
+    };
+    let lightness = FRAC_2_PI * modulus.atan() * rings;
+    let [r, g, b] = hsl_to_rgb(hue, 1.0, lightness);
+    [
+        (r * 255.0).round() as u8,
+        (g * 255.0).round() as u8,
+        (b * 255.0).round() as u8,
+        255,
+    ]
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue * 6.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
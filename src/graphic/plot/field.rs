@@ -0,0 +1,202 @@
+use glam::Vec3;
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+        value::Value,
+    },
+    graphic::plot::{count_arg, plot_function_of, real_arg, vector_point},
+};
+
+/// Arrows per axis unless given, a plane holds more of them than a box.
+const PLANE_DENSITY: usize = 20;
+const SPACE_DENSITY: usize = 8;
+const MAX_DENSITY: usize = 64;
+/// The longest arrow as a fraction of the lattice spacing.
+const ARROW_LENGTH: f32 = 0.9;
+/// Colours from weak to strong, interpolated by magnitude.
+const COLOR_MAP: [[f32; 3]; 5] = [
+    [0.27, 0.00, 0.33],
+    [0.23, 0.32, 0.55],
+    [0.13, 0.57, 0.55],
+    [0.37, 0.79, 0.38],
+    [0.99, 0.91, 0.14],
+];
+
+/// Arrows of a vector field sampled on a lattice.
+#[derive(Debug, Clone)]
+pub struct VectorFieldData {
+    pub origins: Vec<Vec3>,
+    pub vectors: Vec<Vec3>,
+    pub colors: Vec<[f32; 3]>,
+}
+
+/// `field(F)` over the view, `field(F, r)` or `field(F, x0, x1, y0, y1, z0, z1)`,
+/// optionally followed by the number of arrows per axis and `normalize` for arrows
+/// of equal length. A 2-vector `F(x, y)` is drawn in the plane `z = 0`.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    view: (Vec3, Vec3),
+) -> Result<VectorFieldData, CalcError> {
+    let (args, normalize) = match args {
+        [rest @ .., last]
+            if matches!(&last.kind, ExprKind::Ident(name)
+                if name == "normalize" && env.get_variable(name).is_none()) =>
+        {
+            (rest, true)
+        }
+        _ => (args, false),
+    };
+    let (func, min, max, density) = match args {
+        [func] => (func, view.0, view.1, None),
+        [func, radius, rest @ ..] if rest.len() <= 1 => {
+            let radius = real_arg(env, radius)?.abs() as f32;
+            (
+                func,
+                Vec3::splat(-radius),
+                Vec3::splat(radius),
+                rest.first(),
+            )
+        }
+        [func, rest @ ..] if matches!(rest.len(), 6 | 7) => {
+            let bounds = rest[..6]
+                .iter()
+                .map(|arg| real_arg(env, arg).map(|val| val as f32))
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                func,
+                Vec3::new(bounds[0], bounds[2], bounds[4]),
+                Vec3::new(bounds[1], bounds[3], bounds[5]),
+                rest.get(6),
+            )
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!(
+                    "field takes 1, 2, 3, 7 or 8 arguments besides normalize, got {}",
+                    args.len()
+                ),
+                expr.span,
+            ));
+        }
+    };
+    let size = max - min;
+    if size.cmpeq(Vec3::ZERO).any() || !size.is_finite() {
+        return Err(CalcError::new("field needs a box with a volume", expr.span));
+    }
+    // A user function of two variables is a plane field
+    let vars: &[&str] = match &func.kind {
+        ExprKind::Ident(name) if env.get_function(name).is_some_and(|f| f.params.len() == 2) => {
+            &["x", "y"]
+        }
+        _ => &["x", "y", "z"],
+    };
+    let func = plot_function_of(env, func, vars)?;
+
+    let mut first_error = None;
+    let mut sample = |point: Vec3| match env.eval_with(
+        &func,
+        &[
+            ("x", Value::from(point.x as f64)),
+            ("y", Value::from(point.y as f64)),
+            ("z", Value::from(point.z as f64)),
+        ],
+    ) {
+        Ok(val) => match val {
+            Value::Vector(ref items) if matches!(items.len(), 2 | 3) => {
+                Ok(Some((items.len(), vector_point(&val, &[2, 3]).flatten())))
+            }
+            _ => Err(CalcError::new(
+                format!("Expected a 2- or 3-vector, got {}", val.type_name()),
+                func.span,
+            )),
+        },
+        Err(err) => {
+            first_error.get_or_insert(err);
+            Ok(None)
+        }
+    };
+
+    // The number of components decides between a plane and a box of arrows, probe
+    // off-centre points too as fields like r / |r|^3 are undefined at the centre
+    let mut dims = None;
+    for offset in [0.0, 0.13, -0.29, 0.37, -0.41] {
+        if let Some((len, _)) = sample(min + size * (0.5 + offset))? {
+            dims = Some(len);
+            break;
+        }
+    }
+    let Some(dims) = dims else {
+        return Err(first_error
+            .unwrap_or_else(|| CalcError::new("The field is undefined in the box", expr.span)));
+    };
+    let density = match density {
+        Some(arg) => count_arg(env, arg, 2, MAX_DENSITY)?,
+        None if dims == 2 => PLANE_DENSITY,
+        None => SPACE_DENSITY,
+    };
+    let layers = if dims == 2 { 1 } else { density };
+
+    let step = size / (density - 1) as f32;
+    let spacing = if dims == 2 {
+        step.x.abs().min(step.y.abs())
+    } else {
+        step.abs().min_element()
+    };
+    let mut origins = Vec::new();
+    let mut vectors = Vec::new();
+    for k in 0..layers {
+        for j in 0..density {
+            for i in 0..density {
+                let mut origin = min + step * Vec3::new(i as f32, j as f32, k as f32);
+                if dims == 2 {
+                    origin.z = 0.0;
+                }
+                if let Some((_, Some(vector))) = sample(origin)?
+                    && vector != Vec3::ZERO
+                {
+                    origins.push(origin);
+                    vectors.push(vector);
+                }
+            }
+        }
+    }
+    if vectors.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The field vanishes everywhere in the box", expr.span)
+        }));
+    }
+
+    let strongest = vectors.iter().fold(0.0f32, |a, b| a.max(b.length()));
+    let colors = vectors
+        .iter()
+        .map(|vector| magnitude_color(vector.length() / strongest))
+        .collect();
+    for vector in &mut vectors {
+        *vector *= ARROW_LENGTH * spacing
+            / if normalize {
+                vector.length()
+            } else {
+                strongest
+            };
+    }
+    Ok(VectorFieldData {
+        origins,
+        vectors,
+        colors,
+    })
+}
+
+/// `t` from 0 for the weakest to 1 for the strongest arrow, or the lowest and
+/// highest value of a scatter plot.
+pub(super) fn magnitude_color(t: f32) -> [f32; 3] {
+    let position = t.clamp(0.0, 1.0) * (COLOR_MAP.len() - 1) as f32;
+    let index = (position as usize).min(COLOR_MAP.len() - 2);
+    let fraction = position - index as f32;
+    let [low, high] = [COLOR_MAP[index], COLOR_MAP[index + 1]];
+    [0, 1, 2].map(|i| low[i] + (high[i] - low[i]) * fraction)
+}
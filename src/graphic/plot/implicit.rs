@@ -0,0 +1,200 @@
+use glam::{DVec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{BinaryOp, Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+    },
+    graphic::plot::{
+        count_arg,
+        marching::{marching_cubes, marching_squares},
+        mesh::SurfaceMesh,
+        plot_function_of, real_arg,
+    },
+    graphic::scene::SceneGeometry,
+};
+
+/// Samples per axis of the box unless given.
+const RESOLUTION: usize = 40;
+const MAX_RESOLUTION: usize = 150;
+/// Coarse cells per side of a curve's rectangle and how often they are split near it.
+const CURVE_CELLS: usize = 64;
+const CURVE_DEPTH: u32 = 4;
+
+/// `implicit(F = G)` over the view, `implicit(F = G, r)` or
+/// `implicit(F = G, x0, x1, y0, y1[, h])` drawn in the plane `z = h`.
+pub fn build_curve(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    view: (Vec3, Vec3),
+) -> Result<SceneGeometry, CalcError> {
+    let (equation, min, max, height) = match args {
+        [equation] => (
+            equation,
+            view.0.truncate().as_dvec2(),
+            view.1.truncate().as_dvec2(),
+            0.0,
+        ),
+        [equation, radius] => {
+            let radius = real_arg(env, radius)?.abs();
+            (equation, DVec2::splat(-radius), DVec2::splat(radius), 0.0)
+        }
+        [equation, x0, x1, y0, y1, rest @ ..] if rest.len() <= 1 => (
+            equation,
+            DVec2::new(real_arg(env, x0)?, real_arg(env, y0)?),
+            DVec2::new(real_arg(env, x1)?, real_arg(env, y1)?),
+            match rest.first() {
+                Some(height) => real_arg(env, height)?,
+                None => 0.0,
+            },
+        ),
+        _ => {
+            return Err(CalcError::new(
+                format!("implicit takes 1, 2, 5 or 6 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let size = max - min;
+    if size.cmpeq(DVec2::ZERO).any() || !size.is_finite() || !height.is_finite() {
+        return Err(CalcError::new(
+            "implicit needs a rectangle with an area",
+            expr.span,
+        ));
+    }
+    let func = plot_function_of(env, &level_function(equation), &["x", "y"])?;
+
+    let mut first_error = None;
+    let strips = marching_squares(
+        |point| match env.eval_real(&func, &[("x", point.x), ("y", point.y)]) {
+            Ok(val) if val.is_finite() => val,
+            Ok(_) => f64::NAN,
+            Err(err) => {
+                first_error.get_or_insert(err);
+                f64::NAN
+            }
+        },
+        min,
+        max,
+        CURVE_CELLS,
+        CURVE_DEPTH,
+    );
+    if strips.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The curve doesn't pass through the rectangle", expr.span)
+        }));
+    }
+    let strips = strips
+        .into_iter()
+        .map(|strip| {
+            strip
+                .into_iter()
+                .map(|point| point.as_vec2().extend(height as f32))
+                .collect()
+        })
+        .collect();
+    Ok(SceneGeometry::Polyline {
+        strips,
+        closed: false,
+    })
+}
+
+/// `implicit3d(F = G)` over the view, `implicit3d(F = G, r)` or
+/// `implicit3d(F = G, x0, x1, y0, y1, z0, z1[, n])`. A bare `F` means `F = 0`.
+pub fn build_surface(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+    view: (Vec3, Vec3),
+) -> Result<SurfaceMesh, CalcError> {
+    let (equation, min, max, resolution) = match args {
+        [equation] => (equation, view.0, view.1, RESOLUTION),
+        [equation, radius] => {
+            let radius = real_arg(env, radius)?.abs() as f32;
+            (
+                equation,
+                Vec3::splat(-radius),
+                Vec3::splat(radius),
+                RESOLUTION,
+            )
+        }
+        [equation, rest @ ..] if matches!(rest.len(), 6 | 7) => {
+            let bounds = rest[..6]
+                .iter()
+                .map(|arg| real_arg(env, arg).map(|val| val as f32))
+                .collect::<Result<Vec<_>, _>>()?;
+            let resolution = match rest.get(6) {
+                Some(arg) => count_arg(env, arg, 2, MAX_RESOLUTION)?,
+                None => RESOLUTION,
+            };
+            (
+                equation,
+                Vec3::new(bounds[0], bounds[2], bounds[4]),
+                Vec3::new(bounds[1], bounds[3], bounds[5]),
+                resolution,
+            )
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!(
+                    "implicit3d takes 1, 2, 7 or 8 arguments, got {}",
+                    args.len()
+                ),
+                expr.span,
+            ));
+        }
+    };
+    let size = max - min;
+    if size.cmpeq(Vec3::ZERO).any() || !size.is_finite() {
+        return Err(CalcError::new(
+            "implicit3d needs a box with a volume",
+            expr.span,
+        ));
+    }
+    let func = plot_function_of(env, &level_function(equation), &["x", "y", "z"])?;
+
+    let step = size / (resolution - 1) as f32;
+    let mut values = Vec::with_capacity(resolution.pow(3));
+    let mut first_error = None;
+    for k in 0..resolution {
+        for j in 0..resolution {
+            for i in 0..resolution {
+                let point = min + step * Vec3::new(i as f32, j as f32, k as f32);
+                let bindings = [
+                    ("x", point.x as f64),
+                    ("y", point.y as f64),
+                    ("z", point.z as f64),
+                ];
+                values.push(match env.eval_real(&func, &bindings) {
+                    Ok(val) if val.is_finite() => val,
+                    Ok(_) => f64::NAN,
+                    Err(err) => {
+                        first_error.get_or_insert(err);
+                        f64::NAN
+                    }
+                });
+            }
+        }
+    }
+
+    let mesh = marching_cubes(&values, [resolution; 3], min, step);
+    if mesh.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The surface doesn't pass through the box", expr.span)
+        }));
+    }
+    Ok(mesh)
+}
+
+/// `F = G` as `F - G`, whose zero level set is the solution.
+fn level_function(equation: &Expr) -> Expr {
+    match &equation.kind {
+        ExprKind::Equation(lhs, rhs) => Expr::new(
+            ExprKind::Binary(BinaryOp::Sub, lhs.clone(), rhs.clone()),
+            equation.span,
+        ),
+        _ => equation.clone(),
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+use glam::{DVec2, DVec3, Vec3};
+
+use crate::graphic::plot::mesh::{SurfaceMesh, vertex_normals};
+
+/// Corner offsets of a cube.
+const CORNERS: [[usize; 3]; 8] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [1, 1, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [1, 0, 1],
+    [1, 1, 1],
+    [0, 1, 1],
+];
+/// Cube edges as pairs of corners.
+const EDGES: [[usize; 2]; 12] = [
+    [0, 1],
+    [1, 2],
+    [3, 2],
+    [0, 3],
+    [4, 5],
+    [5, 6],
+    [7, 6],
+    [4, 7],
+    [0, 4],
+    [1, 5],
+    [2, 6],
+    [3, 7],
+];
+/// Cube faces as corners in cyclic order and the edges between them, edge `i`
+/// joins corner `i` and `i + 1`.
+const FACES: [([usize; 4], [usize; 4]); 6] = [
+    ([0, 1, 2, 3], [0, 1, 2, 3]),
+    ([4, 5, 6, 7], [4, 5, 6, 7]),
+    ([0, 1, 5, 4], [0, 9, 4, 8]),
+    ([3, 2, 6, 7], [2, 10, 6, 11]),
+    ([0, 3, 7, 4], [3, 11, 7, 8]),
+    ([1, 2, 6, 5], [1, 10, 5, 9]),
+];
+
+/// Polygonises the zero level set of a sampled function. `values` holds `dims`
+/// samples per axis with x varying fastest, NaN where the function is undefined,
+/// and cubes touching an undefined sample are left out.
+///
+/// Rather than the usual 256 case table each cube joins the crossings on its faces
+/// into loops, a face with two opposite negative corners keeps them apart so that
+/// neighbouring cubes agree. Normals follow the gradient towards positive values.
+pub fn marching_cubes(values: &[f64], dims: [usize; 3], min: Vec3, step: Vec3) -> SurfaceMesh {
+    let [nx, ny, nz] = dims;
+    let index = |[i, j, k]: [usize; 3]| (k * ny + j) * nx + i;
+    let gradient = |point: [usize; 3]| {
+        let mut gradient = DVec3::ZERO;
+        for axis in 0..3 {
+            let (mut lo, mut hi) = (point, point);
+            lo[axis] = lo[axis].saturating_sub(1);
+            hi[axis] = (hi[axis] + 1).min(dims[axis] - 1);
+            // One-sided next to undefined samples
+            if values[index(lo)].is_nan() {
+                lo = point;
+            }
+            if values[index(hi)].is_nan() {
+                hi = point;
+            }
+            gradient[axis] = (values[index(hi)] - values[index(lo)])
+                / ((hi[axis] - lo[axis]) as f64 * step[axis] as f64);
+        }
+        gradient
+    };
+
+    let mut mesh = SurfaceMesh::default();
+    let mut gradients: Vec<DVec3> = Vec::new();
+    // Vertices are shared between cubes by grid point and axis of their edge
+    let mut vertices: HashMap<(usize, usize), u32> = HashMap::new();
+    for k in 0..nz.saturating_sub(1) {
+        for j in 0..ny.saturating_sub(1) {
+            for i in 0..nx.saturating_sub(1) {
+                let corners = CORNERS.map(|[di, dj, dk]| [i + di, j + dj, k + dk]);
+                let samples = corners.map(|corner| values[index(corner)]);
+                if samples.iter().any(|val| val.is_nan()) {
+                    continue;
+                }
+                let inside = samples.map(|val| val < 0.0);
+                if inside.iter().all(|&val| val) || inside.iter().all(|&val| !val) {
+                    continue;
+                }
+
+                let mut vertex = |edge: usize| {
+                    let [a, b] = EDGES[edge];
+                    let axis = (0..3).find(|&axis| corners[a][axis] != corners[b][axis]);
+                    let key = (index(corners[a]), axis.unwrap_or(0));
+                    *vertices.entry(key).or_insert_with(|| {
+                        let t = samples[a] / (samples[a] - samples[b]);
+                        let position = |[i, j, k]: [usize; 3]| {
+                            min + step * Vec3::new(i as f32, j as f32, k as f32)
+                        };
+                        let (pa, pb) = (position(corners[a]), position(corners[b]));
+                        mesh.positions.push(pa.lerp(pb, t as f32));
+                        gradients.push(gradient(corners[a]).lerp(gradient(corners[b]), t));
+                        mesh.positions.len() as u32 - 1
+                    })
+                };
+
+                let loops: Vec<Vec<u32>> = face_loops(&inside)
+                    .into_iter()
+                    .map(|edges| edges.into_iter().map(&mut vertex).collect())
+                    .collect();
+                for ids in loops {
+                    for pair in 1..ids.len() - 1 {
+                        mesh.indices
+                            .extend_from_slice(&[ids[0], ids[pair], ids[pair + 1]]);
+                    }
+                }
+            }
+        }
+    }
+
+    // Wind every triangle so that it faces along the gradient
+    for triangle in mesh.indices.chunks_exact_mut(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+        let face = (mesh.positions[b] - mesh.positions[a])
+            .cross(mesh.positions[c] - mesh.positions[a])
+            .as_dvec3();
+        if face.dot(gradients[a] + gradients[b] + gradients[c]) < 0.0 {
+            triangle.swap(1, 2);
+        }
+    }
+    let faces = vertex_normals(&mesh.positions, &mesh.indices);
+    mesh.normals = gradients
+        .iter()
+        .zip(faces)
+        .map(|(gradient, face)| gradient.as_vec3().try_normalize().unwrap_or(face))
+        .collect();
+    mesh
+}
+
+/// The edges crossed by the surface in a cube, as closed loops.
+fn face_loops(inside: &[bool; 8]) -> Vec<Vec<usize>> {
+    let mut links: [Vec<usize>; 12] = Default::default();
+    for (corners, edges) in FACES {
+        let inside = corners.map(|corner| inside[corner]);
+        let crossed: Vec<usize> = (0..4)
+            .filter(|&i| inside[i] != inside[(i + 1) % 4])
+            .collect();
+        let pairs = match crossed.as_slice() {
+            [a, b] => vec![(*a, *b)],
+            // Cut off each negative corner, edge `i - 1` and `i` meet at corner `i`
+            [_, _, _, _] if inside[0] => vec![(3, 0), (1, 2)],
+            [_, _, _, _] => vec![(0, 1), (2, 3)],
+            _ => Vec::new(),
+        };
+        for (a, b) in pairs {
+            links[edges[a]].push(edges[b]);
+            links[edges[b]].push(edges[a]);
+        }
+    }
+
+    let mut visited = [false; 12];
+    let mut loops = Vec::new();
+    for start in 0..12 {
+        if visited[start] || links[start].is_empty() {
+            continue;
+        }
+        let mut edges = vec![start];
+        visited[start] = true;
+        let mut current = start;
+        while let Some(&next) = links[current].iter().find(|&&next| !visited[next]) {
+            visited[next] = true;
+            edges.push(next);
+            current = next;
+        }
+        if edges.len() >= 3 {
+            loops.push(edges);
+        }
+    }
+    loops
+}
+
+/// Halvings of a crossed cell edge, the crossings of neighbouring cells of any size meet.
+const BISECTIONS: usize = 16;
+
+/// Traces the zero level set of `f` over a rectangle into polylines. Cells the
+/// curve crosses are split up to `depth` times where it bends sharply, has a saddle
+/// or runs into undefined values, the coarse `cells` x `cells` grid stays cheap away
+/// from the curve. Sign changes across a pole like that of `tan` are left out.
+pub fn marching_squares(
+    mut f: impl FnMut(DVec2) -> f64,
+    min: DVec2,
+    max: DVec2,
+    cells: usize,
+    depth: u32,
+) -> Vec<Vec<DVec2>> {
+    let step = (max - min) / cells as f64;
+    let point = |i: usize, j: usize| min + step * DVec2::new(i as f64, j as f64);
+    let values: Vec<f64> = (0..=cells)
+        .flat_map(|j| (0..=cells).map(move |i| (i, j)))
+        .map(|(i, j)| f(point(i, j)))
+        .collect();
+
+    let mut segments = Vec::new();
+    for j in 0..cells {
+        for i in 0..cells {
+            let corner = j * (cells + 1) + i;
+            let above = corner + cells + 1;
+            let corners = [
+                values[corner],
+                values[corner + 1],
+                values[above + 1],
+                values[above],
+            ];
+            trace_cell(
+                &mut f,
+                point(i, j),
+                point(i + 1, j + 1),
+                corners,
+                depth,
+                &mut segments,
+            );
+        }
+    }
+    join_segments(segments, step.abs().min_element() * 1e-6)
+}
+
+/// Adds the segments of the curve within a cell, corners are counter-clockwise
+/// from `lo`.
+fn trace_cell(
+    f: &mut impl FnMut(DVec2) -> f64,
+    lo: DVec2,
+    hi: DVec2,
+    values: [f64; 4],
+    depth: u32,
+    segments: &mut Vec<[DVec2; 2]>,
+) {
+    let undefined = values.iter().any(|val| val.is_nan());
+    let positive = values.map(|val| val >= 0.0);
+    let crossed: Vec<usize> = (0..4)
+        .filter(|&i| positive[i] != positive[(i + 1) % 4])
+        .collect();
+    if crossed.is_empty() || (undefined && depth == 0) {
+        return;
+    }
+
+    let mid = (lo + hi) / 2.0;
+    let center = f(mid);
+    let defined = values.iter().filter(|val| !val.is_nan());
+    let spread = defined.clone().fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+        - defined.clone().fold(f64::INFINITY, |a, &b| a.min(b));
+    let average = defined.clone().sum::<f64>() / defined.count() as f64;
+    let bends = center.is_nan() || (center - average).abs() > 0.25 * spread;
+    if depth > 0 && (undefined || crossed.len() == 4 || bends) {
+        let [bottom, right, top, left] = [
+            DVec2::new(mid.x, lo.y),
+            DVec2::new(hi.x, mid.y),
+            DVec2::new(mid.x, hi.y),
+            DVec2::new(lo.x, mid.y),
+        ]
+        .map(&mut *f);
+        let quadrants = [
+            (lo, mid, [values[0], bottom, center, left]),
+            (
+                DVec2::new(mid.x, lo.y),
+                DVec2::new(hi.x, mid.y),
+                [bottom, values[1], right, center],
+            ),
+            (mid, hi, [center, right, values[2], top]),
+            (
+                DVec2::new(lo.x, mid.y),
+                DVec2::new(mid.x, hi.y),
+                [left, center, top, values[3]],
+            ),
+        ];
+        for (lo, hi, values) in quadrants {
+            trace_cell(f, lo, hi, values, depth - 1, segments);
+        }
+        return;
+    }
+
+    let corners = [lo, DVec2::new(hi.x, lo.y), hi, DVec2::new(lo.x, hi.y)];
+    let pairs = match crossed.as_slice() {
+        [a, b] => vec![(*a, *b)],
+        // A saddle, the corners with the sign of the centre are joined through it
+        _ if positive[0] != (center >= 0.0) => vec![(3, 0), (1, 2)],
+        _ => vec![(0, 1), (2, 3)],
+    };
+    let mut crossing = |edge: usize| {
+        let next = (edge + 1) % 4;
+        edge_crossing(
+            f,
+            (corners[edge], values[edge]),
+            (corners[next], values[next]),
+        )
+    };
+    for (a, b) in pairs {
+        if let (Some(start), Some(end)) = (crossing(a), crossing(b)) {
+            segments.push([start, end]);
+        }
+    }
+}
+
+/// Bisects an edge whose ends have opposite signs down to the crossing. `None` for
+/// a pole, where the function grows rather than shrinks towards the sign change,
+/// or when it is undefined in between.
+fn edge_crossing(
+    f: &mut impl FnMut(DVec2) -> f64,
+    (mut a, mut fa): (DVec2, f64),
+    (mut b, mut fb): (DVec2, f64),
+) -> Option<DVec2> {
+    let bound = fa.abs().min(fb.abs());
+    for _ in 0..BISECTIONS {
+        let mid = (a + b) / 2.0;
+        let fm = f(mid);
+        if fm.is_nan() {
+            return None;
+        }
+        if (fm >= 0.0) == (fa >= 0.0) {
+            (a, fa) = (mid, fm);
+        } else {
+            (b, fb) = (mid, fm);
+        }
+    }
+    let crossing = a.lerp(b, fa / (fa - fb));
+    (f(crossing).abs() <= bound).then_some(crossing)
+}
+
+/// Chains segments sharing end points into polylines, closed curves end where they start.
+fn join_segments(segments: Vec<[DVec2; 2]>, tolerance: f64) -> Vec<Vec<DVec2>> {
+    let key = |point: DVec2| (point / tolerance).round().as_i64vec2().to_array();
+    let mut ends: HashMap<[i64; 2], Vec<usize>> = HashMap::new();
+    for (index, segment) in segments.iter().enumerate() {
+        for point in segment {
+            ends.entry(key(*point)).or_default().push(index);
+        }
+    }
+
+    let mut used = vec![false; segments.len()];
+    let next_segment = |point: DVec2, used: &mut Vec<bool>| {
+        let index = *ends.get(&key(point))?.iter().find(|&&index| !used[index])?;
+        used[index] = true;
+        let [a, b] = segments[index];
+        Some(if key(a) == key(point) { b } else { a })
+    };
+    let mut strips = Vec::new();
+    for index in 0..segments.len() {
+        if used[index] {
+            continue;
+        }
+        used[index] = true;
+        let [a, b] = segments[index];
+        let mut strip = vec![b];
+        while let Some(point) = next_segment(*strip.last().unwrap(), &mut used) {
+            strip.push(point);
+        }
+        let mut start = vec![a];
+        while let Some(point) = next_segment(*start.last().unwrap(), &mut used) {
+            start.push(point);
+        }
+        start.reverse();
+        start.extend(strip);
+        strips.push(start);
+    }
+    strips
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+/// Triangles with a normal per vertex, built from a grid of samples.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u32>,
+}
+
+impl SurfaceMesh {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Triangulates a row-major `cols` x `rows` grid of samples, `None` where the
+/// function is undefined. A cell missing one corner keeps the triangle of the
+/// other three, cells missing more are left out. Edges of the grid that meet
+/// like the seam of a torus or the poles of a sphere are shaded as one.
+pub fn grid_mesh(cols: usize, rows: usize, samples: &[Option<Vec3>]) -> SurfaceMesh {
+    let mut mesh = SurfaceMesh::default();
+    let vertex: Vec<Option<u32>> = samples
+        .iter()
+        .map(|sample| {
+            let point = (*sample)?;
+            mesh.positions.push(point);
+            Some(mesh.positions.len() as u32 - 1)
+        })
+        .collect();
+
+    for row in 0..rows.saturating_sub(1) {
+        for col in 0..cols.saturating_sub(1) {
+            let corner = row * cols + col;
+            let above = corner + cols;
+            // Counter-clockwise seen from +z for a grid with x along columns
+            let quad = [
+                vertex[corner],
+                vertex[corner + 1],
+                vertex[above + 1],
+                vertex[above],
+            ];
+            match quad {
+                [Some(a), Some(b), Some(c), Some(d)] => {
+                    mesh.indices.extend_from_slice(&[a, b, c, a, c, d]);
+                }
+                _ => {
+                    let defined: Vec<u32> = quad.into_iter().flatten().collect();
+                    if defined.len() == 3 {
+                        mesh.indices.extend_from_slice(&defined);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut normals = normal_sums(&mesh.positions, &mesh.indices);
+    let edges = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .filter(|&(row, col)| row == 0 || row + 1 == rows || col == 0 || col + 1 == cols)
+        .filter_map(|(row, col)| vertex[row * cols + col]);
+    smooth_seams(&mesh.positions, &mut normals, edges);
+    mesh.normals = normals.into_iter().map(unit_normal).collect();
+    mesh
+}
+
+/// Sums the normals of coincident vertices among `candidates`. Normals facing
+/// the opposite way are flipped for the sum, which keeps the seam of a
+/// one-sided surface like the Möbius strip smooth.
+fn smooth_seams(positions: &[Vec3], normals: &mut [Vec3], candidates: impl Iterator<Item = u32>) {
+    let Some(first) = positions.first() else {
+        return;
+    };
+    let (min, max) = positions
+        .iter()
+        .fold((*first, *first), |(min, max), point| {
+            (min.min(*point), max.max(*point))
+        });
+    let tolerance = ((max - min).length() * 1e-5).max(f32::MIN_POSITIVE);
+
+    let mut groups: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+    for index in candidates {
+        let key = (positions[index as usize] / tolerance)
+            .round()
+            .as_i64vec3()
+            .to_array();
+        groups.entry(key).or_default().push(index as usize);
+    }
+    for group in groups.values().filter(|group| group.len() > 1) {
+        // Vertices only touching degenerate triangles like the poles of a sphere have no normal
+        let reference = group
+            .iter()
+            .map(|&index| normals[index])
+            .find(|normal| *normal != Vec3::ZERO)
+            .unwrap_or(Vec3::ZERO);
+        let sign = |normal: Vec3| {
+            if normal.dot(reference) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            }
+        };
+        let sum: Vec3 = group
+            .iter()
+            .map(|&index| normals[index] * sign(normals[index]))
+            .sum();
+        for &index in group {
+            normals[index] = sum * sign(normals[index]);
+        }
+    }
+}
+
+/// Averages the face normals around each vertex, larger triangles weigh more.
+pub fn vertex_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    normal_sums(positions, indices)
+        .into_iter()
+        .map(unit_normal)
+        .collect()
+}
+
+fn normal_sums(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+        let face = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+        if face.is_finite() {
+            for index in [a, b, c] {
+                normals[index] += face;
+            }
+        }
+    }
+    normals
+}
+
+fn unit_normal(sum: Vec3) -> Vec3 {
+    sum.try_normalize().unwrap_or(Vec3::Z)
+}
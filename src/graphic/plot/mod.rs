@@ -0,0 +1,278 @@
+pub mod adaptive;
+pub mod contour;
+pub mod coordinates;
+pub mod curve;
+pub mod domain;
+pub mod field;
+pub mod implicit;
+pub mod marching;
+pub mod mesh;
+pub mod numeric;
+pub mod phase;
+pub mod region;
+pub mod revolution;
+pub mod riemann;
+pub mod scatter;
+pub mod surface;
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+        number::NumberMode,
+        value::Value,
+    },
+    graphic::{camera::GraphicCamera, plot::coordinates::CoordinateSystem, scene::SceneGeometry},
+};
+
+/// Whether `expr` is a plot command like `domain(z^2 - 1)`, a condition in `x`, `y`
+/// or `z` like `y < sin(x)`, an equation of a coordinate like `y = x^2` or
+/// `r = 1 + cos(theta)` or a differential equation like `dy/dx = x - y` rather than
+/// a value.
+pub fn is_plot(env: &Environment, expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Equation(lhs, rhs) => {
+            CoordinateSystem::of(env, lhs, rhs).is_some() || phase::is_flow(env, expr)
+        }
+        ExprKind::Vector(_) => phase::is_flow(env, expr),
+        ExprKind::Condition(_, _, _) => expr.identifiers().iter().any(|name| {
+            matches!(name.as_str(), "x" | "y" | "z") && env.get_variable(name).is_none()
+        }),
+        ExprKind::Call(name, _) => {
+            env.get_function(name).is_none()
+                && matches!(
+                    name.as_str(),
+                    "domain"
+                        | "domain3d"
+                        | "arrow"
+                        | "surface"
+                        | "parametric"
+                        | "curve"
+                        | "graph"
+                        | "contour"
+                        | "field"
+                        | "implicit"
+                        | "implicit3d"
+                        | "scatter"
+                        | "revolve"
+                        | "riemann"
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Whether the plot changes with the camera, curves are refined to its pixels and
+/// commands without bounds cover [`GraphicCamera::view_box`].
+pub fn depends_on_view(env: &Environment, expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Call(name, args) => match name.as_str() {
+            "curve" | "graph" => true,
+            "implicit" | "implicit3d" => args.len() == 1,
+            "field" => match args.as_slice() {
+                [_] => true,
+                [_, last] => matches!(&last.kind, ExprKind::Ident(name)
+                    if name == "normalize" && env.get_variable(name).is_none()),
+                _ => false,
+            },
+            _ => false,
+        },
+        ExprKind::Condition(_, _, _) | ExprKind::Vector(_) => true,
+        ExprKind::Equation(lhs, rhs) => {
+            phase::is_flow(env, expr)
+                || matches!(
+                    CoordinateSystem::of(env, lhs, rhs),
+                    Some(CoordinateSystem::Cartesian | CoordinateSystem::Polar)
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Samples a plot command into scene geometry as seen by `camera`, see
+/// [`depends_on_view`]. Differential equations trace their solutions through `seeds`.
+pub fn build_plot(
+    env: &Environment,
+    expr: &Expr,
+    camera: &GraphicCamera,
+    seeds: &[Vec2],
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let env = sampling_env(env);
+    let view = camera.view_box();
+    let ExprKind::Call(name, args) = &expr.kind else {
+        return match &expr.kind {
+            _ if phase::is_flow(&env, expr) => phase::build(&env, expr, view, seeds),
+            ExprKind::Condition(_, _, _) => region::build(&env, expr, view),
+            ExprKind::Equation(_, _) => coordinates::build(&env, expr, camera).map(|g| vec![g]),
+            _ => Err(CalcError::new("Not a plot command", expr.span)),
+        };
+    };
+    let geometry = match name.as_str() {
+        "contour" => return contour::build(&env, expr, args),
+        "revolve" => return revolution::build(&env, expr, args),
+        "riemann" => return riemann::build(&env, expr, args),
+        "domain" => domain::build(&env, expr, args, false).map(SceneGeometry::DomainColoring),
+        "domain3d" => domain::build(&env, expr, args, true).map(SceneGeometry::DomainColoring),
+        "arrow" => arrow(&env, expr, args),
+        "curve" => curve::build(&env, expr, args, camera),
+        "graph" => curve::build_graph(&env, expr, args, camera),
+        "surface" => surface::build(&env, expr, args).map(SceneGeometry::Surface),
+        "parametric" => surface::build_parametric(&env, expr, args).map(SceneGeometry::Surface),
+        "field" => field::build(&env, expr, args, view).map(SceneGeometry::VectorField),
+        "scatter" => scatter::build(&env, expr, args).map(SceneGeometry::Scatter),
+        "implicit" => implicit::build_curve(&env, expr, args, view),
+        "implicit3d" => implicit::build_surface(&env, expr, args, view).map(SceneGeometry::Surface),
+        _ => Err(CalcError::new(
+            format!("Unknown plot command '{}'", name),
+            expr.span,
+        )),
+    }?;
+    Ok(vec![geometry])
+}
+
+/// Plots are sampled many times, exact arithmetic would only slow them down.
+fn sampling_env(env: &Environment) -> Environment {
+    let mut env = env.clone();
+    env.number_mode = NumberMode::Float;
+    env
+}
+
+/// Turns a plot argument into an expression of one variable, a bare function
+/// name like `sin` or a user function `f` is applied to `default_var`.
+fn plot_function(
+    env: &Environment,
+    arg: &Expr,
+    default_var: &str,
+) -> Result<(Expr, String), CalcError> {
+    if let ExprKind::Ident(name) = &arg.kind
+        && env.get_variable(name).is_none()
+        && name != default_var
+    {
+        let call = Expr::new(
+            ExprKind::Call(name.clone(), vec![Expr::ident(default_var)]),
+            arg.span,
+        );
+        return Ok((call, default_var.to_owned()));
+    }
+    if arg.contains_ident(default_var) {
+        return Ok((arg.clone(), default_var.to_owned()));
+    }
+    let free: Vec<String> = arg
+        .identifiers()
+        .into_iter()
+        .filter(|name| env.get_variable(name).is_none())
+        .collect();
+    match free.as_slice() {
+        [] => Ok((arg.clone(), default_var.to_owned())),
+        [var] => Ok((arg.clone(), var.clone())),
+        _ => Err(CalcError::new(
+            format!(
+                "Expected a function of one variable, found {}",
+                free.join(", ")
+            ),
+            arg.span,
+        )),
+    }
+}
+
+/// Like [`plot_function`] for several variables, a user function `f` is applied to
+/// all of `vars` and `z = f(x, y)` plots its right hand side.
+fn plot_function_of(env: &Environment, arg: &Expr, vars: &[&str]) -> Result<Expr, CalcError> {
+    match &arg.kind {
+        ExprKind::Ident(name) if env.get_function(name).is_some() => {
+            let args = vars.iter().map(|var| Expr::ident(var)).collect();
+            return Ok(Expr::new(ExprKind::Call(name.clone(), args), arg.span));
+        }
+        ExprKind::Equation(lhs, rhs) if matches!(lhs.kind, ExprKind::Ident(_)) => {
+            return plot_function_of(env, rhs, vars);
+        }
+        _ => {}
+    }
+    let free: Vec<String> = arg
+        .identifiers()
+        .into_iter()
+        .filter(|name| env.get_variable(name).is_none() && !vars.contains(&name.as_str()))
+        .collect();
+    if free.is_empty() {
+        Ok(arg.clone())
+    } else {
+        let (last, rest) = vars.split_last().unwrap_or((&"", &[]));
+        let vars = match rest {
+            [] => last.to_string(),
+            _ => format!("{} and {}", rest.join(", "), last),
+        };
+        Err(CalcError::new(
+            format!("Expected a function of {}, found {}", vars, free.join(", ")),
+            arg.span,
+        ))
+    }
+}
+
+/// `arrow(v)` from the origin or `arrow(v, start)`.
+fn arrow(env: &Environment, expr: &Expr, args: &[Expr]) -> Result<SceneGeometry, CalcError> {
+    let point = |arg: &Expr| {
+        let val = env.eval(arg)?;
+        let [x, y, z] = val.as_vec3().ok_or_else(|| {
+            CalcError::new(
+                format!("Expected a real 3-vector, got {}", val.type_name()),
+                arg.span,
+            )
+        })?;
+        Ok(Vec3::new(x as f32, y as f32, z as f32))
+    };
+    match args {
+        [vector] => Ok(SceneGeometry::Arrow {
+            start: Vec3::ZERO,
+            end: point(vector)?,
+        }),
+        [vector, start] => {
+            let start = point(start)?;
+            Ok(SceneGeometry::Arrow {
+                start,
+                end: start + point(vector)?,
+            })
+        }
+        _ => Err(CalcError::new(
+            format!("arrow takes 1 or 2 arguments, got {}", args.len()),
+            expr.span,
+        )),
+    }
+}
+
+fn real_arg(env: &Environment, arg: &Expr) -> Result<f64, CalcError> {
+    env.eval_real(arg, &[])
+}
+
+/// A sample count or resolution argument, a whole number in `min..=max`.
+fn count_arg(env: &Environment, arg: &Expr, min: usize, max: usize) -> Result<usize, CalcError> {
+    let n = real_arg(env, arg)?;
+    if n.fract() != 0.0 || !(min as f64..=max as f64).contains(&n) {
+        return Err(CalcError::new(
+            format!("Expected a whole number from {min} to {max}"),
+            arg.span,
+        ));
+    }
+    Ok(n as usize)
+}
+
+/// The point of a real vector with one of the lengths in `dims`, `Some(None)` where
+/// a component is complex or infinite and `None` for any other value.
+fn vector_point(val: &Value, dims: &[usize]) -> Option<Option<Vec3>> {
+    let Value::Vector(items) = val else {
+        return None;
+    };
+    if !dims.contains(&items.len()) {
+        return None;
+    }
+    let mut point = Vec3::ZERO;
+    for (i, item) in items.iter().enumerate() {
+        match item.as_f64() {
+            Some(component) if component.is_finite() => point[i] = component as f32,
+            _ => return Some(None),
+        }
+    }
+    Some(Some(point))
+}
@@ -0,0 +1,103 @@
+use glam::Vec3;
+
+use crate::{
+    calc::{
+        eval::Environment,
+        numeric::{NumericMethod, NumericResult},
+        value::Value,
+    },
+    graphic::{plot::sampling_env, scene::SceneGeometry},
+};
+
+/// Samples along the shaded area of an integral.
+const AREA_SAMPLES: usize = 200;
+/// How far an infinite integration bound is drawn.
+const INFINITE_EXTENT: f64 = 10.0;
+
+/// Roots and extrema are marked, integrals shade the area under the curve in the
+/// xy-plane and 2D extrema also shade their search region. Trajectories are drawn
+/// as paths.
+pub fn build(env: &Environment, result: &NumericResult) -> Vec<SceneGeometry> {
+    let value = result.value.as_f64().unwrap_or(0.0);
+    match (result.method, result.bounds.as_slice()) {
+        (NumericMethod::Root, _) => vec![SceneGeometry::Markers(vec![point(&result.at)])],
+        (NumericMethod::Integral, [(a, b)]) => {
+            vec![area_under(&sampling_env(env), result, *a, *b)]
+        }
+        (NumericMethod::Minimum | NumericMethod::Maximum, [_]) => {
+            vec![SceneGeometry::Markers(vec![point(&[result.at[0], value])])]
+        }
+        (NumericMethod::Minimum | NumericMethod::Maximum, [(x0, x1), (y0, y1)]) => vec![
+            SceneGeometry::Area {
+                outline: vec![
+                    point(&[*x0, *y0]),
+                    point(&[*x1, *y0]),
+                    point(&[*x1, *y1]),
+                    point(&[*x0, *y1]),
+                ],
+                alpha: 0.15,
+            },
+            SceneGeometry::Markers(vec![point(&[result.at[0], result.at[1], value])]),
+        ],
+        (NumericMethod::Trajectory, _) => trajectory(result).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Missing coordinates are 0, so 1D and 2D points lie in the xy-plane.
+fn point(coords: &[f64]) -> Vec3 {
+    let coord = |i: usize| coords.get(i).copied().unwrap_or(0.0) as f32;
+    Vec3::new(coord(0), coord(1), coord(2))
+}
+
+/// A scalar solution is drawn against `t`, larger states in their phase space.
+fn trajectory(result: &NumericResult) -> Option<SceneGeometry> {
+    let Value::Matrix(samples) = &result.value else {
+        return None;
+    };
+    let points = samples
+        .to_rows()
+        .iter()
+        .map(|row| {
+            let reals: Vec<f64> = row.iter().filter_map(Value::as_f64).collect();
+            if reals.len() == 2 {
+                point(&reals)
+            } else {
+                point(&reals[1..])
+            }
+        })
+        .collect();
+    Some(SceneGeometry::Polyline {
+        strips: vec![points],
+        closed: false,
+    })
+}
+
+/// The curve over `a..b` followed by the axis back, which the polygon fill turns
+/// into a strip of quads.
+fn area_under(env: &Environment, result: &NumericResult, a: f64, b: f64) -> SceneGeometry {
+    let (a, b) = match (a.is_finite(), b.is_finite()) {
+        (true, true) => (a, b),
+        (true, false) => (a, a + INFINITE_EXTENT.copysign(b)),
+        (false, true) => (b + INFINITE_EXTENT.copysign(a), b),
+        (false, false) => (INFINITE_EXTENT.copysign(a), INFINITE_EXTENT.copysign(b)),
+    };
+    let var = result.vars[0].as_str();
+    let xs: Vec<f64> = (0..=AREA_SAMPLES)
+        .map(|i| a + (b - a) * i as f64 / AREA_SAMPLES as f64)
+        .collect();
+    let curve = xs.iter().map(|x| {
+        // Singular points like the end of 1/sqrt(x) are drawn on the axis
+        let y = env
+            .eval_real(&result.func, &[(var, *x)])
+            .ok()
+            .filter(|y| y.is_finite())
+            .unwrap_or(0.0);
+        Vec3::new(*x as f32, y as f32, 0.0)
+    });
+    let axis = xs.iter().rev().map(|x| Vec3::new(*x as f32, 0.0, 0.0));
+    SceneGeometry::Area {
+        outline: curve.chain(axis).collect(),
+        alpha: 0.35,
+    }
+}
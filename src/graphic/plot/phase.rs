@@ -0,0 +1,455 @@
+use glam::{DVec2, Vec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{BinaryOp, Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+        numeric::{find_root_in, find_root_system},
+        value::Value,
+    },
+    graphic::{
+        plot::field::{VectorFieldData, magnitude_color},
+        scene::SceneGeometry,
+    },
+};
+
+/// Glyphs along the shorter side of the view, each filling this fraction of the
+/// spacing between them.
+const FIELD_DENSITY: usize = 20;
+const GLYPH_LENGTH: f64 = 0.7;
+/// Steps of a solution curve across the shorter side of the view and the most it
+/// takes each way.
+const STEPS_PER_VIEW: f64 = 400.0;
+const MAX_STEPS: usize = 8000;
+/// Solution curves are followed this many view sizes beyond the view.
+const CURVE_MARGIN: f64 = 0.5;
+/// Cells along the shorter side searched for equilibria of a system, and samples
+/// of `y` searched for the constant solutions of `dy/dx = f(y)`.
+const EQUILIBRIUM_CELLS: usize = 16;
+const EQUILIBRIUM_SAMPLES: usize = 200;
+
+/// A differential equation in the plane.
+enum Flow {
+    /// `dy/dx = f(x, y)`
+    Slope(Expr),
+    /// `[x', y'] = F(x, y)`
+    System(Expr),
+}
+
+/// Whether `expr` is a differential equation in `x` and `y`, see [`build`].
+pub fn is_flow(env: &Environment, expr: &Expr) -> bool {
+    flow_of(env, expr).is_some()
+}
+
+fn flow_of(env: &Environment, expr: &Expr) -> Option<Flow> {
+    let flow = match &expr.kind {
+        ExprKind::Equation(lhs, rhs) => match &lhs.kind {
+            ExprKind::Binary(BinaryOp::Div, dy, dx) if is_ident(dy, "dy") && is_ident(dx, "dx") => {
+                Flow::Slope((**rhs).clone())
+            }
+            ExprKind::Ident(name) if name == "y'" => Flow::Slope((**rhs).clone()),
+            ExprKind::Vector(items) => match items.as_slice() {
+                [x, y] if is_ident(x, "x'") && is_ident(y, "y'") => Flow::System((**rhs).clone()),
+                _ => return None,
+            },
+            _ => return None,
+        },
+        ExprKind::Vector(items) => {
+            let [first, second] = items.as_slice() else {
+                return None;
+            };
+            let (ExprKind::Equation(x, f), ExprKind::Equation(y, g)) = (&first.kind, &second.kind)
+            else {
+                return None;
+            };
+            if !is_ident(x, "x'") || !is_ident(y, "y'") {
+                return None;
+            }
+            let func = ExprKind::Vector(vec![(**f).clone(), (**g).clone()]);
+            Flow::System(Expr::new(func, expr.span))
+        }
+        _ => return None,
+    };
+    let (Flow::Slope(func) | Flow::System(func)) = &flow;
+    func.identifiers()
+        .iter()
+        .all(|name| matches!(name.as_str(), "x" | "y") || env.get_variable(name).is_some())
+        .then_some(flow)
+}
+
+fn is_ident(expr: &Expr, name: &str) -> bool {
+    matches!(&expr.kind, ExprKind::Ident(ident) if ident == name)
+}
+
+/// The slope field of `dy/dx = f(x, y)` or `y' = f(x, y)` as segments, or the
+/// direction field of `[x' = f(x, y), y' = g(x, y)]` or `[x', y'] = F(x, y)` as arrows,
+/// across the view. Solution curves are traced both ways through the `seeds`.
+/// Equilibria of a system are marked with their type, as are the constant
+/// solutions of `dy/dx = f(y)`.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    view: (Vec3, Vec3),
+    seeds: &[Vec2],
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let Some(flow) = flow_of(env, expr) else {
+        return Err(CalcError::new(
+            "Expected a differential equation in x and y",
+            expr.span,
+        ));
+    };
+    let checked = |point: DVec2| -> Result<DVec2, CalcError> {
+        match &flow {
+            Flow::Slope(func) => {
+                let slope = env.eval_real(func, &[("x", point.x), ("y", point.y)])?;
+                Ok(DVec2::new(1.0, slope))
+            }
+            Flow::System(func) => {
+                let bindings = [("x", Value::from(point.x)), ("y", Value::from(point.y))];
+                match env.eval_with(func, &bindings)? {
+                    Value::Vector(items) if items.len() == 2 => {
+                        match (items[0].as_f64(), items[1].as_f64()) {
+                            (Some(x), Some(y)) => Ok(DVec2::new(x, y)),
+                            _ => Err(CalcError::new("The derivative is complex", func.span)),
+                        }
+                    }
+                    val => Err(CalcError::new(
+                        format!("Expected a 2-vector, got {}", val.type_name()),
+                        func.span,
+                    )),
+                }
+            }
+        }
+    };
+    let velocity = |point: DVec2| checked(point).ok().filter(|val| val.is_finite());
+
+    let (min, max) = (view.0.truncate().as_dvec2(), view.1.truncate().as_dvec2());
+    let size = max - min;
+    let spacing = size.min_element() / FIELD_DENSITY as f64;
+    let counts = (size / spacing).round().as_uvec2();
+    let mut glyphs = Vec::new();
+    for j in 0..counts.y {
+        for i in 0..counts.x {
+            let point = min + DVec2::new(i as f64 + 0.5, j as f64 + 0.5) * spacing;
+            if let Some(val) = velocity(point)
+                && val != DVec2::ZERO
+            {
+                glyphs.push((point, val));
+            }
+        }
+    }
+    if glyphs.is_empty() {
+        checked((min + max) / 2.0)?;
+        return Err(CalcError::new(
+            "The equation is undefined across the view",
+            expr.span,
+        ));
+    }
+
+    let to_vec3 = |point: DVec2| point.as_vec2().extend(0.0);
+    let length = spacing * GLYPH_LENGTH;
+    let mut geometry = vec![match &flow {
+        Flow::Slope(_) => SceneGeometry::Polyline {
+            strips: glyphs
+                .iter()
+                .map(|(point, val)| {
+                    let half = val.normalize() * length / 2.0;
+                    vec![to_vec3(point - half), to_vec3(point + half)]
+                })
+                .collect(),
+            closed: false,
+        },
+        // The grid holds hundreds of glyphs, drawn as one instanced vector field rather
+        // than a `DrawableArrow` each. Single arrows mark the flow at the seeds.
+        Flow::System(_) => {
+            let strongest = glyphs
+                .iter()
+                .fold(0.0f64, |a, (_, val)| a.max(val.length()));
+            SceneGeometry::VectorField(VectorFieldData {
+                origins: glyphs
+                    .iter()
+                    .map(|(point, val)| to_vec3(point - val.normalize() * length / 2.0))
+                    .collect(),
+                vectors: glyphs
+                    .iter()
+                    .map(|(_, val)| (val.normalize() * length).as_vec2().extend(0.0))
+                    .collect(),
+                colors: glyphs
+                    .iter()
+                    .map(|(_, val)| magnitude_color((val.length() / strongest) as f32))
+                    .collect(),
+            })
+        }
+    }];
+
+    let strips: Vec<Vec<Vec3>> = seeds
+        .iter()
+        .map(|seed| trajectory(&velocity, seed.as_dvec2(), min, max))
+        .filter(|path| path.len() > 1)
+        .map(|path| path.into_iter().map(to_vec3).collect())
+        .collect();
+    if !strips.is_empty() {
+        geometry.push(SceneGeometry::Polyline {
+            strips,
+            closed: false,
+        });
+    }
+
+    let mut labels = Vec::new();
+    match &flow {
+        Flow::Slope(func) if !func.contains_ident("x") => {
+            let x = (min.x + max.x) / 2.0;
+            let lines: Vec<Vec<Vec3>> = constant_solutions(&velocity, x, min.y, max.y)
+                .into_iter()
+                .map(|(y, kind)| {
+                    labels.push((to_vec3(DVec2::new(min.x + spacing, y)), kind.to_owned()));
+                    vec![to_vec3(DVec2::new(min.x, y)), to_vec3(DVec2::new(max.x, y))]
+                })
+                .collect();
+            if !lines.is_empty() {
+                geometry.push(SceneGeometry::Polyline {
+                    strips: lines,
+                    closed: false,
+                });
+            }
+        }
+        Flow::Slope(_) => {}
+        Flow::System(_) => {
+            let points: Vec<Vec3> = equilibria(&velocity, min, max)
+                .into_iter()
+                .map(|(point, kind)| {
+                    labels.push((to_vec3(point), kind));
+                    to_vec3(point)
+                })
+                .collect();
+            if !points.is_empty() {
+                geometry.push(SceneGeometry::Markers(points));
+            }
+            // The direction of the flow at each seed
+            for seed in seeds {
+                if let Some(dir) = velocity(seed.as_dvec2()).and_then(DVec2::try_normalize) {
+                    let start = seed.extend(0.0);
+                    geometry.push(SceneGeometry::Arrow {
+                        start,
+                        end: start + (dir * length).as_vec2().extend(0.0),
+                    });
+                }
+            }
+        }
+    }
+    if !labels.is_empty() {
+        geometry.push(SceneGeometry::Labels(labels));
+    }
+    Ok(geometry)
+}
+
+/// The solution through `seed` traced both ways along the direction of the flow,
+/// until it leaves the view, turns back at an equilibrium or closes up.
+fn trajectory(
+    velocity: &impl Fn(DVec2) -> Option<DVec2>,
+    seed: DVec2,
+    min: DVec2,
+    max: DVec2,
+) -> Vec<DVec2> {
+    let size = max - min;
+    let step = size.min_element() / STEPS_PER_VIEW;
+    let (lower, upper) = (min - size * CURVE_MARGIN, max + size * CURVE_MARGIN);
+    let mut halves = Vec::with_capacity(2);
+    for sign in [1.0, -1.0] {
+        let direction = |point| {
+            velocity(point)
+                .and_then(DVec2::try_normalize)
+                .map(|dir| dir * sign)
+        };
+        let mut path = vec![seed];
+        let mut point = seed;
+        let mut previous: Option<DVec2> = None;
+        for taken in 0..MAX_STEPS {
+            let Some(next) = runge_kutta(&direction, point, step) else {
+                break;
+            };
+            // Turning back within a step means the curve ran into an equilibrium
+            if previous.is_some_and(|previous| previous.dot(next - point) < 0.0) {
+                break;
+            }
+            previous = Some(next - point);
+            point = next;
+            path.push(point);
+            if point.cmplt(lower).any() || point.cmpgt(upper).any() {
+                break;
+            }
+            if taken > 8 && point.distance(seed) < step {
+                path.push(seed);
+                return path;
+            }
+        }
+        halves.push(path);
+    }
+    let forward = halves.remove(0);
+    let mut path = halves.remove(0);
+    path.reverse();
+    path.extend(forward.into_iter().skip(1));
+    path
+}
+
+fn runge_kutta(
+    direction: &impl Fn(DVec2) -> Option<DVec2>,
+    point: DVec2,
+    step: f64,
+) -> Option<DVec2> {
+    let k1 = direction(point)?;
+    let k2 = direction(point + k1 * step / 2.0)?;
+    let k3 = direction(point + k2 * step / 2.0)?;
+    let k4 = direction(point + k3 * step)?;
+    Some(point + (k1 + 2.0 * k2 + 2.0 * k3 + k4) * step / 6.0)
+}
+
+/// Zeros of the field found by Newton's method from the cells where both
+/// components change sign, with the type of their linearisation.
+fn equilibria(
+    velocity: &impl Fn(DVec2) -> Option<DVec2>,
+    min: DVec2,
+    max: DVec2,
+) -> Vec<(DVec2, String)> {
+    let size = max - min;
+    let cell = size.min_element() / EQUILIBRIUM_CELLS as f64;
+    let counts = (size / cell).ceil().as_uvec2();
+    let corners: Vec<Vec<Option<DVec2>>> = (0..=counts.y)
+        .map(|j| {
+            (0..=counts.x)
+                .map(|i| velocity(min + DVec2::new(i as f64, j as f64) * cell))
+                .collect()
+        })
+        .collect();
+    let residual = |point: &[f64]| {
+        velocity(DVec2::new(point[0], point[1]))
+            .map(|val| vec![val.x, val.y])
+            .ok_or_else(|| "The field is undefined".to_owned())
+    };
+
+    let mut found: Vec<(DVec2, String)> = Vec::new();
+    for j in 0..counts.y as usize {
+        for i in 0..counts.x as usize {
+            let values = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)]
+                .map(|(i, j)| corners[j][i])
+                .into_iter()
+                .collect::<Option<Vec<_>>>();
+            let Some(values) = values else {
+                continue;
+            };
+            let (low, high) = values.iter().fold(
+                (DVec2::INFINITY, DVec2::NEG_INFINITY),
+                |(low, high), val| (low.min(*val), high.max(*val)),
+            );
+            if low.cmpgt(DVec2::ZERO).any() || high.cmplt(DVec2::ZERO).any() {
+                continue;
+            }
+            let corner = min + DVec2::new(i as f64, j as f64) * cell;
+            let guess = corner + cell / 2.0;
+            let Ok((root, _)) = find_root_system(&residual, &[guess.x, guess.y]) else {
+                continue;
+            };
+            let point = DVec2::new(root[0], root[1]);
+            let near = point.cmpge(corner - cell).all() && point.cmple(corner + cell * 2.0).all();
+            if near
+                && found
+                    .iter()
+                    .all(|(other, _)| other.distance(point) > cell * 1e-3)
+            {
+                found.push((point, classify(velocity, point, cell)));
+            }
+        }
+    }
+    found
+}
+
+/// The type of an equilibrium from the trace and determinant of the Jacobian.
+fn classify(velocity: &impl Fn(DVec2) -> Option<DVec2>, point: DVec2, scale: f64) -> String {
+    let h = scale * 1e-5;
+    let partial = |offset: DVec2| match (velocity(point + offset), velocity(point - offset)) {
+        (Some(ahead), Some(behind)) => (ahead - behind) / (2.0 * h),
+        _ => DVec2::NAN,
+    };
+    let (dx, dy) = (partial(DVec2::new(h, 0.0)), partial(DVec2::new(0.0, h)));
+    let trace = dx.x + dy.y;
+    let det = dx.x * dy.y - dy.x * dx.y;
+    let tolerance = 1e-6 * (dx.abs() + dy.abs()).element_sum().powi(2);
+    if !trace.is_finite() || !det.is_finite() {
+        "equilibrium".to_owned()
+    } else if det < -tolerance {
+        "saddle".to_owned()
+    } else if det <= tolerance {
+        "degenerate".to_owned()
+    } else if trace.abs() <= tolerance.sqrt() {
+        "centre".to_owned()
+    } else {
+        let stability = if trace < 0.0 { "stable" } else { "unstable" };
+        let shape = if trace * trace >= 4.0 * det {
+            "node"
+        } else {
+            "spiral"
+        };
+        format!("{} {}", stability, shape)
+    }
+}
+
+/// Heights `y` where `f(y)` vanishes, from sign changes at `x`, and whether the
+/// solutions nearby approach them.
+fn constant_solutions(
+    velocity: &impl Fn(DVec2) -> Option<DVec2>,
+    x: f64,
+    y0: f64,
+    y1: f64,
+) -> Vec<(f64, &'static str)> {
+    let slope = |y: f64| velocity(DVec2::new(x, y)).map_or(f64::NAN, |val| val.y);
+    let step = (y1 - y0) / EQUILIBRIUM_SAMPLES as f64;
+    let samples: Vec<(f64, f64)> = (0..=EQUILIBRIUM_SAMPLES)
+        .map(|k| {
+            let y = y0 + step * k as f64;
+            (y, slope(y))
+        })
+        .collect();
+    let largest = samples
+        .iter()
+        .map(|(_, val)| val.abs())
+        .filter(|val| val.is_finite())
+        .fold(0.0, f64::max);
+    let root_of = |y: f64| {
+        let val = slope(y);
+        if val.is_finite() {
+            Ok(val)
+        } else {
+            Err("The slope is undefined".to_owned())
+        }
+    };
+
+    let mut found: Vec<(f64, &'static str)> = Vec::new();
+    for pair in samples.windows(2) {
+        let [(a, fa), (b, fb)] = [pair[0], pair[1]];
+        if fa.is_nan() || fb.is_nan() || fa * fb > 0.0 || (fa == 0.0 && fb == 0.0) {
+            continue;
+        }
+        let Ok((y, _)) = find_root_in(&root_of, a, b) else {
+            continue;
+        };
+        // Poles change sign too
+        if slope(y).abs() > largest * 1e-6
+            || found
+                .iter()
+                .any(|(other, _)| (other - y).abs() < step / 2.0)
+        {
+            continue;
+        }
+        let (below, above) = (slope(y - step / 2.0), slope(y + step / 2.0));
+        let kind = if below > 0.0 && above < 0.0 {
+            "stable"
+        } else if below < 0.0 && above > 0.0 {
+            "unstable"
+        } else {
+            "semi-stable"
+        };
+        found.push((y, kind));
+    }
+    found
+}
@@ -0,0 +1,234 @@
+use glam::{DVec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{ConditionOp, Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+    },
+    graphic::{
+        plot::{
+            marching::{marching_cubes, marching_squares},
+            mesh::SurfaceMesh,
+            plot_function_of,
+        },
+        scene::SceneGeometry,
+    },
+};
+
+/// Cells per side of a plane region and samples per axis of a solid.
+const PLANE_CELLS: usize = 160;
+const SPACE_RESOLUTION: usize = 40;
+/// Coarse cells per side when tracing the boundary of a plane region and how often
+/// they are split near it.
+const BOUNDARY_CELLS: usize = 64;
+const BOUNDARY_DEPTH: u32 = 4;
+const PLANE_ALPHA: f32 = 0.3;
+const SPACE_ALPHA: f32 = 0.35;
+/// Margin of undefined points, far outside so the boundary hugs the defined ones.
+const UNDEFINED: f64 = -1e12;
+
+/// Shades where a condition like `y < sin(x)` or `x^2 + y^2 <= 4 and y > 0` holds
+/// across the view, in the plane `z = 0` or as a solid when it involves `z`.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    view: (Vec3, Vec3),
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    check_condition(expr)?;
+    let solid = expr
+        .identifiers()
+        .iter()
+        .any(|name| name == "z" && env.get_variable(name).is_none());
+    let vars: &[&str] = if solid { &["x", "y", "z"] } else { &["x", "y"] };
+    let condition = plot_function_of(env, expr, vars)?;
+
+    let mut first_error = None;
+    let mut sample = |point: Vec3| {
+        let bindings = [
+            ("x", point.x as f64),
+            ("y", point.y as f64),
+            ("z", point.z as f64),
+        ];
+        margin(env, &condition, &bindings, &mut first_error)
+    };
+    let geometry = if solid {
+        let mesh = solid_mesh(view, &mut sample);
+        (!mesh.is_empty()).then(|| {
+            vec![SceneGeometry::Region {
+                mesh,
+                alpha: SPACE_ALPHA,
+            }]
+        })
+    } else {
+        let (min, max) = (view.0.truncate().as_dvec2(), view.1.truncate().as_dvec2());
+        let mesh = plane_mesh(min, max, &mut sample);
+        (!mesh.is_empty()).then(|| {
+            let strips = marching_squares(
+                |point| sample(point.as_vec2().extend(0.0)),
+                min,
+                max,
+                BOUNDARY_CELLS,
+                BOUNDARY_DEPTH,
+            )
+            .into_iter()
+            .map(|strip| {
+                strip
+                    .iter()
+                    .map(|point| point.as_vec2().extend(0.0))
+                    .collect()
+            })
+            .collect();
+            vec![
+                SceneGeometry::Region {
+                    mesh,
+                    alpha: PLANE_ALPHA,
+                },
+                SceneGeometry::Polyline {
+                    strips,
+                    closed: false,
+                },
+            ]
+        })
+    };
+    geometry.ok_or_else(|| {
+        first_error
+            .unwrap_or_else(|| CalcError::new("The condition holds nowhere in view", expr.span))
+    })
+}
+
+/// Only comparisons joined by `and` and `or` have a margin.
+fn check_condition(expr: &Expr) -> Result<(), CalcError> {
+    match &expr.kind {
+        ExprKind::Condition(op, lhs, rhs) if !op.is_comparison() => {
+            check_condition(lhs)?;
+            check_condition(rhs)
+        }
+        ExprKind::Condition(_, _, _) => Ok(()),
+        _ => Err(CalcError::new(
+            format!("Expected a comparison, found {}", expr),
+            expr.span,
+        )),
+    }
+}
+
+/// How far the condition holds, positive inside and negative outside with the
+/// boundary where it vanishes. `and` is the smaller margin, `or` the larger.
+fn margin(
+    env: &Environment,
+    condition: &Expr,
+    bindings: &[(&str, f64)],
+    first_error: &mut Option<CalcError>,
+) -> f64 {
+    let ExprKind::Condition(op, lhs, rhs) = &condition.kind else {
+        return UNDEFINED;
+    };
+    if !op.is_comparison() {
+        let a = margin(env, lhs, bindings, first_error);
+        let b = margin(env, rhs, bindings, first_error);
+        return if *op == ConditionOp::And {
+            a.min(b)
+        } else {
+            a.max(b)
+        };
+    }
+    let mut side = |arg: &Expr| match env.eval_real(arg, bindings) {
+        Ok(val) if val.is_finite() => Some(val),
+        Ok(_) => None,
+        Err(err) => {
+            first_error.get_or_insert(err);
+            None
+        }
+    };
+    let (Some(a), Some(b)) = (side(lhs), side(rhs)) else {
+        return UNDEFINED;
+    };
+    match op {
+        ConditionOp::Less | ConditionOp::LessEqual => b - a,
+        _ => a - b,
+    }
+}
+
+/// Translucent cells clipped to the region, each corner inside and each crossing
+/// of a cell edge is a vertex of a convex polygon.
+fn plane_mesh(min: DVec2, max: DVec2, margin: &mut impl FnMut(Vec3) -> f64) -> SurfaceMesh {
+    let step = (max - min) / PLANE_CELLS as f64;
+    let point = |i: usize, j: usize| min + step * DVec2::new(i as f64, j as f64);
+    let samples: Vec<f64> = (0..=PLANE_CELLS)
+        .flat_map(|j| (0..=PLANE_CELLS).map(move |i| (i, j)))
+        .map(|(i, j)| margin(point(i, j).as_vec2().extend(0.0)))
+        .collect();
+
+    let mut mesh = SurfaceMesh::default();
+    let mut polygon = Vec::with_capacity(8);
+    for j in 0..PLANE_CELLS {
+        for i in 0..PLANE_CELLS {
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+            let values = corners.map(|(i, j)| samples[j * (PLANE_CELLS + 1) + i]);
+            let points = corners.map(|(i, j)| point(i, j));
+            polygon.clear();
+            for k in 0..4 {
+                let next = (k + 1) % 4;
+                let (a, b) = (values[k], values[next]);
+                if a >= 0.0 {
+                    polygon.push(points[k]);
+                }
+                if (a >= 0.0) != (b >= 0.0) {
+                    polygon.push(points[k] + (points[next] - points[k]) * (a / (a - b)));
+                }
+            }
+            if polygon.len() < 3 {
+                continue;
+            }
+            let first = mesh.positions.len() as u32;
+            for point in &polygon {
+                mesh.positions.push(point.as_vec2().extend(0.0));
+                mesh.normals.push(Vec3::Z);
+            }
+            for k in 1..polygon.len() as u32 - 1 {
+                mesh.indices.extend([first, first + k, first + k + 1]);
+            }
+        }
+    }
+    mesh
+}
+
+/// The boundary of the region within the box closed off by caps on its faces. The
+/// samples sit at the centres of a grid of cells and are surrounded by a layer
+/// mirroring their margins, so the surface crosses into it exactly on the faces.
+fn solid_mesh(view: (Vec3, Vec3), margin: &mut impl FnMut(Vec3) -> f64) -> SurfaceMesh {
+    let n = SPACE_RESOLUTION;
+    let step = (view.1 - view.0) / n as f32;
+    let inner: Vec<f64> = (0..n.pow(3))
+        .map(|index| {
+            let cell = Vec3::new(
+                (index % n) as f32,
+                (index / n % n) as f32,
+                (index / (n * n)) as f32,
+            );
+            margin(view.0 + step * (cell + 0.5))
+        })
+        .collect();
+
+    let dims = n + 2;
+    let mut values = Vec::with_capacity(dims.pow(3));
+    for k in 0..dims {
+        for j in 0..dims {
+            for i in 0..dims {
+                let clamp = |i: usize| i.clamp(1, n) - 1;
+                let val = inner[(clamp(k) * n + clamp(j)) * n + clamp(i)];
+                let border = [i, j, k].iter().any(|&i| i == 0 || i == dims - 1);
+                values.push(if border { -val.abs() } else { val });
+            }
+        }
+    }
+    // The margin grows inwards, turn the front faces outwards
+    let mut mesh = marching_cubes(&values, [dims; 3], view.0 - step * 0.5, step);
+    for triangle in mesh.indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+    for normal in &mut mesh.normals {
+        *normal = -*normal;
+    }
+    mesh
+}
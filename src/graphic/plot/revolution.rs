@@ -0,0 +1,216 @@
+use std::f32::consts::TAU;
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+    },
+    graphic::{
+        plot::{mesh::grid_mesh, plot_function, real_arg},
+        scene::SceneGeometry,
+    },
+};
+
+/// Samples along a curve and steps of a full turn around the axis.
+const SAMPLES: usize = 121;
+const TURNS: usize = 72;
+
+/// The line in the plane `z = 0` a curve is revolved around.
+#[derive(Debug, Clone, Copy)]
+enum Axis {
+    /// `y = c`
+    Horizontal(f32),
+    /// `x = c`
+    Vertical(f32),
+}
+
+impl Axis {
+    /// Whether `arg` names an axis, `x` or `y` for the coordinate axes or a line
+    /// `y = c` or `x = c`.
+    fn is_axis(env: &Environment, arg: &Expr) -> bool {
+        let coordinate = |expr: &Expr| {
+            matches!(&expr.kind, ExprKind::Ident(name)
+                if matches!(name.as_str(), "x" | "y") && env.get_variable(name).is_none())
+        };
+        match &arg.kind {
+            ExprKind::Equation(lhs, _) => coordinate(lhs),
+            _ => coordinate(arg),
+        }
+    }
+
+    /// `x` and `y` are the lines `y = 0` and `x = 0`.
+    fn parse(env: &Environment, arg: &Expr) -> Result<Self, CalcError> {
+        match &arg.kind {
+            ExprKind::Equation(lhs, rhs) => {
+                let offset = real_arg(env, rhs)? as f32;
+                match &lhs.kind {
+                    ExprKind::Ident(name) if name == "y" => Ok(Axis::Horizontal(offset)),
+                    _ => Ok(Axis::Vertical(offset)),
+                }
+            }
+            ExprKind::Ident(name) if name == "x" => Ok(Axis::Horizontal(0.0)),
+            _ => Ok(Axis::Vertical(0.0)),
+        }
+    }
+
+    /// `point` turned by `angle` around the axis.
+    fn revolve(self, point: Vec2, angle: f32) -> Vec3 {
+        let (sin, cos) = angle.sin_cos();
+        match self {
+            Axis::Horizontal(c) => {
+                let radius = point.y - c;
+                Vec3::new(point.x, c + radius * cos, radius * sin)
+            }
+            Axis::Vertical(c) => {
+                let radius = point.x - c;
+                Vec3::new(c + radius * cos, point.y, radius * sin)
+            }
+        }
+    }
+
+    /// From the nearest point of the axis to `point`.
+    fn offset(self, point: Vec3) -> Vec3 {
+        match self {
+            Axis::Horizontal(c) => Vec3::new(0.0, point.y - c, point.z),
+            Axis::Vertical(c) => Vec3::new(point.x - c, 0.0, point.z),
+        }
+    }
+}
+
+/// `revolve(f, a, b)` for the surface swept by `y = f(x)` over `[a, b]` and
+/// `revolve(f, g, a, b)` for the solid between two curves, whose slices are the
+/// washers or shells of a volume integral. The axis is `x` unless given last as
+/// `y` or a line `y = c` or `x = c`. The mesh sweeps around the axis as it appears
+/// and the curves stay drawn in the plane.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let (args, axis) = match args {
+        [rest @ .., last] if rest.len() >= 3 && Axis::is_axis(env, last) => {
+            (rest, Axis::parse(env, last)?)
+        }
+        _ => (args, Axis::Horizontal(0.0)),
+    };
+    let (funcs, a, b) = match args {
+        [func, a, b] => (std::slice::from_ref(func), a, b),
+        [_, _, a, b] => (&args[..2], a, b),
+        _ => {
+            return Err(CalcError::new(
+                format!("revolve takes 3 to 5 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let (a, b) = (real_arg(env, a)?, real_arg(env, b)?);
+    if a == b || !a.is_finite() || !b.is_finite() {
+        return Err(CalcError::new(
+            "revolve needs an interval with a length",
+            expr.span,
+        ));
+    }
+
+    let mut first_error = None;
+    let mut curves = Vec::with_capacity(funcs.len());
+    for func in funcs {
+        let (func, var) = plot_function(env, func, "x")?;
+        let curve: Vec<Option<Vec2>> = (0..SAMPLES)
+            .map(|i| {
+                let x = a + (b - a) * i as f64 / (SAMPLES - 1) as f64;
+                match env.eval_real(&func, &[(&var, x)]) {
+                    Ok(y) if y.is_finite() => Some(Vec2::new(x as f32, y as f32)),
+                    Ok(_) => None,
+                    Err(err) => {
+                        first_error.get_or_insert(err);
+                        None
+                    }
+                }
+            })
+            .collect();
+        curves.push(curve);
+    }
+
+    let (profile, closed) = match curves.as_slice() {
+        [curve] => (curve.clone(), false),
+        [outer, inner] => {
+            let [Some(outer_a), Some(outer_b), Some(inner_a), Some(inner_b)] =
+                [outer[0], outer[SAMPLES - 1], inner[0], inner[SAMPLES - 1]]
+            else {
+                return Err(first_error.unwrap_or_else(|| {
+                    CalcError::new("Both curves need a value at a and b", expr.span)
+                }));
+            };
+            // One loop around the region, starting halfway along the end at a so
+            // the seam lies flat. Gaps keep the corners sharp.
+            let middle = (outer_a + inner_a) / 2.0;
+            let mut profile = vec![Some(middle), Some(outer_a), None];
+            profile.extend(outer);
+            profile.extend([None, Some(outer_b), Some(inner_b), None]);
+            profile.extend(inner.iter().rev());
+            profile.extend([None, Some(inner_a), Some(middle)]);
+            (profile, true)
+        }
+        _ => unreachable!(),
+    };
+
+    let samples: Vec<Option<Vec3>> = (0..=TURNS)
+        .flat_map(|turn| {
+            let angle = TAU * turn as f32 / TURNS as f32;
+            profile
+                .iter()
+                .map(move |point| point.map(|point| axis.revolve(point, angle)))
+        })
+        .collect();
+    // Rows of the grid are steps around the axis, so the triangles are in the order of the sweep
+    let mut mesh = grid_mesh(profile.len(), TURNS + 1, &samples);
+    if mesh.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The function is undefined on the whole interval", expr.span)
+        }));
+    }
+
+    // Face away from the axis, or out of the solid by the sign of its volume
+    let outwards: f32 = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            if closed {
+                a.dot(b.cross(c))
+            } else {
+                (b - a).cross(c - a).dot(axis.offset((a + b + c) / 3.0))
+            }
+        })
+        .sum();
+    if outwards < 0.0 {
+        for triangle in mesh.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        for normal in &mut mesh.normals {
+            *normal = -*normal;
+        }
+    }
+
+    let strips = profile
+        .split(Option::is_none)
+        .map(|strip| {
+            strip
+                .iter()
+                .flatten()
+                .map(|point| point.extend(0.0))
+                .collect::<Vec<_>>()
+        })
+        .filter(|strip| strip.len() >= 2)
+        .collect();
+    Ok(vec![
+        SceneGeometry::Sweep(mesh),
+        SceneGeometry::Polyline {
+            strips,
+            closed: false,
+        },
+    ])
+}
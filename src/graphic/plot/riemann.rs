@@ -0,0 +1,418 @@
+use glam::{Vec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind},
+        error::CalcError,
+        eval::Environment,
+        numeric::integrate,
+        token::Span,
+    },
+    graphic::{
+        plot::{
+            contour::round_significant, count_arg, mesh::SurfaceMesh, plot_function,
+            plot_function_of, real_arg,
+        },
+        scene::SceneGeometry,
+    },
+};
+
+/// Subdivisions of the interval or of each side of the rectangle unless given.
+const SUBDIVISIONS: usize = 10;
+pub const MAX_SUBDIVISIONS: usize = 200;
+/// Samples of the curve drawn over the rectangles.
+const CURVE_SAMPLES: usize = 200;
+
+/// Where the height of a rectangle is taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule {
+    Left,
+    Right,
+    Midpoint,
+    Trapezoid,
+}
+
+impl Rule {
+    fn of(env: &Environment, arg: &Expr) -> Option<Self> {
+        let ExprKind::Ident(name) = &arg.kind else {
+            return None;
+        };
+        if env.get_variable(name).is_some() {
+            return None;
+        }
+        match name.as_str() {
+            "left" => Some(Rule::Left),
+            "right" => Some(Rule::Right),
+            "midpoint" => Some(Rule::Midpoint),
+            "trapezoid" => Some(Rule::Trapezoid),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Rule::Left => "Left",
+            Rule::Right => "Right",
+            Rule::Midpoint => "Midpoint",
+            Rule::Trapezoid => "Trapezoid",
+        }
+    }
+}
+
+/// The arguments of a `riemann` command with the rule taken out.
+struct RiemannArgs<'a> {
+    func: &'a Expr,
+    bounds: Vec<&'a Expr>,
+    count: Option<&'a Expr>,
+    rule: Rule,
+}
+
+fn split_args<'a>(
+    env: &Environment,
+    expr: &Expr,
+    args: &'a [Expr],
+) -> Result<RiemannArgs<'a>, CalcError> {
+    let mut rule = None;
+    let mut rest = Vec::with_capacity(args.len());
+    for (index, arg) in args.iter().enumerate() {
+        match Rule::of(env, arg) {
+            Some(found) if index >= 3 && rule.is_none() => rule = Some(found),
+            _ => rest.push(arg),
+        }
+    }
+    let (func, bounds, count) = match rest.as_slice() {
+        [func, bounds @ ..] if matches!(bounds.len(), 2 | 4) => (*func, bounds.to_vec(), None),
+        [func, bounds @ .., count] if matches!(bounds.len(), 2 | 4) => {
+            (*func, bounds.to_vec(), Some(*count))
+        }
+        _ => {
+            return Err(CalcError::new(
+                format!(
+                    "riemann takes 3, 4, 5 or 6 arguments besides the rule, got {}",
+                    rest.len()
+                ),
+                expr.span,
+            ));
+        }
+    };
+    Ok(RiemannArgs {
+        func,
+        bounds,
+        count,
+        rule: rule.unwrap_or(Rule::Midpoint),
+    })
+}
+
+/// The subdivisions of a `riemann` command for a slider, with the span of their
+/// argument or the empty span before the closing parenthesis when it is left out.
+/// `None` unless they are given as a plain number.
+pub fn subdivisions(env: &Environment, expr: &Expr) -> Option<(usize, Span)> {
+    let ExprKind::Call(name, args) = &expr.kind else {
+        return None;
+    };
+    if name != "riemann" || env.get_function(name).is_some() {
+        return None;
+    }
+    match split_args(env, expr, args).ok()?.count {
+        None => {
+            let close = expr.span.end.checked_sub(1)?;
+            Some((SUBDIVISIONS, Span::new(close, close)))
+        }
+        Some(arg) if matches!(arg.kind, ExprKind::Number(_)) => {
+            let count = count_arg(env, arg, 1, MAX_SUBDIVISIONS).ok()?;
+            Some((count, arg.span))
+        }
+        Some(_) => None,
+    }
+}
+
+/// `riemann(f, a, b[, n])` for the rectangles under `y = f(x)` and
+/// `riemann(f, x0, x1, y0, y1[, n])` for the prisms under `z = f(x, y)`, followed by
+/// `left`, `right`, `midpoint` or `trapezoid` for where their heights are taken. The
+/// sum is labelled with the integral it approximates.
+pub fn build(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let args = split_args(env, expr, args)?;
+    let bounds = args
+        .bounds
+        .iter()
+        .map(|arg| real_arg(env, arg))
+        .collect::<Result<Vec<_>, _>>()?;
+    if bounds.iter().any(|val| !val.is_finite()) || bounds.chunks(2).any(|b| b[0] == b[1]) {
+        return Err(CalcError::new(
+            "riemann needs finite bounds that differ",
+            expr.span,
+        ));
+    }
+    let count = match args.count {
+        Some(arg) => count_arg(env, arg, 1, MAX_SUBDIVISIONS)?,
+        None => SUBDIVISIONS,
+    };
+    match bounds.as_slice() {
+        [a, b] => build_interval(env, args.func, args.rule, (*a, *b), count),
+        _ => build_rectangle(env, args.func, args.rule, &bounds, count),
+    }
+}
+
+/// Rectangles or trapezoids in the plane, one translucent mesh with their outlines
+/// and the curve drawn over them.
+fn build_interval(
+    env: &Environment,
+    func: &Expr,
+    rule: Rule,
+    (a, b): (f64, f64),
+    count: usize,
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let (func, var) = plot_function(env, func, "x")?;
+    let f = |x: f64| -> Result<f64, CalcError> {
+        let y = env.eval_real(&func, &[(&var, x)])?;
+        if !y.is_finite() {
+            return Err(CalcError::new(
+                format!(
+                    "The sum needs a value at {} = {}",
+                    var,
+                    round_significant(x)
+                ),
+                func.span,
+            ));
+        }
+        Ok(y)
+    };
+
+    let width = (b - a) / count as f64;
+    let mut terms = Vec::with_capacity(count);
+    let mut mesh = SurfaceMesh::default();
+    let mut strips = Vec::with_capacity(count + 1);
+    let mut top = 0.0f64;
+    for i in 0..count {
+        let x0 = a + width * i as f64;
+        let x1 = x0 + width;
+        let (h0, h1) = match rule {
+            Rule::Trapezoid => (f(x0)?, f(x1)?),
+            _ => {
+                let x = match rule {
+                    Rule::Left => x0,
+                    Rule::Right => x1,
+                    _ => (x0 + x1) / 2.0,
+                };
+                let h = f(x)?;
+                (h, h)
+            }
+        };
+        terms.push(width * (h0 + h1) / 2.0);
+        top = top.max(h0).max(h1);
+
+        let corner = |x: f64, y: f64| Vec2::new(x as f32, y as f32);
+        let [bottom0, bottom1, top1, top0] = [
+            corner(x0, 0.0),
+            corner(x1, 0.0),
+            corner(x1, h1),
+            corner(x0, h0),
+        ];
+        if h0 * h1 < 0.0 {
+            // A trapezoid crossing the axis is two triangles meeting where its top does
+            let crossing = corner(x0 + (x1 - x0) * h0 / (h0 - h1), 0.0);
+            push_triangle(&mut mesh, [bottom0, crossing, top0]);
+            push_triangle(&mut mesh, [crossing, bottom1, top1]);
+        } else {
+            push_triangle(&mut mesh, [bottom0, bottom1, top1]);
+            push_triangle(&mut mesh, [bottom0, top1, top0]);
+        }
+        strips.push(
+            [bottom0, top0, top1, bottom1]
+                .iter()
+                .map(|point| point.extend(0.0))
+                .collect(),
+        );
+    }
+
+    // The curve may be undefined between the points the sum needs
+    let mut curve = Vec::new();
+    for i in 0..=CURVE_SAMPLES {
+        let x = a + (b - a) * i as f64 / CURVE_SAMPLES as f64;
+        match env.eval_real(&func, &[(&var, x)]) {
+            Ok(y) if y.is_finite() => curve.push(Vec3::new(x as f32, y as f32, 0.0)),
+            _ => strips.extend((curve.len() >= 2).then(|| std::mem::take(&mut curve))),
+        }
+    }
+    strips.extend((curve.len() >= 2).then_some(curve));
+
+    let integral = integrate(
+        &|x: f64| {
+            env.eval_real(&func, &[(&var, x)])
+                .map_err(|err| err.message)
+        },
+        a,
+        b,
+    );
+    let anchor = Vec3::new(((a + b) / 2.0) as f32, top as f32, 0.0);
+    Ok(vec![
+        SceneGeometry::Region { mesh, alpha: 0.35 },
+        SceneGeometry::Polyline {
+            strips,
+            closed: false,
+        },
+        SceneGeometry::Labels(vec![(anchor, label(rule, count, &terms, integral))]),
+    ])
+}
+
+/// Prisms over the cells of the rectangle as one mesh, the trapezoid rule averages
+/// the corners of a cell.
+fn build_rectangle(
+    env: &Environment,
+    func: &Expr,
+    rule: Rule,
+    bounds: &[f64],
+    count: usize,
+) -> Result<Vec<SceneGeometry>, CalcError> {
+    let func = plot_function_of(env, func, &["x", "y"])?;
+    let f = |x: f64, y: f64| -> Result<f64, CalcError> {
+        let z = env.eval_real(&func, &[("x", x), ("y", y)])?;
+        if !z.is_finite() {
+            return Err(CalcError::new(
+                format!(
+                    "The sum needs a value at ({}, {})",
+                    round_significant(x),
+                    round_significant(y)
+                ),
+                func.span,
+            ));
+        }
+        Ok(z)
+    };
+
+    let [x0, x1, y0, y1] = [bounds[0], bounds[1], bounds[2], bounds[3]];
+    let (dx, dy) = ((x1 - x0) / count as f64, (y1 - y0) / count as f64);
+    let mut terms = Vec::with_capacity(count * count);
+    let mut mesh = SurfaceMesh::default();
+    let mut top = 0.0f64;
+    for j in 0..count {
+        for i in 0..count {
+            let (xa, ya) = (x0 + dx * i as f64, y0 + dy * j as f64);
+            let (xb, yb) = (xa + dx, ya + dy);
+            let height = match rule {
+                Rule::Left => f(xa, ya)?,
+                Rule::Right => f(xb, yb)?,
+                Rule::Midpoint => f((xa + xb) / 2.0, (ya + yb) / 2.0)?,
+                Rule::Trapezoid => (f(xa, ya)? + f(xb, ya)? + f(xb, yb)? + f(xa, yb)?) / 4.0,
+            };
+            terms.push(dx * dy * height);
+            top = top.max(height);
+            let corners = [
+                Vec3::new(xa as f32, ya as f32, 0.0),
+                Vec3::new(xb as f32, yb as f32, height as f32),
+            ];
+            push_prism(
+                &mut mesh,
+                corners[0].min(corners[1]),
+                corners[0].max(corners[1]),
+            );
+        }
+    }
+
+    let integral = integrate(
+        &|x: f64| {
+            integrate(
+                &|y: f64| {
+                    env.eval_real(&func, &[("x", x), ("y", y)])
+                        .map_err(|err| err.message)
+                },
+                y0,
+                y1,
+            )
+            .map(|(val, _)| val)
+        },
+        x0,
+        x1,
+    );
+    let anchor = Vec3::new(
+        ((x0 + x1) / 2.0) as f32,
+        ((y0 + y1) / 2.0) as f32,
+        top as f32,
+    );
+    Ok(vec![
+        SceneGeometry::Surface(mesh),
+        SceneGeometry::Labels(vec![(anchor, label(rule, count, &terms, integral))]),
+    ])
+}
+
+/// The sum of the signed areas or volumes next to the integral, which is left out
+/// when it fails to converge.
+fn label(rule: Rule, count: usize, terms: &[f64], integral: Result<(f64, f64), String>) -> String {
+    let tolerance = rounding_tolerance(terms);
+    let round = |val: f64| {
+        if val.abs() <= tolerance {
+            0.0
+        } else {
+            round_significant(val)
+        }
+    };
+    let sum = terms.iter().sum();
+    let mut text = format!("{} sum of {}: {}", rule.name(), count, round(sum));
+    if let Ok((integral, _)) = integral {
+        text.push_str(&format!(", integral {}", round(integral)));
+    }
+    text
+}
+
+/// Results this close to 0 are only rounding errors next to the terms without their
+/// sign, like the sum of `sin` over a period.
+fn rounding_tolerance(terms: &[f64]) -> f64 {
+    terms.iter().map(|term| term.abs()).sum::<f64>() * 1e-10
+}
+
+/// A flat triangle facing `+z` whichever way its corners go round.
+fn push_triangle(mesh: &mut SurfaceMesh, mut corners: [Vec2; 3]) {
+    if (corners[1] - corners[0]).perp_dot(corners[2] - corners[0]) < 0.0 {
+        corners.swap(1, 2);
+    }
+    let base = mesh.positions.len() as u32;
+    for corner in corners {
+        mesh.positions.push(corner.extend(0.0));
+        mesh.normals.push(Vec3::Z);
+    }
+    mesh.indices.extend([base, base + 1, base + 2]);
+}
+
+/// A box with its own corners on each face so the edges stay sharp.
+fn push_prism(mesh: &mut SurfaceMesh, min: Vec3, max: Vec3) {
+    let [x0, y0, z0] = min.to_array();
+    let [x1, y1, z1] = max.to_array();
+    // Counter-clockwise seen from outside
+    let faces = [
+        (
+            Vec3::NEG_Z,
+            [[x0, y0, z0], [x0, y1, z0], [x1, y1, z0], [x1, y0, z0]],
+        ),
+        (
+            Vec3::Z,
+            [[x0, y0, z1], [x1, y0, z1], [x1, y1, z1], [x0, y1, z1]],
+        ),
+        (
+            Vec3::NEG_Y,
+            [[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+        ),
+        (
+            Vec3::Y,
+            [[x1, y1, z0], [x0, y1, z0], [x0, y1, z1], [x1, y1, z1]],
+        ),
+        (
+            Vec3::NEG_X,
+            [[x0, y1, z0], [x0, y0, z0], [x0, y0, z1], [x0, y1, z1]],
+        ),
+        (
+            Vec3::X,
+            [[x1, y0, z0], [x1, y1, z0], [x1, y1, z1], [x1, y0, z1]],
+        ),
+    ];
+    for (normal, corners) in faces {
+        let base = mesh.positions.len() as u32;
+        mesh.positions.extend(corners.map(Vec3::from));
+        mesh.normals.extend([normal; 4]);
+        mesh.indices
+            .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
@@ -0,0 +1,144 @@
+use glam::Vec3;
+
+use crate::{
+    calc::{ast::Expr, error::CalcError, eval::Environment, value::Value},
+    graphic::plot::field::magnitude_color,
+};
+
+/// Diameter of a point in pixels unless given, and the range sizes are kept in.
+const POINT_SIZE: f32 = 6.0;
+const MIN_POINT_SIZE: f32 = 1.0;
+const MAX_POINT_SIZE: f32 = 64.0;
+
+/// Points of a scatter plot with their diameter in pixels and their colour,
+/// `None` when they take the colour of their row.
+#[derive(Debug, Clone)]
+pub struct ScatterData {
+    pub points: Vec<Vec3>,
+    pub sizes: Vec<f32>,
+    pub colors: Option<Vec<[f32; 3]>>,
+}
+
+/// A list argument, a single number stands for every point.
+enum Column {
+    List(Vec<f64>),
+    Number(f64),
+}
+
+impl Column {
+    fn get(&self, index: usize) -> f64 {
+        match self {
+            Column::List(items) => items[index],
+            Column::Number(val) => *val,
+        }
+    }
+}
+
+/// `scatter(M)` with a point in each row of a matrix of 2 or 3 columns, or
+/// `scatter(xs, ys[, zs[, sizes[, values]]])` of lists of the same length where a
+/// number stands for every point. Sizes are diameters in pixels and values colour
+/// the points from the lowest to the highest. Points in the plane lie at `z = 0`,
+/// those with a complex or undefined coordinate are left out.
+pub fn build(env: &Environment, expr: &Expr, args: &[Expr]) -> Result<ScatterData, CalcError> {
+    let columns = match args {
+        [table] => match env.eval(table)? {
+            Value::Matrix(matrix) if matches!(matrix.cols(), 2 | 3) => (0..matrix.cols())
+                .map(|col| Column::List(matrix.column(col).iter().map(real).collect()))
+                .collect(),
+            val => {
+                return Err(CalcError::new(
+                    format!(
+                        "Expected a matrix with 2 or 3 columns, got {}",
+                        val.type_name()
+                    ),
+                    table.span,
+                ));
+            }
+        },
+        [_, _, ..] if args.len() <= 5 => args
+            .iter()
+            .map(|arg| column(env, arg))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => {
+            return Err(CalcError::new(
+                format!("scatter takes 1 to 5 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+
+    let mut count = None;
+    for (arg, col) in args.iter().zip(&columns) {
+        if let Column::List(items) = col {
+            match count {
+                Some(count) if count != items.len() => {
+                    return Err(CalcError::new(
+                        format!("Expected {} values, got {}", count, items.len()),
+                        arg.span,
+                    ));
+                }
+                _ => count = Some(items.len()),
+            }
+        }
+    }
+    let coordinate = |col: Option<&Column>, index| col.map_or(0.0, |col| col.get(index));
+    let values = columns.get(4);
+    let mut data = ScatterData {
+        points: Vec::new(),
+        sizes: Vec::new(),
+        colors: values.map(|_| Vec::new()),
+    };
+    let mut kept_values = Vec::new();
+    for index in 0..count.unwrap_or(1) {
+        let point = [0, 1, 2].map(|axis| coordinate(columns.get(axis), index));
+        let size = columns
+            .get(3)
+            .map_or(POINT_SIZE as f64, |col| col.get(index));
+        let value = coordinate(values, index);
+        if point
+            .iter()
+            .chain([&size, &value])
+            .all(|val| val.is_finite())
+        {
+            data.points.push(Vec3::from(point.map(|val| val as f32)));
+            data.sizes
+                .push((size as f32).clamp(MIN_POINT_SIZE, MAX_POINT_SIZE));
+            kept_values.push(value);
+        }
+    }
+    if data.points.is_empty() {
+        return Err(CalcError::new("No point has real coordinates", expr.span));
+    }
+    if let Some(colors) = &mut data.colors {
+        let (lowest, highest) = kept_values.iter().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(lowest, highest), val| (lowest.min(*val), highest.max(*val)),
+        );
+        let range = highest - lowest;
+        colors.extend(kept_values.iter().map(|val| {
+            let t = if range > 0.0 {
+                (val - lowest) / range
+            } else {
+                0.5
+            };
+            magnitude_color(t as f32)
+        }));
+    }
+    Ok(data)
+}
+
+fn column(env: &Environment, arg: &Expr) -> Result<Column, CalcError> {
+    match env.eval(arg)? {
+        Value::Vector(items) => Ok(Column::List(items.iter().map(real).collect())),
+        Value::Number(val) => Ok(Column::Number(val.to_f64())),
+        val => Err(CalcError::new(
+            format!("Expected a list or a number, got {}", val.type_name()),
+            arg.span,
+        )),
+    }
+}
+
+/// Entries that aren't real leave their point out.
+fn real(val: &Value) -> f64 {
+    val.as_f64().unwrap_or(f64::NAN)
+}
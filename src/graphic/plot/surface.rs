@@ -0,0 +1,163 @@
+use std::f64::consts::TAU;
+
+use glam::Vec3;
+
+use crate::{
+    calc::{ast::Expr, error::CalcError, eval::Environment, value::Value},
+    graphic::plot::{
+        count_arg,
+        mesh::{SurfaceMesh, grid_mesh},
+        plot_function_of, real_arg, vector_point,
+    },
+};
+
+/// Samples per side of the domain unless given.
+pub(super) const RESOLUTION: usize = 81;
+const MAX_RESOLUTION: usize = 400;
+/// Heights beyond this many domain widths are treated like poles and cut away.
+const HEIGHT_LIMIT: f64 = 10.0;
+
+/// `surface(f)`, `surface(f, r)` or `surface(f, x0, x1, y0, y1[, n])` for `z = f(x, y)`.
+pub fn build(env: &Environment, expr: &Expr, args: &[Expr]) -> Result<SurfaceMesh, CalcError> {
+    let (func, bounds, resolution) = explicit_domain(env, expr, args, "surface")?;
+    let func = plot_function_of(env, func, &["x", "y"])?;
+
+    let height_limit = height_limit(bounds);
+    sample_grid(expr, bounds, resolution, |x, y| {
+        Ok(env.eval_real(&func, &[("x", x), ("y", y)]).map(|z| {
+            (z.is_finite() && z.abs() <= height_limit)
+                .then(|| Vec3::new(x as f32, y as f32, z as f32))
+        }))
+    })
+}
+
+/// `parametric(r)` over `0..2π` squared or `parametric(r, u0, u1, v0, v1[, n])` for a
+/// 3-vector `r(u, v)`.
+pub fn build_parametric(
+    env: &Environment,
+    expr: &Expr,
+    args: &[Expr],
+) -> Result<SurfaceMesh, CalcError> {
+    let (func, bounds, resolution) =
+        domain_args(env, expr, args, "parametric", [0.0, TAU, 0.0, TAU])?;
+    let func = plot_function_of(env, func, &["u", "v"])?;
+
+    sample_grid(expr, bounds, resolution, |u, v| {
+        match env.eval_with(&func, &[("u", Value::from(u)), ("v", Value::from(v))]) {
+            Ok(val) => match vector_point(&val, &[3]) {
+                Some(point) => Ok(Ok(point)),
+                None => Err(CalcError::new(
+                    format!("Expected a 3-vector, got {}", val.type_name()),
+                    func.span,
+                )),
+            },
+            Err(err) => Ok(Err(err)),
+        }
+    })
+}
+
+/// The arguments of [`build`], shared with plots drawn over the same graph.
+pub(super) fn explicit_domain<'a>(
+    env: &Environment,
+    expr: &Expr,
+    args: &'a [Expr],
+    cmd: &str,
+) -> Result<(&'a Expr, [f64; 4], usize), CalcError> {
+    match args {
+        [func, radius] => {
+            let radius = real_arg(env, radius)?.abs();
+            if radius == 0.0 || !radius.is_finite() {
+                return Err(CalcError::new(
+                    format!("{cmd} needs a rectangle with an area"),
+                    expr.span,
+                ));
+            }
+            Ok((func, [-radius, radius, -radius, radius], RESOLUTION))
+        }
+        _ => domain_args(env, expr, args, cmd, [-2.0, 2.0, -2.0, 2.0]),
+    }
+}
+
+/// Values of `z = f(x, y)` beyond this are cut away.
+pub(super) fn height_limit([x0, x1, y0, y1]: [f64; 4]) -> f64 {
+    HEIGHT_LIMIT * (x1 - x0).abs().max((y1 - y0).abs())
+}
+
+/// The function, rectangle and resolution of `cmd(f)` or `cmd(f, a0, a1, b0, b1[, n])`.
+fn domain_args<'a>(
+    env: &Environment,
+    expr: &Expr,
+    args: &'a [Expr],
+    cmd: &str,
+    default: [f64; 4],
+) -> Result<(&'a Expr, [f64; 4], usize), CalcError> {
+    let (func, bounds, resolution) = match args {
+        [func] => (func, default, RESOLUTION),
+        [func, a0, a1, b0, b1, rest @ ..] if rest.len() <= 1 => (
+            func,
+            [
+                real_arg(env, a0)?,
+                real_arg(env, a1)?,
+                real_arg(env, b0)?,
+                real_arg(env, b1)?,
+            ],
+            match rest.first() {
+                Some(arg) => count_arg(env, arg, 2, MAX_RESOLUTION)?,
+                None => RESOLUTION,
+            },
+        ),
+        _ => {
+            return Err(CalcError::new(
+                format!("{cmd} takes 1, 2, 5 or 6 arguments, got {}", args.len()),
+                expr.span,
+            ));
+        }
+    };
+    let [a0, a1, b0, b1] = bounds;
+    if a0 == a1 || b0 == b1 || bounds.iter().any(|val| !val.is_finite()) {
+        return Err(CalcError::new(
+            format!("{cmd} needs a rectangle with an area"),
+            expr.span,
+        ));
+    }
+    Ok((func, bounds, resolution))
+}
+
+/// Samples `point(a, b)` over the rectangle into a mesh. The inner result is a
+/// failed evaluation which leaves a hole, the outer one aborts the plot.
+pub(super) fn sample_grid(
+    expr: &Expr,
+    [a0, a1, b0, b1]: [f64; 4],
+    resolution: usize,
+    point: impl Fn(f64, f64) -> Result<Result<Option<Vec3>, CalcError>, CalcError>,
+) -> Result<SurfaceMesh, CalcError> {
+    let mut samples = Vec::with_capacity(resolution * resolution);
+    let mut first_error = None;
+    for row in 0..resolution {
+        let b = b0 + (b1 - b0) * row as f64 / (resolution - 1) as f64;
+        for col in 0..resolution {
+            let a = a0 + (a1 - a0) * col as f64 / (resolution - 1) as f64;
+            samples.push(point(a, b)?.unwrap_or_else(|err| {
+                first_error.get_or_insert(err);
+                None
+            }));
+        }
+    }
+
+    // Rectangles given right to left or top to bottom would flip the winding
+    let mut mesh = grid_mesh(resolution, resolution, &samples);
+    if (a1 < a0) != (b1 < b0) {
+        for triangle in mesh.indices.chunks_exact_mut(3) {
+            triangle.swap(1, 2);
+        }
+        for normal in &mut mesh.normals {
+            *normal = -*normal;
+        }
+    }
+    if mesh.is_empty() {
+        return Err(first_error.unwrap_or_else(|| {
+            CalcError::new("The function is undefined on the whole domain", expr.span)
+        }));
+    }
+    Ok(mesh)
+}
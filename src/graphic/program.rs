@@ -13,6 +13,10 @@ pub enum ProgramId {
     Default,
     DrawableLine,
     DrawableArrow,
+    DrawableDomainColoring,
+    DrawableSurface,
+    DrawableVectorField,
+    DrawableScatter,
 }
 
 pub fn compile_shader_program(
@@ -121,6 +125,62 @@ impl ProgramManager {
             },
         );
 
+        programs.insert(
+            ProgramId::DrawableDomainColoring,
+            ManagedProgram::RAW {
+                vert_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/domain.vsh"
+                )),
+                frag_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/domain.fsh"
+                )),
+            },
+        );
+
+        programs.insert(
+            ProgramId::DrawableSurface,
+            ManagedProgram::RAW {
+                vert_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/surface.vsh"
+                )),
+                frag_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/surface.fsh"
+                )),
+            },
+        );
+
+        programs.insert(
+            ProgramId::DrawableVectorField,
+            ManagedProgram::RAW {
+                vert_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/field.vsh"
+                )),
+                frag_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/field.fsh"
+                )),
+            },
+        );
+
+        programs.insert(
+            ProgramId::DrawableScatter,
+            ManagedProgram::RAW {
+                vert_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/scatter.vsh"
+                )),
+                frag_shader: include_str!(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/static/shader/drawable/scatter.fsh"
+                )),
+            },
+        );
+
         Self {
             programs: Arc::new(RwLock::new(programs)),
         }
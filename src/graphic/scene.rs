@@ -0,0 +1,275 @@
+use glam::Vec3;
+
+use crate::graphic::{
+    camera::GraphicCamera,
+    drawable::{
+        arrow::DrawableArrow, domain::DrawableDomainColoring, drawable::GraphicDrawable,
+        field::DrawableVectorField, marker::DrawableMarkers, polygon::DrawablePolygon,
+        polyline::DrawablePolyline, scatter::DrawableScatter, surface::DrawableSurface,
+    },
+    plot::{
+        domain::DomainColoringData, field::VectorFieldData, mesh::SurfaceMesh, scatter::ScatterData,
+    },
+};
+
+/// Seconds a [`SceneGeometry::Sweep`] takes to appear.
+const SWEEP_DURATION: f32 = 2.5;
+
+/// CPU side geometry of a scene object, turned into a drawable once GL is available.
+#[derive(Debug, Clone)]
+pub enum SceneGeometry {
+    DomainColoring(DomainColoringData),
+    Arrow {
+        start: Vec3,
+        end: Vec3,
+    },
+    /// Roots and extrema found by the numerical methods
+    Markers(Vec<Vec3>),
+    /// A path like the trajectory of an ODE, in pieces where it is undefined
+    Polyline {
+        strips: Vec<Vec<Vec3>>,
+        closed: bool,
+    },
+    /// A translucent fill like the area under an integrated curve
+    Area {
+        outline: Vec<Vec3>,
+        alpha: f32,
+    },
+    /// A shaded triangle mesh like the graph of `f(x, y)`
+    Surface(SurfaceMesh),
+    /// A mesh that appears in the order of its triangles, like a solid of revolution
+    /// sweeping around its axis
+    Sweep(SurfaceMesh),
+    /// A translucent mesh like the region where inequalities hold
+    Region {
+        mesh: SurfaceMesh,
+        alpha: f32,
+    },
+    /// Arrows coloured by magnitude, drawn in one instanced call
+    VectorField(VectorFieldData),
+    /// Round points of data in their own size and colour, drawn in one instanced call
+    Scatter(ScatterData),
+    /// Text next to points like the levels of contour lines, painted by the UI over the scene
+    Labels(Vec<(Vec3, String)>),
+}
+
+impl SceneGeometry {
+    /// `color` is ignored by geometry that colours itself like domain colouring, labels
+    /// have no drawable.
+    fn create_drawable(
+        &self,
+        gl: &glow::Context,
+        color: [f32; 4],
+    ) -> Option<Box<dyn GraphicDrawable + Send>> {
+        let drawable: Box<dyn GraphicDrawable + Send> = match self {
+            SceneGeometry::DomainColoring(data) => {
+                let mut drawable = DrawableDomainColoring::new(gl);
+                drawable.set_image(gl, data.resolution, data.resolution, &data.pixels);
+                match &data.heights {
+                    Some(heights) => drawable.set_grid(
+                        gl,
+                        data.min,
+                        data.max,
+                        data.resolution,
+                        data.resolution,
+                        Some(heights),
+                    ),
+                    None => drawable.set_grid(gl, data.min, data.max, 2, 2, None),
+                }
+                Box::new(drawable)
+            }
+            SceneGeometry::Arrow { start, end } => {
+                let mut drawable = DrawableArrow::new(gl);
+                drawable.set_color(color);
+                drawable.set_line_width(3.0);
+                drawable.set_points(gl, *start, *end);
+                Box::new(drawable)
+            }
+            SceneGeometry::Markers(points) => {
+                let mut drawable = DrawableMarkers::new(gl);
+                drawable.set_color(color);
+                drawable.set_line_width(3.0);
+                drawable.set_points(gl, points, 0.08);
+                Box::new(drawable)
+            }
+            SceneGeometry::Polyline { strips, closed } => {
+                let mut drawable = DrawablePolyline::new(gl);
+                drawable.set_color(color);
+                drawable.set_line_width(2.0);
+                drawable.set_closed(*closed);
+                drawable.set_strips(gl, strips);
+                Box::new(drawable)
+            }
+            SceneGeometry::Area { outline, alpha } => {
+                let mut drawable = DrawablePolygon::new(gl);
+                drawable.set_color([color[0], color[1], color[2], *alpha]);
+                drawable.set_verts(gl, outline);
+                Box::new(drawable)
+            }
+            SceneGeometry::Surface(mesh) => {
+                let mut drawable = DrawableSurface::new(gl);
+                drawable.set_color(color);
+                drawable.set_mesh(gl, mesh);
+                Box::new(drawable)
+            }
+            SceneGeometry::Sweep(mesh) => {
+                let mut drawable = DrawableSurface::new(gl);
+                drawable.set_color(color);
+                drawable.set_mesh(gl, mesh);
+                drawable.set_sweep(SWEEP_DURATION);
+                Box::new(drawable)
+            }
+            SceneGeometry::Region { mesh, alpha } => {
+                let mut drawable = DrawableSurface::new(gl);
+                drawable.set_color([color[0], color[1], color[2], *alpha]);
+                drawable.set_mesh(gl, mesh);
+                Box::new(drawable)
+            }
+            SceneGeometry::VectorField(data) => {
+                let mut drawable = DrawableVectorField::new(gl);
+                drawable.set_color(color);
+                drawable.set_line_width(1.5);
+                drawable.set_arrows(gl, &data.origins, &data.vectors, &data.colors);
+                Box::new(drawable)
+            }
+            SceneGeometry::Scatter(data) => {
+                let mut drawable = DrawableScatter::new(gl);
+                drawable.set_color(color);
+                let colors = match &data.colors {
+                    Some(colors) => colors.clone(),
+                    None => vec![[color[0], color[1], color[2]]; data.points.len()],
+                };
+                drawable.set_points(gl, &data.points, &colors, &data.sizes);
+                Box::new(drawable)
+            }
+            SceneGeometry::Labels(_) => return None,
+        };
+        Some(drawable)
+    }
+}
+
+pub struct SceneObject {
+    pub id: usize,
+    pub label: String,
+    pub geometry: SceneGeometry,
+    pub color: [f32; 4],
+    pub visible: bool,
+    drawable: Option<Box<dyn GraphicDrawable + Send>>,
+    synced: bool,
+}
+
+/// Plotted objects, shared with the UI which adds and removes them between frames.
+pub struct GraphicScene {
+    objects: Vec<SceneObject>,
+    next_id: usize,
+    /// Drawables of removed objects, GL is only available while painting
+    stale: Vec<Box<dyn GraphicDrawable + Send>>,
+}
+
+impl Default for GraphicScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphicScene {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            next_id: 0,
+            stale: Vec::new(),
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        label: impl Into<String>,
+        geometry: SceneGeometry,
+        color: [f32; 4],
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.push(SceneObject {
+            id,
+            label: label.into(),
+            geometry,
+            color,
+            visible: true,
+            drawable: None,
+            synced: false,
+        });
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        if let Some(index) = self.objects.iter().position(|object| object.id == id) {
+            let object = self.objects.remove(index);
+            self.stale.extend(object.drawable);
+        }
+    }
+
+    pub fn set_visible(&mut self, id: usize, visible: bool) {
+        if let Some(object) = self.objects.iter_mut().find(|object| object.id == id) {
+            object.visible = visible;
+        }
+    }
+
+    pub fn objects(&self) -> &[SceneObject] {
+        &self.objects
+    }
+
+    /// Creates drawables for new objects and releases the removed ones.
+    pub fn sync(&mut self, gl: &glow::Context) {
+        for drawable in self.stale.drain(..) {
+            drawable.destroy(gl);
+        }
+        for object in &mut self.objects {
+            if !object.synced {
+                object.drawable = object.geometry.create_drawable(gl, object.color);
+                object.synced = true;
+            }
+        }
+    }
+
+    /// Advances the animations of drawables by `frame_time` seconds.
+    pub fn update(&mut self, frame_time: f32) {
+        for object in &mut self.objects {
+            if let Some(drawable) = &mut object.drawable {
+                drawable.update(frame_time);
+            }
+        }
+    }
+
+    /// Text of the visible label objects with their anchor and colour.
+    pub fn labels(&self) -> impl Iterator<Item = (Vec3, &str, [f32; 4])> {
+        self.objects
+            .iter()
+            .filter(|object| object.visible)
+            .filter_map(|object| match &object.geometry {
+                SceneGeometry::Labels(labels) => Some((labels, object.color)),
+                _ => None,
+            })
+            .flat_map(|(labels, color)| {
+                labels
+                    .iter()
+                    .map(move |(point, text)| (*point, text.as_str(), color))
+            })
+    }
+
+    pub fn draw(&self, gl: &glow::Context, camera: &GraphicCamera) {
+        for object in self.objects.iter().filter(|object| object.visible) {
+            if let Some(drawable) = &object.drawable {
+                drawable.draw(gl, camera);
+            }
+        }
+    }
+
+    pub fn destroy(&mut self, gl: &glow::Context) {
+        for object in &mut self.objects {
+            self.stale.extend(object.drawable.take());
+        }
+        for drawable in self.stale.drain(..) {
+            drawable.destroy(gl);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod calc;
 pub mod command;
 pub mod graphic;
 pub mod resource;
@@ -0,0 +1,607 @@
+use std::collections::HashSet;
+
+use glam::{Vec2, Vec3};
+
+use crate::{
+    calc::{
+        ast::{Expr, ExprKind, Statement},
+        eval::{Environment, Evaluated, with_stack},
+        number::NumberMode,
+        parser::{parse, parse_with},
+        table::DataTable,
+    },
+    graphic::{
+        camera::GraphicCamera,
+        grid::GridStyle,
+        plot::{
+            build_plot, depends_on_view, is_plot, numeric,
+            phase::is_flow,
+            riemann::{MAX_SUBDIVISIONS, subdivisions},
+        },
+        scene::{GraphicScene, SceneGeometry},
+    },
+    ui::{app::CalcApp, image::IMAGE_MANAGER},
+};
+
+/// Colours handed out to new rows in turn.
+const PALETTE: [[f32; 4]; 8] = [
+    [0.85, 0.2, 0.15, 1.0],
+    [0.15, 0.4, 0.85, 1.0],
+    [0.1, 0.6, 0.25, 1.0],
+    [0.95, 0.55, 0.1, 1.0],
+    [0.55, 0.25, 0.75, 1.0],
+    [0.1, 0.6, 0.65, 1.0],
+    [0.8, 0.3, 0.55, 1.0],
+    [0.45, 0.45, 0.45, 1.0],
+];
+
+/// A line of the algebra panel, an expression, assignment or plot linked to the scene
+/// objects drawn from it.
+#[derive(Debug, Clone)]
+pub struct AlgebraRow {
+    pub id: usize,
+    pub input: String,
+    pub color: [f32; 4],
+    pub visible: bool,
+    /// Result or error of the last evaluation
+    pub result: Result<Option<String>, String>,
+    objects: Vec<usize>,
+    /// What the objects were drawn from, see [`dependency_key`]
+    drawn_from: Option<String>,
+    /// Camera the objects were sampled for when they depend on it
+    drawn_view: Option<GraphicCamera>,
+    /// Points in the plane the solutions of a differential equation pass through
+    seeds: Vec<Vec2>,
+}
+
+impl AlgebraRow {
+    pub fn new(id: usize) -> Self {
+        Self {
+            id,
+            input: String::new(),
+            color: PALETTE[id % PALETTE.len()],
+            visible: true,
+            result: Ok(None),
+            objects: Vec::new(),
+            drawn_from: None,
+            drawn_view: None,
+            seeds: Vec::new(),
+        }
+    }
+
+    /// Replaces the scene objects of the row.
+    fn redraw(&mut self, scene: &mut GraphicScene, geometry: Vec<SceneGeometry>) {
+        self.clear_objects(scene);
+        for geometry in geometry {
+            let id = scene.add(self.input.trim(), geometry, self.color);
+            scene.set_visible(id, self.visible);
+            self.objects.push(id);
+        }
+    }
+
+    fn clear_objects(&mut self, scene: &mut GraphicScene) {
+        for id in self.objects.drain(..) {
+            scene.remove(id);
+        }
+        self.drawn_from = None;
+        self.drawn_view = None;
+    }
+}
+
+/// Changes to the rows made while drawing them, applied afterwards.
+enum RowAction {
+    Edited,
+    /// Row index and whether Enter moves on to the next row
+    Commit(usize, bool),
+    ToggleVisible(usize),
+    MoveUp(usize),
+    MoveDown(usize),
+    Delete(usize),
+    ClearSeeds(usize),
+}
+
+impl CalcApp {
+    pub fn draw_algebra_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.add(
+                IMAGE_MANAGER
+                    .widget(ctx, "icon/sw.png", image::ImageFormat::Png)
+                    .fit_to_exact_size(egui::Vec2::new(24.0, 24.0)),
+            );
+            ui.heading("Algebra");
+        });
+        if self.draw_number_mode_ui(ui) {
+            self.evaluate_rows(true);
+        }
+        self.draw_view_ui(ui);
+        self.draw_grid_ui(ui);
+        if self.draw_data_ui(ctx, ui) {
+            self.evaluate_rows(true);
+        }
+        // Plots depending on the view are resampled once the camera comes to rest
+        let camera = self.graphic_renderer.lock().map(|renderer| renderer.camera);
+        if camera.is_ok_and(|camera| camera != self.sampled_camera)
+            && !ctx.input(|input| {
+                input.pointer.any_down() || input.smooth_scroll_delta != egui::Vec2::ZERO
+            })
+        {
+            self.evaluate_rows(false);
+        }
+        ui.separator();
+
+        let mut actions = Vec::new();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let count = self.rows.len();
+            for index in 0..count {
+                // Keyed by id so focus follows a row when it moves
+                ui.push_id(self.rows[index].id, |ui| {
+                    self.draw_row_ui(ui, index, &mut actions)
+                });
+            }
+        });
+        // Indices stay valid when rows are moved or deleted last
+        actions.sort_by_key(|action| {
+            matches!(
+                action,
+                RowAction::MoveUp(_) | RowAction::MoveDown(_) | RowAction::Delete(_)
+            )
+        });
+        for action in actions {
+            self.apply_row_action(action);
+        }
+    }
+
+    fn draw_row_ui(&mut self, ui: &mut egui::Ui, index: usize, actions: &mut Vec<RowAction>) {
+        let count = self.rows.len();
+        let focus = self.focus_row == Some(self.rows[index].id);
+        let row = &mut self.rows[index];
+        let is_last = index + 1 == count;
+        ui.horizontal(|ui| {
+            // The swatch links the row to its objects and toggles them
+            let (rect, swatch) =
+                ui.allocate_exact_size(egui::Vec2::splat(14.0), egui::Sense::click());
+            let [r, g, b, _] = row.color.map(|channel| (channel * 255.0) as u8);
+            let color = egui::Color32::from_rgb(r, g, b);
+            if row.objects.is_empty() {
+                ui.painter().circle_stroke(
+                    rect.center(),
+                    5.0,
+                    egui::Stroke::new(1.0, egui::Color32::GRAY),
+                );
+            } else if row.visible {
+                ui.painter().circle_filled(rect.center(), 6.0, color);
+            } else {
+                ui.painter()
+                    .circle_stroke(rect.center(), 5.0, egui::Stroke::new(2.0, color));
+            }
+            if swatch
+                .on_hover_text(if row.visible { "Hide" } else { "Show" })
+                .clicked()
+            {
+                actions.push(RowAction::ToggleVisible(index));
+            }
+
+            let buttons_width = if is_last { 0.0 } else { 72.0 };
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut row.input)
+                    .hint_text("Input")
+                    .desired_width(ui.available_width() - buttons_width),
+            );
+            if focus {
+                response.request_focus();
+            }
+            if response.changed() {
+                actions.push(RowAction::Edited);
+            }
+            if response.lost_focus() {
+                let enter = ui.input(|input| input.key_pressed(egui::Key::Enter));
+                actions.push(RowAction::Commit(index, enter));
+            }
+
+            if !is_last {
+                if ui
+                    .add_enabled(index > 0, egui::Button::new("▲").small())
+                    .clicked()
+                {
+                    actions.push(RowAction::MoveUp(index));
+                }
+                if ui
+                    .add_enabled(index + 2 < count, egui::Button::new("▼").small())
+                    .clicked()
+                {
+                    actions.push(RowAction::MoveDown(index));
+                }
+                if ui.small_button("✖").on_hover_text("Delete").clicked() {
+                    actions.push(RowAction::Delete(index));
+                }
+            }
+        });
+        match &row.result {
+            Ok(Some(result)) => {
+                ui.label(egui::RichText::new(result).small().weak());
+            }
+            Ok(None) => {}
+            Err(err) => {
+                ui.label(
+                    egui::RichText::new(err)
+                        .small()
+                        .color(ui.visuals().error_fg_color),
+                );
+            }
+        }
+        // The slider writes the number of subdivisions into the input
+        if let Ok(expr) = parse(row.input.trim())
+            && let Some((mut count, span)) = subdivisions(&self.environment, &expr)
+        {
+            let slider = egui::Slider::new(&mut count, 1..=MAX_SUBDIVISIONS)
+                .logarithmic(true)
+                .text("subdivisions");
+            if ui.add(slider).changed() {
+                let offset = row.input.len() - row.input.trim_start().len();
+                let text = if span.start == span.end {
+                    format!(", {}", count)
+                } else {
+                    count.to_string()
+                };
+                row.input
+                    .replace_range(span.start + offset..span.end + offset, &text);
+                actions.push(RowAction::Commit(index, false));
+            }
+        }
+        if !row.seeds.is_empty() {
+            let text = match row.seeds.len() {
+                1 => "Clear 1 solution".to_owned(),
+                count => format!("Clear {} solutions", count),
+            };
+            if ui.small_button(text).clicked() {
+                actions.push(RowAction::ClearSeeds(index));
+            }
+        }
+        if focus {
+            self.focus_row = None;
+        }
+    }
+
+    fn apply_row_action(&mut self, action: RowAction) {
+        match action {
+            RowAction::Edited => self.evaluate_rows(false),
+            RowAction::Commit(index, enter) => {
+                self.evaluate_rows(true);
+                if enter && let Some(next) = self.rows.get(index + 1) {
+                    self.focus_row = Some(next.id);
+                }
+                if let Some(row) = self.rows.get(index)
+                    && let Ok(mut info) = self.info.lock()
+                {
+                    *info = row.result.clone();
+                }
+            }
+            RowAction::ToggleVisible(index) => {
+                let row = &mut self.rows[index];
+                row.visible = !row.visible;
+                if let Ok(mut graphic_renderer) = self.graphic_renderer.lock() {
+                    for id in &row.objects {
+                        graphic_renderer.scene.set_visible(*id, row.visible);
+                    }
+                }
+            }
+            RowAction::MoveUp(index) => {
+                self.rows.swap(index - 1, index);
+                self.evaluate_rows(true);
+            }
+            RowAction::MoveDown(index) => {
+                self.rows.swap(index, index + 1);
+                self.evaluate_rows(true);
+            }
+            RowAction::Delete(index) => {
+                let mut row = self.rows.remove(index);
+                if let Ok(mut graphic_renderer) = self.graphic_renderer.lock() {
+                    row.clear_objects(&mut graphic_renderer.scene);
+                }
+                self.evaluate_rows(true);
+            }
+            RowAction::ClearSeeds(index) => {
+                let row = &mut self.rows[index];
+                row.seeds.clear();
+                row.drawn_from = None;
+                self.evaluate_rows(true);
+            }
+        }
+    }
+
+    /// Traces a solution through `seed` for each shown differential equation.
+    pub fn add_seed(&mut self, seed: Vec2) {
+        let mut added = false;
+        for row in &mut self.rows {
+            if row.visible
+                && parse(row.input.trim()).is_ok_and(|expr| is_flow(&self.environment, &expr))
+            {
+                row.seeds.push(seed);
+                row.drawn_from = None;
+                added = true;
+            }
+        }
+        if added {
+            self.evaluate_rows(true);
+        }
+    }
+
+    /// Returns whether the number mode changed.
+    fn draw_number_mode_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mode = &mut self.environment.number_mode;
+        let previous = *mode;
+        ui.horizontal(|ui| {
+            ui.selectable_value(mode, NumberMode::Exact, "Exact");
+            let decimal_digits = match *mode {
+                NumberMode::Decimal(digits) => digits,
+                _ => 30,
+            };
+            ui.selectable_value(mode, NumberMode::Decimal(decimal_digits), "Decimal");
+            ui.selectable_value(mode, NumberMode::Float, "Float");
+            if let NumberMode::Decimal(digits) = mode {
+                ui.add(
+                    egui::DragValue::new(digits)
+                        .range(1..=1000)
+                        .suffix(" digits"),
+                );
+            }
+        });
+        *mode != previous
+    }
+
+    /// Switches between the 3D view and the 2D graphing view of the xy-plane.
+    fn draw_view_ui(&mut self, ui: &mut egui::Ui) {
+        let Ok(mut graphic_renderer) = self.graphic_renderer.lock() else {
+            return;
+        };
+        let camera = &mut graphic_renderer.camera;
+        let mut plane = camera.is_orthographic();
+        let changed = ui
+            .horizontal(|ui| {
+                ui.label("View");
+                let flat = ui.selectable_value(&mut plane, true, "2D").changed();
+                ui.selectable_value(&mut plane, false, "3D").changed() || flat
+            })
+            .inner;
+        if changed {
+            let (aspect_ratio, viewport_height) = (camera.aspect_ratio, camera.viewport_height);
+            *camera = if plane {
+                GraphicCamera::plane()
+            } else {
+                GraphicCamera::default()
+            };
+            camera.aspect_ratio = aspect_ratio;
+            camera.viewport_height = viewport_height;
+        }
+    }
+
+    fn draw_grid_ui(&mut self, ui: &mut egui::Ui) {
+        let Ok(mut graphic_renderer) = self.graphic_renderer.lock() else {
+            return;
+        };
+        let style = &mut graphic_renderer.grid.style;
+        ui.horizontal(|ui| {
+            ui.label("Grid");
+            ui.selectable_value(style, GridStyle::Cartesian, "Cartesian");
+            ui.selectable_value(style, GridStyle::Polar, "Polar");
+            ui.selectable_value(style, GridStyle::Hidden, "None");
+        });
+    }
+
+    /// Lists the imported tables and imports files dropped onto the window. Returns
+    /// whether the tables changed.
+    fn draw_data_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        for file in ctx.input(|input| input.raw.dropped_files.clone()) {
+            let Some(path) = file.path else {
+                continue;
+            };
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let table = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| DataTable::parse(&name, &text));
+            match table {
+                Ok(table) => {
+                    self.tables.retain(|other| other.name != table.name);
+                    self.tables.push(table);
+                    changed = true;
+                }
+                Err(err) => {
+                    if let Ok(mut info) = self.info.lock() {
+                        *info = Err(format!("Couldn't import {}: {}", path.display(), err));
+                    }
+                }
+            }
+        }
+
+        if self.tables.is_empty() {
+            ui.label(
+                egui::RichText::new("Drop a CSV file here to import it")
+                    .small()
+                    .weak(),
+            );
+        }
+        let mut removed = None;
+        for (index, table) in self.tables.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let rows = table.columns.first().map_or(0, |(_, values)| values.len());
+                ui.label(egui::RichText::new(&table.name).strong())
+                    .on_hover_text(format!("{} rows", rows));
+                let columns: Vec<&str> = table
+                    .columns
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                ui.label(egui::RichText::new(columns.join(", ")).small().weak());
+                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            self.tables.remove(index);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Evaluates every row in order into a fresh environment. Scene objects are only
+    /// redrawn on `commit`, sampling plots is too slow to redo on every key press.
+    pub fn evaluate_rows(&mut self, commit: bool) {
+        if self
+            .rows
+            .last()
+            .is_none_or(|row| !row.input.trim().is_empty())
+        {
+            self.rows.push(AlgebraRow::new(self.next_row_id));
+            self.next_row_id += 1;
+        }
+
+        let mut env = Environment::new();
+        env.number_mode = self.environment.number_mode;
+        for table in &self.tables {
+            table.define(&mut env);
+        }
+        let Ok(mut graphic_renderer) = self.graphic_renderer.lock() else {
+            return;
+        };
+        let camera = graphic_renderer.camera;
+        let scene = &mut graphic_renderer.scene;
+        let rows = &mut self.rows;
+        with_stack(|| {
+            for row in rows {
+                evaluate_row(&mut env, scene, &camera, row, commit);
+            }
+        });
+        self.environment = env;
+        self.sampled_camera = camera;
+    }
+}
+
+fn evaluate_row(
+    env: &mut Environment,
+    scene: &mut GraphicScene,
+    camera: &GraphicCamera,
+    row: &mut AlgebraRow,
+    commit: bool,
+) {
+    let input = row.input.trim().to_owned();
+    if input.is_empty() {
+        row.result = Ok(None);
+        row.clear_objects(scene);
+        return;
+    }
+    // Plots like `r = 1 + cos(theta)` look like assignments, so they are recognised
+    // before the input becomes a statement
+    let expr = match parse_with(&input, &|name| env.get_variable(name).is_some()) {
+        Ok(expr) => expr,
+        Err(err) => {
+            row.result = Err(err.report(&input));
+            if commit {
+                row.clear_objects(scene);
+            }
+            return;
+        }
+    };
+    let stmt = Statement::from_expr(expr.clone());
+    let key = dependency_key(env, &input, &stmt);
+    let stale_view =
+        row.drawn_from.as_ref() == Some(&key) && row.drawn_view.is_some_and(|view| view != *camera);
+    let redraw = (commit && row.drawn_from.as_ref() != Some(&key)) || stale_view;
+
+    if is_plot(env, &expr) {
+        if redraw {
+            match build_plot(env, &expr, camera, &row.seeds) {
+                Ok(geometry) => {
+                    row.redraw(scene, geometry);
+                    row.drawn_from = Some(key);
+                    row.drawn_view = depends_on_view(env, &expr).then_some(*camera);
+                    row.result = Ok(Some(format!("Plotted {}", expr)));
+                }
+                Err(err) => {
+                    row.clear_objects(scene);
+                    row.result = Err(err.report(&input));
+                }
+            }
+        } else if row.drawn_from.as_ref() != Some(&key) {
+            row.result = Ok(Some("Press Enter to plot".to_owned()));
+        }
+        return;
+    }
+
+    match env.execute(&stmt) {
+        Ok(evaluated) => {
+            row.result = Ok(Some(evaluated.to_string()));
+            if redraw {
+                row.redraw(scene, row_geometry(env, &evaluated));
+                row.drawn_from = Some(key);
+            }
+        }
+        Err(err) => {
+            row.result = Err(err.report(&input));
+            if commit {
+                row.clear_objects(scene);
+            }
+        }
+    }
+}
+
+/// 3-vectors are drawn as arrows from the origin, numerical results as their markers,
+/// paths and areas.
+fn row_geometry(env: &Environment, evaluated: &Evaluated) -> Vec<SceneGeometry> {
+    match evaluated {
+        Evaluated::Numeric(_, result) => numeric::build(env, result),
+        Evaluated::Value(val) | Evaluated::Assign(_, val) => val
+            .as_vec3()
+            .map(|[x, y, z]| SceneGeometry::Arrow {
+                start: Vec3::ZERO,
+                end: Vec3::new(x as f32, y as f32, z as f32),
+            })
+            .into_iter()
+            .collect(),
+        Evaluated::Function(_, _) => Vec::new(),
+    }
+}
+
+/// The row input with the number mode and the definitions it refers to, following
+/// user functions. Objects are redrawn when it changes.
+fn dependency_key(env: &Environment, input: &str, stmt: &Statement) -> String {
+    let expr = match stmt {
+        Statement::Expr(expr) | Statement::Assign(_, expr) => expr,
+        Statement::Function { body, .. } => body,
+    };
+    let mut key = format!("{:?}\n{}", env.number_mode, input);
+    let mut pending = referenced_names(expr);
+    let mut seen = HashSet::new();
+    while let Some(name) = pending.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        if let Some(val) = env.get_variable(&name) {
+            key.push_str(&format!("\n{} = {}", name, val));
+        }
+        if let Some(func) = env.get_function(&name) {
+            key.push_str(&format!(
+                "\n{}({}) = {}",
+                name,
+                func.params.join(", "),
+                func.body
+            ));
+            pending.extend(referenced_names(&func.body));
+        }
+    }
+    key
+}
+
+/// Variables and called function names.
+fn referenced_names(expr: &Expr) -> Vec<String> {
+    let mut names = Vec::new();
+    expr.visit(&mut |expr| match &expr.kind {
+        ExprKind::Ident(name) | ExprKind::Call(name, _) => names.push(name.clone()),
+        _ => {}
+    });
+    names
+}
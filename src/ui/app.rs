@@ -3,20 +3,34 @@ use std::sync::{Arc, Mutex};
 use glam::Vec2;
 
 use crate::{
-    graphic::graphic::{GraphicRenderer, GraphicUpdateOptions},
-    ui::image::IMAGE_MANAGER,
+    calc::{eval::Environment, table::DataTable},
+    graphic::{
+        camera::GraphicCamera,
+        graphic::{GraphicRenderer, GraphicUpdateOptions},
+    },
+    ui::algebra::AlgebraRow,
 };
 
 pub struct CalcApp {
     fps: u64,
-    graphic_renderer: Arc<Mutex<GraphicRenderer>>,
+    pub graphic_renderer: Arc<Mutex<GraphicRenderer>>,
     pub info: Arc<Mutex<Result<Option<String>, String>>>,
     info_frame_color: Option<egui::Color32>,
+    /// Definitions of the algebra rows, rebuilt whenever they are evaluated
+    pub environment: Environment,
+    pub rows: Vec<AlgebraRow>,
+    pub next_row_id: usize,
+    /// Row to focus on the next frame, after Enter moved on from the one above
+    pub focus_row: Option<usize>,
+    /// Camera the rows were last evaluated with
+    pub sampled_camera: GraphicCamera,
+    /// Imported data defined before the rows
+    pub tables: Vec<DataTable>,
 }
 
 pub fn create_ui() -> eframe::Result {
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([480.0, 320.0]),
+        viewport: egui::ViewportBuilder::default().with_inner_size([960.0, 600.0]),
         // multisampling: 1,
         depth_buffer: 24,
         // stencil_buffer: 8,
@@ -69,10 +83,17 @@ impl CalcApp {
             )),
             info: Arc::new(Mutex::new(Ok(None))),
             info_frame_color: None,
+            environment: Environment::new(),
+            rows: vec![AlgebraRow::new(0)],
+            next_row_id: 1,
+            focus_row: None,
+            sampled_camera: GraphicCamera::default(),
+            tables: Vec::new(),
         })
     }
 
-    fn draw_graphic(&self, ui: &mut egui::Ui) {
+    /// Returns the point of the plane that was clicked.
+    fn draw_graphic(&self, ui: &mut egui::Ui) -> Option<Vec2> {
         let available_size = ui.available_size();
         let desired_size = egui::Vec2::new(available_size.x, available_size.y);
         let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::all());
@@ -90,14 +111,27 @@ impl CalcApp {
                 None
             };
 
+        let scroll = if response.hovered() {
+            ui.input(|input| input.smooth_scroll_delta.y)
+        } else {
+            0.0
+        };
+        let cursor = response.hover_pos().map(|pos| {
+            let uv = (pos - rect.min) / rect.size();
+            Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0)
+        });
+
         let graphic_renderer = self.graphic_renderer.clone();
         let graphic_options = GraphicUpdateOptions {
             drag_motion: Vec2::new(drag_motion.x, drag_motion.y),
             drag_button,
+            scroll,
+            cursor,
         };
         let gl_cb = egui_glow::CallbackFn::new(move |_info, painter| {
             if let Ok(mut graphic_renderer) = graphic_renderer.lock() {
                 graphic_renderer.camera.aspect_ratio = desired_size.x / desired_size.y;
+                graphic_renderer.camera.viewport_height = desired_size.y;
                 graphic_renderer.paint(painter.gl(), graphic_options.clone());
             }
         });
@@ -106,21 +140,52 @@ impl CalcApp {
             rect,
         };
         ui.painter().add(paint_cb);
+
+        // Labels are painted as text over the scene
+        let mut clicked = None;
+        if let Ok(graphic_renderer) = self.graphic_renderer.lock() {
+            let mut camera = graphic_renderer.camera;
+            camera.aspect_ratio = desired_size.x / desired_size.y;
+            if response.clicked()
+                && let Some(cursor) = cursor
+            {
+                clicked = camera.plane_point(cursor).map(|point| point.truncate());
+            }
+            for (point, text, [r, g, b, _]) in graphic_renderer.scene.labels() {
+                let Some(ndc) = camera.project(point) else {
+                    continue;
+                };
+                if ndc.abs().max_element() > 1.0 {
+                    continue;
+                }
+                let pos = rect.min
+                    + egui::Vec2::new(
+                        (ndc.x + 1.0) / 2.0 * rect.width(),
+                        (1.0 - ndc.y) / 2.0 * rect.height(),
+                    );
+                ui.painter().text(
+                    pos,
+                    egui::Align2::LEFT_BOTTOM,
+                    text,
+                    egui::FontId::proportional(12.0),
+                    egui::Color32::from_rgb(
+                        (r * 200.0) as u8,
+                        (g * 200.0) as u8,
+                        (b * 200.0) as u8,
+                    ),
+                );
+            }
+        }
+        clicked
     }
 }
 
 impl eframe::App for CalcApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        egui::TopBottomPanel::top("top_bar_panel").show(ctx, |ui| {
-            ui.vertical_centered_justified(|ui| {
-                ui.heading("Top Pannel");
-                ui.add(
-                    IMAGE_MANAGER
-                        .widget(&ctx, "icon/sw.png", image::ImageFormat::Png)
-                        .fit_to_exact_size(egui::Vec2::new(32.0, 32.0)),
-                );
-            });
-        });
+        egui::SidePanel::left("algebra_panel")
+            .resizable(true)
+            .default_width(280.0)
+            .show(ctx, |ui| self.draw_algebra_ui(ctx, ui));
         egui::TopBottomPanel::bottom("bottom_info_bar_panel")
             .frame({
                 let mut frame = egui::Frame::new();
@@ -140,7 +205,12 @@ impl eframe::App for CalcApp {
                     .outer_margin(egui::Margin::ZERO),
             )
             .show(ctx, |ui| {
-                egui::Frame::canvas(ui.style()).show(ui, |ui| self.draw_graphic(ui));
+                let clicked = egui::Frame::canvas(ui.style())
+                    .show(ui, |ui| self.draw_graphic(ui))
+                    .inner;
+                if let Some(seed) = clicked {
+                    self.add_seed(seed);
+                }
                 ctx.request_repaint_after(std::time::Duration::from_millis(1000 / self.fps));
             });
     }
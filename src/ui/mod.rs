@@ -1,3 +1,4 @@
+pub mod algebra;
 pub mod app;
 pub mod image;
 pub mod info;